@@ -0,0 +1,545 @@
+use std::cmp::Ordering;
+use crate::command::sql::parser::expr::{BinaryOperator, Expr, PatternOperator, UnaryOperator};
+use crate::command::sql::parser::sql_statement::CreateTableStatement;
+use crate::models::dbfile::dbtable::tablepage::Record;
+use crate::models::dbfile::dbtable::{Affinity, Value};
+use crate::models::dbfile::header::TextEncoding;
+use crate::models::error::SQLCommandError;
+
+// Evaluates expressions against the rows of one table the way SQLite does
+// (https://www.sqlite.org/datatype3.html#comparison_expressions): NULL makes most operators
+// NULL, values of different storage classes compare NULL < numbers < text < blobs, and a
+// column's affinity converts the value it is compared with.
+pub struct Evaluator<'a> {
+    table: &'a CreateTableStatement,
+    text_encoding: TextEncoding     // BINARY compares text in the database encoding
+}
+
+// The collating sequences SQLite builds in (https://www.sqlite.org/datatype3.html#collation).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Collation {
+    Binary,
+    NoCase,     // folds ASCII letters only
+    RTrim       // ignores trailing spaces
+}
+
+impl Collation {
+    pub fn from_name(name: &str) -> Result<Self, SQLCommandError> {
+        match name.to_ascii_uppercase().as_str() {
+            "BINARY" => Ok(Collation::Binary),
+            "NOCASE" => Ok(Collation::NoCase),
+            "RTRIM" => Ok(Collation::RTrim),
+            _ => Err(SQLCommandError::InvalidExpression(format!("no such collation sequence: {}", name)))
+        }
+    }
+
+    // SQLite hands NOCASE and RTRIM their operands as UTF-8, whatever the database encoding.
+    pub fn compare(&self, a: &str, b: &str, text_encoding: TextEncoding) -> Ordering {
+        match self {
+            Collation::Binary => text_encoding.compare(a, b),
+            Collation::NoCase => a.bytes().map(|byte| byte.to_ascii_lowercase()).cmp(b.bytes().map(|byte| byte.to_ascii_lowercase())),
+            Collation::RTrim => a.trim_end_matches(' ').as_bytes().cmp(b.trim_end_matches(' ').as_bytes())
+        }
+    }
+}
+
+// One element of a LIKE or GLOB pattern. All but AnyRun match exactly one character.
+enum PatternElement {
+    AnyRun,                                             // % or *
+    AnyOne,                                             // _ or ?
+    Char(char),
+    Set { negated: bool, ranges: Vec<(char, char)> }    // [...], a lone character being a range of one
+}
+
+impl PatternElement {
+    fn matches(&self, c: char, ignore_case: bool) -> bool {
+        match self {
+            PatternElement::AnyRun | PatternElement::AnyOne => true,
+            PatternElement::Char(expected) if ignore_case => expected.eq_ignore_ascii_case(&c),
+            PatternElement::Char(expected) => *expected == c,
+            PatternElement::Set { negated, ranges } => ranges.iter().any(|&(first, last)| first <= c && c <= last) != *negated
+        }
+    }
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(table: &'a CreateTableStatement, text_encoding: TextEncoding) -> Self {
+        Self { table, text_encoding }
+    }
+
+    // Whether a row passes a WHERE clause. A clause that is NULL filters the row out, like false.
+    pub fn is_true(&self, expr: &Expr, record: &Record) -> Result<bool, SQLCommandError> {
+        Ok(Self::truth(&self.evaluate(expr, record)?) == Some(true))
+    }
+
+    pub fn evaluate(&self, expr: &Expr, record: &Record) -> Result<Value, SQLCommandError> {
+        match expr {
+            Expr::Column { name, .. } => self.column_value(name, record),
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Collate { operand, collation } => {
+                Collation::from_name(collation)?;
+                self.evaluate(operand, record)
+            },
+            Expr::Unary { operator, operand } => Ok(Self::unary(*operator, self.evaluate(operand, record)?)),
+            // The right side isn't needed once the left decides the outcome
+            Expr::Binary { left, operator: BinaryOperator::And, right } => {
+                let left = Self::truth(&self.evaluate(left, record)?);
+                if left == Some(false) {
+                    return Ok(Self::from_truth(left));
+                }
+                Ok(Self::from_truth(Self::and(left, Self::truth(&self.evaluate(right, record)?))))
+            },
+            Expr::Binary { left, operator: BinaryOperator::Or, right } => {
+                let left = Self::truth(&self.evaluate(left, record)?);
+                if left == Some(true) {
+                    return Ok(Self::from_truth(left));
+                }
+                Ok(Self::from_truth(Self::or(left, Self::truth(&self.evaluate(right, record)?))))
+            },
+            Expr::Binary { left, operator, right } if Self::is_comparison(*operator) => Ok(Self::from_truth(self.compare(left, *operator, right, record)?)),
+            Expr::Binary { left, operator, right } => Ok(Self::arithmetic(self.evaluate(left, record)?, *operator, self.evaluate(right, record)?)),
+            Expr::IsNull { operand, negated } => Ok(Self::from_truth(Some(self.evaluate(operand, record)?.is_null() != *negated))),
+            Expr::InList { operand, list, negated } => Ok(Self::from_truth(self.in_list(operand, list, record)?.map(|found| found != *negated))),
+            // x BETWEEN low AND high is x >= low AND x <= high
+            Expr::Between { operand, low, high, negated } => {
+                let in_range = Self::and(self.compare(operand, BinaryOperator::GreaterOrEqual, low, record)?,
+                                         self.compare(operand, BinaryOperator::LessOrEqual, high, record)?);
+                Ok(Self::from_truth(in_range.map(|in_range| in_range != *negated)))
+            },
+            Expr::Like { operand, operator, pattern, escape, negated } => {
+                let matched = self.pattern_matches(operand, *operator, pattern, escape.as_deref(), record)?;
+                Ok(Self::from_truth(matched.map(|matched| matched != *negated)))
+            },
+            Expr::Function { name, arguments, .. } => self.function(name, arguments, record)
+        }
+    }
+
+    fn column_value(&self, name: &str, record: &Record) -> Result<Value, SQLCommandError> {
+        if let Some(column_index) = self.table.column_index(name) {
+            // A record written before a column was added doesn't have a value for it, and reads as its DEFAULT
+            return Ok(record.column_values.get(column_index).or_else(|| self.table.column_defaults.get(column_index)).cloned().unwrap_or(Value::Null));
+        }
+        if self.table.is_rowid_alias(name) {
+            return Ok(Value::Integer(record.row_id));
+        }
+        Err(SQLCommandError::UnknownColumn(name.to_string()))
+    }
+
+    // A column has the affinity of its declared type and the rowid is an integer. Other
+    // expressions have none, which is not the same as the BLOB affinity of a column.
+    pub fn affinity(&self, expr: &Expr) -> Option<Affinity> {
+        match expr {
+            Expr::Column { name, .. } => match self.table.column_index(name) {
+                                            Some(column_index) => Some(self.table.column_affinity(column_index)),
+                                            None if self.table.is_rowid_alias(name) => Some(Affinity::Integer),
+                                            None => None
+                                         },
+            Expr::Collate { operand, .. } => self.affinity(operand),
+            _ => None
+        }
+    }
+
+    // A COLLATE operator on either side wins over the declared collation of a column on either side.
+    fn collation(&self, left: &Expr, right: &Expr) -> Result<Collation, SQLCommandError> {
+        let collation = Self::explicit_collation(left).or_else(|| Self::explicit_collation(right))
+                                                      .or_else(|| self.column_collation(left))
+                                                      .or_else(|| self.column_collation(right));
+        match collation {
+            Some(collation) => Collation::from_name(collation),
+            None => Ok(Collation::Binary)
+        }
+    }
+
+    // The collation an expression sorts with on its own, as an ORDER BY term.
+    pub fn ordering_collation(&self, expr: &Expr) -> Result<Collation, SQLCommandError> {
+        match Self::explicit_collation(expr).or_else(|| self.column_collation(expr)) {
+            Some(collation) => Collation::from_name(collation),
+            None => Ok(Collation::Binary)
+        }
+    }
+
+    fn explicit_collation(expr: &Expr) -> Option<&str> {
+        match expr {
+            Expr::Collate { collation, .. } => Some(collation),
+            _ => None
+        }
+    }
+
+    fn column_collation(&self, expr: &Expr) -> Option<&str> {
+        match expr {
+            Expr::Column { name, .. } => self.table.column_index(name).and_then(|column_index| self.table.column_collations[column_index].as_deref()),
+            _ => None
+        }
+    }
+
+    // When one side has a numeric affinity and the other doesn't, the other side is converted
+    // to a number if it looks like one. Otherwise TEXT affinity converts a side with no affinity
+    // at all, but not a BLOB column.
+    fn comparison_operands(&self, left: &Expr, right: &Expr, record: &Record) -> Result<(Value, Value), SQLCommandError> {
+        let (left_affinity, right_affinity) = (self.affinity(left), self.affinity(right));
+        let (left_value, right_value) = (self.evaluate(left, record)?, self.evaluate(right, record)?);
+        let is_numeric = |affinity: Option<Affinity>| affinity.is_some_and(|affinity| affinity.is_numeric());
+        let operands = match (left_affinity, right_affinity) {
+                            (left_affinity, right_affinity) if is_numeric(left_affinity) && !is_numeric(right_affinity) => (left_value, right_value.apply_affinity(Affinity::Numeric)),
+                            (left_affinity, right_affinity) if is_numeric(right_affinity) && !is_numeric(left_affinity) => (left_value.apply_affinity(Affinity::Numeric), right_value),
+                            (Some(Affinity::Text), None) => (left_value, right_value.apply_affinity(Affinity::Text)),
+                            (None, Some(Affinity::Text)) => (left_value.apply_affinity(Affinity::Text), right_value),
+                            _ => (left_value, right_value)
+                       };
+        Ok(operands)
+    }
+
+    // The outcome of `left operator right`, NULL when either side is, except for IS and IS NOT.
+    fn compare(&self, left: &Expr, operator: BinaryOperator, right: &Expr, record: &Record) -> Result<Option<bool>, SQLCommandError> {
+        let (left_value, right_value) = self.comparison_operands(left, right, record)?;
+        let collation = self.collation(left, right)?;
+        match (operator, left_value.is_null(), right_value.is_null()) {
+            (BinaryOperator::Is, true, true) | (BinaryOperator::IsNot, false, true) | (BinaryOperator::IsNot, true, false) => return Ok(Some(true)),
+            (BinaryOperator::Is, _, true) | (BinaryOperator::Is, true, _) | (BinaryOperator::IsNot, true, true) => return Ok(Some(false)),
+            (_, true, _) | (_, _, true) => return Ok(None),
+            _ => ()
+        }
+        let ordering = Self::compare_values(&left_value, &right_value, collation, self.text_encoding);
+        let outcome = match operator {
+                        BinaryOperator::Equals | BinaryOperator::Is => ordering == Ordering::Equal,
+                        BinaryOperator::NotEquals | BinaryOperator::IsNot => ordering != Ordering::Equal,
+                        BinaryOperator::Less => ordering == Ordering::Less,
+                        BinaryOperator::LessOrEqual => ordering != Ordering::Greater,
+                        BinaryOperator::Greater => ordering == Ordering::Greater,
+                        _ => ordering != Ordering::Less
+                      };
+        Ok(Some(outcome))
+    }
+
+    pub fn compare_values(left: &Value, right: &Value, collation: Collation, text_encoding: TextEncoding) -> Ordering {
+        match (left, right) {
+            (Value::Text(left), Value::Text(right)) => collation.compare(left, right, text_encoding),
+            _ => left.compare(right)
+        }
+    }
+
+    fn is_comparison(operator: BinaryOperator) -> bool {
+        matches!(operator, BinaryOperator::Equals | BinaryOperator::NotEquals | BinaryOperator::Is | BinaryOperator::IsNot |
+                           BinaryOperator::Less | BinaryOperator::LessOrEqual | BinaryOperator::Greater | BinaryOperator::GreaterOrEqual)
+    }
+
+    // True if the operand equals an item of the list, NULL if it doesn't but a NULL is involved.
+    // The list items take the affinity of the operand.
+    fn in_list(&self, operand: &Expr, list: &[Expr], record: &Record) -> Result<Option<bool>, SQLCommandError> {
+        if list.is_empty() {
+            return Ok(Some(false));
+        }
+        let value = self.evaluate(operand, record)?;
+        if value.is_null() {
+            return Ok(None);
+        }
+        let affinity = match self.affinity(operand) {
+                            Some(affinity) if affinity.is_numeric() => Some(Affinity::Numeric),
+                            affinity => affinity
+                       };
+        let mut saw_null = false;
+        for item in list {
+            let item_value = self.evaluate(item, record)?;
+            let item_value = match affinity {
+                                Some(affinity) => item_value.apply_affinity(affinity),
+                                None => item_value
+                             };
+            if item_value.is_null() {
+                saw_null = true;
+            } else if Self::compare_values(&value, &item_value, self.collation(operand, item)?, self.text_encoding) == Ordering::Equal {
+                return Ok(Some(true));
+            }
+        }
+        Ok(if saw_null { None } else { Some(false) })
+    }
+
+    fn pattern_matches(&self, operand: &Expr, operator: PatternOperator, pattern: &Expr, escape: Option<&Expr>, record: &Record) -> Result<Option<bool>, SQLCommandError> {
+        let value = self.evaluate(operand, record)?;
+        let pattern = self.evaluate(pattern, record)?;
+        let escape = match escape {
+                        Some(escape) => Some(self.evaluate(escape, record)?),
+                        None => None
+                     };
+        if value.is_null() || pattern.is_null() || escape.as_ref().is_some_and(Value::is_null) {
+            return Ok(None);
+        }
+        let escape = match escape.map(|escape| escape.to_string().chars().collect::<Vec<char>>()) {
+                        Some(escape) if escape.len() == 1 => Some(escape[0]),
+                        Some(_) => return Err(SQLCommandError::InvalidExpression(String::from("ESCAPE expression must be a single character"))),
+                        None => None
+                     };
+        let (text, pattern): (Vec<char>, Vec<char>) = (value.to_string().chars().collect(), pattern.to_string().chars().collect());
+        let matched = match operator {
+                        PatternOperator::Like => Self::like(&pattern, &text, escape),
+                        PatternOperator::Glob => Self::glob(&pattern, &text)
+                      };
+        Ok(Some(matched))
+    }
+
+    // % matches any run of characters and _ any one character. ASCII letters match either case.
+    fn like(pattern: &[char], text: &[char], escape: Option<char>) -> bool {
+        let mut elements = Vec::with_capacity(pattern.len());
+        let mut characters = pattern.iter();
+        while let Some(&c) = characters.next() {
+            elements.push(match c {
+                            _ if Some(c) == escape => match characters.next() {
+                                                        Some(&literal) => PatternElement::Char(literal),
+                                                        None => return false
+                                                      },
+                            '%' => PatternElement::AnyRun,
+                            '_' => PatternElement::AnyOne,
+                            c => PatternElement::Char(c)
+                          });
+        }
+        Self::wildcard_match(&elements, text, true)
+    }
+
+    // * matches any run of characters, ? any one character and [...] one of a set, as in
+    // [a-z] or [^0-9]. Unlike LIKE, GLOB is case sensitive.
+    fn glob(pattern: &[char], text: &[char]) -> bool {
+        let mut elements = Vec::with_capacity(pattern.len());
+        let mut position = 0;
+        while let Some(&c) = pattern.get(position) {
+            position += 1;
+            elements.push(match c {
+                            '*' => PatternElement::AnyRun,
+                            '?' => PatternElement::AnyOne,
+                            '[' => match Self::glob_set(&pattern[position..]) {
+                                        Some((set, length)) => {
+                                            position += length;
+                                            set
+                                        },
+                                        None => return false
+                                   },
+                            c => PatternElement::Char(c)
+                          });
+        }
+        Self::wildcard_match(&elements, text, false)
+    }
+
+    // The set that follows a [, and how many characters it takes up with its closing ].
+    // A ] right after the [ (or [^) is part of the set. None if the set is never closed.
+    fn glob_set(pattern: &[char]) -> Option<(PatternElement, usize)> {
+        let (negated, mut position) = if pattern.first() == Some(&'^') { (true, 1) } else { (false, 0) };
+        let set_start = position;
+        let mut ranges = Vec::new();
+        loop {
+            let first = *pattern.get(position)?;
+            if first == ']' && position > set_start {
+                return Some((PatternElement::Set { negated, ranges }, position + 1));
+            }
+            match (pattern.get(position + 1), pattern.get(position + 2)) {
+                (Some('-'), Some(&last)) if last != ']' => {
+                    ranges.push((first, last));
+                    position += 3;
+                },
+                _ => {
+                    ranges.push((first, first));
+                    position += 1;
+                }
+            }
+        }
+    }
+
+    // Every element but AnyRun takes exactly one character, so on a mismatch it is enough to go
+    // back to the last AnyRun and let it take one more character: earlier runs never need to
+    // give any back. That keeps matching at O(pattern * text) however many wildcards there are.
+    fn wildcard_match(pattern: &[PatternElement], text: &[char], ignore_case: bool) -> bool {
+        let (mut p, mut t) = (0, 0);
+        let mut last_run: Option<(usize, usize)> = None;     // pattern after the last AnyRun, text it has taken up to
+        while t < text.len() {
+            match pattern.get(p) {
+                Some(PatternElement::AnyRun) => {
+                    p += 1;
+                    last_run = Some((p, t));
+                },
+                Some(element) if element.matches(text[t], ignore_case) => {
+                    p += 1;
+                    t += 1;
+                },
+                _ => match last_run {
+                        Some((run_end, taken)) => {
+                            p = run_end;
+                            t = taken + 1;
+                            last_run = Some((run_end, t));
+                        },
+                        None => return false
+                     }
+            }
+        }
+        pattern[p..].iter().all(|element| matches!(element, PatternElement::AnyRun))
+    }
+
+    fn function(&self, name: &str, arguments: &[Expr], record: &Record) -> Result<Value, SQLCommandError> {
+        let arguments: Vec<Value> = arguments.iter()
+                                             .map(|argument| self.evaluate(argument, record))
+                                             .collect::<Result<_, _>>()?;
+        let wrong_argument_count = || SQLCommandError::InvalidExpression(format!("wrong number of arguments to function {}()", name));
+        let value = match (name.to_ascii_lowercase().as_str(), arguments.as_slice()) {
+                        ("abs", [Value::Integer(n)]) => Value::Integer(n.checked_abs().ok_or_else(|| SQLCommandError::InvalidExpression(String::from("integer overflow")))?),
+                        ("abs", [Value::Null]) => Value::Null,
+                        ("abs", [value]) => match Self::to_number(value.clone()) {
+                                                Value::Integer(n) => Value::Real(n.abs() as f64),
+                                                Value::Real(n) => Value::Real(n.abs()),
+                                                value => value
+                                            },
+                        ("coalesce", arguments) if arguments.len() >= 2 => arguments.iter().find(|argument| !argument.is_null()).cloned().unwrap_or(Value::Null),
+                        ("ifnull", [first, second]) => if first.is_null() { second.clone() } else { first.clone() },
+                        ("nullif", [first, second]) => if Self::compare_values(first, second, Collation::Binary, self.text_encoding) == Ordering::Equal { Value::Null } else { first.clone() },
+                        ("length", [Value::Null]) => Value::Null,
+                        ("length", [Value::Blob(bytes)]) => Value::Integer(bytes.len() as i64),
+                        ("length", [value]) => Value::Integer(value.to_string().chars().count() as i64),
+                        ("lower", [Value::Null]) | ("upper", [Value::Null]) => Value::Null,
+                        ("lower", [value]) => Value::Text(value.to_string().to_ascii_lowercase()),
+                        ("upper", [value]) => Value::Text(value.to_string().to_ascii_uppercase()),
+                        ("typeof", [value]) => Value::Text(String::from(match value {
+                                                                            Value::Null => "null",
+                                                                            Value::Integer(_) => "integer",
+                                                                            Value::Real(_) => "real",
+                                                                            Value::Text(_) => "text",
+                                                                            Value::Blob(_) => "blob"
+                                                                        })),
+                        ("abs" | "coalesce" | "ifnull" | "nullif" | "length" | "lower" | "upper" | "typeof", _) => return Err(wrong_argument_count()),
+                        _ => return Err(SQLCommandError::UnsupportedCommand(format!("{}()", name)))
+                    };
+        Ok(value)
+    }
+
+    fn unary(operator: UnaryOperator, value: Value) -> Value {
+        if value.is_null() {
+            return Value::Null;
+        }
+        match operator {
+            UnaryOperator::Not => Self::from_truth(Self::truth(&value).map(|truth| !truth)),
+            UnaryOperator::Plus => value,
+            UnaryOperator::BitNot => Value::Integer(!Self::to_integer(value)),
+            UnaryOperator::Negate => match Self::to_number(value) {
+                                        Value::Integer(n) => n.checked_neg().map(Value::Integer).unwrap_or(Value::Real(-(n as f64))),
+                                        Value::Real(n) => Value::Real(-n),
+                                        value => value
+                                     }
+        }
+    }
+
+    // Integer arithmetic that overflows is done with reals instead. Dividing by zero gives NULL.
+    fn arithmetic(left: Value, operator: BinaryOperator, right: Value) -> Value {
+        if left.is_null() || right.is_null() {
+            return Value::Null;
+        }
+        match operator {
+            BinaryOperator::Concatenate => return Value::Text(format!("{}{}", left, right)),
+            BinaryOperator::BitAnd => return Value::Integer(Self::to_integer(left) & Self::to_integer(right)),
+            BinaryOperator::BitOr => return Value::Integer(Self::to_integer(left) | Self::to_integer(right)),
+            BinaryOperator::ShiftLeft => return Value::Integer(Self::shift_left(Self::to_integer(left), Self::to_integer(right))),
+            BinaryOperator::ShiftRight => return Value::Integer(Self::shift_left(Self::to_integer(left), Self::to_integer(right).saturating_neg())),
+            _ => ()
+        }
+
+        let result = match (Self::to_number(left), Self::to_number(right)) {
+                        (Value::Integer(a), Value::Integer(b)) => {
+                            let result = match operator {
+                                            BinaryOperator::Add => a.checked_add(b),
+                                            BinaryOperator::Subtract => a.checked_sub(b),
+                                            BinaryOperator::Multiply => a.checked_mul(b),
+                                            BinaryOperator::Divide if b == 0 => return Value::Null,
+                                            BinaryOperator::Divide => a.checked_div(b),
+                                            BinaryOperator::Modulo if b == 0 => return Value::Null,
+                                            _ => Some(a.wrapping_rem(b))
+                                         };
+                            match result {
+                                Some(result) => return Value::Integer(result),
+                                None => Self::real_arithmetic(a as f64, operator, b as f64)
+                            }
+                        },
+                        (a, b) => Self::real_arithmetic(Self::to_real(a), operator, Self::to_real(b))
+                     };
+        match result {
+            Some(result) if !result.is_nan() => Value::Real(result),
+            _ => Value::Null
+        }
+    }
+
+    // The remainder of reals is that of their integer parts, as in SQLite.
+    fn real_arithmetic(a: f64, operator: BinaryOperator, b: f64) -> Option<f64> {
+        match operator {
+            BinaryOperator::Add => Some(a + b),
+            BinaryOperator::Subtract => Some(a - b),
+            BinaryOperator::Multiply => Some(a * b),
+            BinaryOperator::Divide if b == 0.0 => None,
+            BinaryOperator::Divide => Some(a / b),
+            _ => match (a as i64, b as i64) {
+                    (_, 0) => None,
+                    (a, b) => Some(a.wrapping_rem(b) as f64)
+                 }
+        }
+    }
+
+    // Shifting by a negative amount shifts the other way; shifting 64 or more bits out leaves
+    // nothing but the sign.
+    fn shift_left(value: i64, shift: i64) -> i64 {
+        match shift {
+            64.. => 0,
+            0..=63 => value << shift,
+            ..=-64 => if value < 0 { -1 } else { 0 },
+            _ => value >> -shift
+        }
+    }
+
+    // Text and blobs used as numbers are read up to the first character that can't be part of one.
+    fn to_number(value: Value) -> Value {
+        match value {
+            Value::Text(text) => Value::numeric_prefix(&text),
+            Value::Blob(bytes) => Value::numeric_prefix(&String::from_utf8_lossy(&bytes)),
+            value => value
+        }
+    }
+
+    fn to_integer(value: Value) -> i64 {
+        match Self::to_number(value) {
+            Value::Integer(n) => n,
+            Value::Real(n) => n as i64,
+            _ => 0
+        }
+    }
+
+    fn to_real(value: Value) -> f64 {
+        match value {
+            Value::Integer(n) => n as f64,
+            Value::Real(n) => n,
+            _ => 0.0
+        }
+    }
+
+    // NULL is neither true nor false. Any other value is true unless it is zero as a number.
+    pub fn truth(value: &Value) -> Option<bool> {
+        match Self::to_number(value.clone()) {
+            Value::Integer(n) => Some(n != 0),
+            Value::Real(n) => Some(n != 0.0),
+            _ => None
+        }
+    }
+
+    fn from_truth(truth: Option<bool>) -> Value {
+        match truth {
+            Some(truth) => Value::Integer(truth as i64),
+            None => Value::Null
+        }
+    }
+
+    // Three-valued AND and OR: false AND NULL is false, true OR NULL is true.
+    fn and(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+        match (left, right) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None
+        }
+    }
+
+    fn or(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+        match (left, right) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None
+        }
+    }
+}
@@ -1,3 +1,10 @@
 pub mod select;
-pub use select::select;
-pub mod parser;
\ No newline at end of file
+pub use select::{select, select_rows, SelectRows};
+pub mod parser;
+pub mod queryplan;
+pub use queryplan::{Constraint, QueryPlan};
+pub mod evaluator;
+pub use evaluator::Evaluator;
+pub mod sorter;
+pub use sorter::{SortKey, SortedRows, Sorter, DEFAULT_SORT_BUDGET};
+pub mod sortrun;
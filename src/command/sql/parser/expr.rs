@@ -0,0 +1,508 @@
+use std::fmt;
+use std::iter::Peekable;
+
+use crate::command::sql::parser::sql_statement::unexpected_token;
+use crate::command::sql::parser::sql_token::{is_keyword, Operator, Symbol};
+use crate::command::sql::parser::SQLToken;
+use crate::models::dbfile::dbtable::Value;
+use crate::models::error::SQLSyntaxError;
+
+// SQLite refuses expressions nested more than 1000 levels deep (SQLITE_MAX_EXPR_DEPTH). Parsing
+// and evaluating recurse once per level, so deeper ones could overflow the stack.
+pub const MAX_EXPR_DEPTH: usize = 1000;
+
+// An SQL expression, as found in a WHERE clause, a select list or an ORDER BY term.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Column { table: Option<String>, name: String },
+    Literal(Value),
+    Unary { operator: UnaryOperator, operand: Box<Expr> },
+    Binary { left: Box<Expr>, operator: BinaryOperator, right: Box<Expr> },
+    IsNull { operand: Box<Expr>, negated: bool },
+    InList { operand: Box<Expr>, list: Vec<Expr>, negated: bool },
+    Between { operand: Box<Expr>, low: Box<Expr>, high: Box<Expr>, negated: bool },
+    Like { operand: Box<Expr>, operator: PatternOperator, pattern: Box<Expr>, escape: Option<Box<Expr>>, negated: bool },
+    Function { name: String, arguments: Vec<Expr>, distinct: bool },     // count(*) has no arguments
+    Collate { operand: Box<Expr>, collation: String }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Negate,
+    Plus,
+    BitNot,
+    Not
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    Equals,
+    NotEquals,
+    Is,
+    IsNot,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    BitAnd,
+    BitOr,
+    ShiftLeft,
+    ShiftRight,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Concatenate
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternOperator {
+    Like,
+    Glob
+}
+
+// How tightly operators bind, loosest first (https://www.sqlite.org/lang_expr.html#operators).
+// IS, IN, LIKE, GLOB, BETWEEN and ISNULL bind like =.
+const OR: u8 = 1;
+const AND: u8 = 2;
+const NOT: u8 = 3;
+const EQUALITY: u8 = 4;
+const COMPARISON: u8 = 5;
+const BITWISE: u8 = 6;
+const ADDITIVE: u8 = 7;
+const MULTIPLICATIVE: u8 = 8;
+const CONCATENATE: u8 = 9;
+const COLLATE: u8 = 10;
+const UNARY: u8 = 11;
+const PRIMARY: u8 = 12;
+
+impl BinaryOperator {
+    fn from_operator(operator: Operator) -> Option<Self> {
+        match operator {
+            Operator::Equals | Operator::DoubleEquals => Some(BinaryOperator::Equals),
+            Operator::NotEquals | Operator::LessOrGreater => Some(BinaryOperator::NotEquals),
+            Operator::Less => Some(BinaryOperator::Less),
+            Operator::LessOrEqual => Some(BinaryOperator::LessOrEqual),
+            Operator::Greater => Some(BinaryOperator::Greater),
+            Operator::GreaterOrEqual => Some(BinaryOperator::GreaterOrEqual),
+            Operator::BitAnd => Some(BinaryOperator::BitAnd),
+            Operator::BitOr => Some(BinaryOperator::BitOr),
+            Operator::ShiftLeft => Some(BinaryOperator::ShiftLeft),
+            Operator::ShiftRight => Some(BinaryOperator::ShiftRight),
+            Operator::Plus => Some(BinaryOperator::Add),
+            Operator::Minus => Some(BinaryOperator::Subtract),
+            Operator::Star => Some(BinaryOperator::Multiply),
+            Operator::Slash => Some(BinaryOperator::Divide),
+            Operator::Percent => Some(BinaryOperator::Modulo),
+            Operator::Concatenate => Some(BinaryOperator::Concatenate),
+            Operator::BitNot | Operator::Arrow | Operator::DoubleArrow => None
+        }
+    }
+
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => OR,
+            BinaryOperator::And => AND,
+            BinaryOperator::Equals | BinaryOperator::NotEquals | BinaryOperator::Is | BinaryOperator::IsNot => EQUALITY,
+            BinaryOperator::Less | BinaryOperator::LessOrEqual | BinaryOperator::Greater | BinaryOperator::GreaterOrEqual => COMPARISON,
+            BinaryOperator::BitAnd | BinaryOperator::BitOr | BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => BITWISE,
+            BinaryOperator::Add | BinaryOperator::Subtract => ADDITIVE,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => MULTIPLICATIVE,
+            BinaryOperator::Concatenate => CONCATENATE
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinaryOperator::Or => "OR",
+            BinaryOperator::And => "AND",
+            BinaryOperator::Equals => "=",
+            BinaryOperator::NotEquals => "!=",
+            BinaryOperator::Is => "IS",
+            BinaryOperator::IsNot => "IS NOT",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessOrEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterOrEqual => ">=",
+            BinaryOperator::BitAnd => "&",
+            BinaryOperator::BitOr => "|",
+            BinaryOperator::ShiftLeft => "<<",
+            BinaryOperator::ShiftRight => ">>",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+            BinaryOperator::Concatenate => "||"
+        }
+    }
+
+    // The operator that gives the same result with its operands swapped, as `5 < age` is `age > 5`.
+    pub fn flipped(&self) -> Option<Self> {
+        match self {
+            BinaryOperator::Less => Some(BinaryOperator::Greater),
+            BinaryOperator::LessOrEqual => Some(BinaryOperator::GreaterOrEqual),
+            BinaryOperator::Greater => Some(BinaryOperator::Less),
+            BinaryOperator::GreaterOrEqual => Some(BinaryOperator::LessOrEqual),
+            BinaryOperator::Equals | BinaryOperator::NotEquals | BinaryOperator::Is | BinaryOperator::IsNot => Some(*self),
+            _ => None
+        }
+    }
+}
+
+impl Expr {
+    // Parses the longest expression at the front of the tokens, leaving whatever follows it.
+    pub fn from_tokens(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>) -> Result<Self, SQLSyntaxError> {
+        Self::parse(tokens_iterator, OR, 0)
+    }
+
+    // Precedence climbing: after an operand, keep taking operators that bind at least as tightly as
+    // `min_precedence`. Right operands only take operators binding tighter, so equal ones group left.
+    // `depth` counts the levels above the expression; each operator taken puts the operand before it one level further down.
+    fn parse(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>, min_precedence: u8, depth: usize) -> Result<Self, SQLSyntaxError> {
+        let mut left = Self::parse_prefix(tokens_iterator, depth)?;
+        let mut depth = depth;
+        while let Some(precedence) = tokens_iterator.peek().and_then(Self::infix_precedence) {
+            if precedence < min_precedence {
+                break;
+            }
+            depth = Self::deeper(depth)?;
+            left = Self::parse_infix(tokens_iterator, left, precedence, depth)?;
+        }
+        Ok(left)
+    }
+
+    fn deeper(depth: usize) -> Result<usize, SQLSyntaxError> {
+        if depth >= MAX_EXPR_DEPTH {
+            return Err(SQLSyntaxError::ExpressionTooDeep(MAX_EXPR_DEPTH));
+        }
+        Ok(depth + 1)
+    }
+
+    fn infix_precedence(token: &SQLToken) -> Option<u8> {
+        match token {
+            SQLToken::Operator(operator) => BinaryOperator::from_operator(*operator).map(|operator| operator.precedence()),
+            SQLToken::Keyword(word) => match word.to_ascii_uppercase().as_str() {
+                "OR" => Some(OR),
+                "AND" => Some(AND),
+                "IS" | "IN" | "LIKE" | "GLOB" | "BETWEEN" | "ISNULL" | "NOTNULL" | "NOT" => Some(EQUALITY),
+                "COLLATE" => Some(COLLATE),
+                _ => None
+            },
+            _ => None
+        }
+    }
+
+    fn parse_prefix(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>, depth: usize) -> Result<Self, SQLSyntaxError> {
+        let depth = Self::deeper(depth)?;
+        let token = tokens_iterator.next();
+        match token {
+            Some(SQLToken::IntegerLiteral(integer)) => Ok(Expr::Literal(Value::Integer(integer))),
+            Some(SQLToken::FloatLiteral(real)) => Ok(Expr::Literal(Value::Real(real))),
+            Some(SQLToken::StringLiteral(text)) => Ok(Expr::Literal(Value::Text(text))),
+            Some(SQLToken::BlobLiteral(bytes)) => Ok(Expr::Literal(Value::Blob(bytes))),
+            Some(token) if token.is_keyword("NULL") => Ok(Expr::Literal(Value::Null)),
+            // A sign in front of a number is folded into it, so `-5` is a literal like `5`
+            Some(SQLToken::Operator(Operator::Minus)) => match Self::parse_prefix(tokens_iterator, depth)? {
+                Expr::Literal(Value::Integer(integer)) if integer != i64::MIN => Ok(Expr::Literal(Value::Integer(-integer))),
+                Expr::Literal(Value::Real(real)) => Ok(Expr::Literal(Value::Real(-real))),
+                operand => Ok(Expr::Unary { operator: UnaryOperator::Negate, operand: Box::new(operand) })
+            },
+            Some(SQLToken::Operator(Operator::Plus)) => match Self::parse_prefix(tokens_iterator, depth)? {
+                literal @ Expr::Literal(Value::Integer(_) | Value::Real(_)) => Ok(literal),
+                operand => Ok(Expr::Unary { operator: UnaryOperator::Plus, operand: Box::new(operand) })
+            },
+            Some(SQLToken::Operator(Operator::BitNot)) => {
+                let operand = Self::parse_prefix(tokens_iterator, depth)?;
+                Ok(Expr::Unary { operator: UnaryOperator::BitNot, operand: Box::new(operand) })
+            },
+            Some(token) if token.is_keyword("NOT") => {
+                let operand = Self::parse(tokens_iterator, NOT, depth)?;
+                Ok(Expr::Unary { operator: UnaryOperator::Not, operand: Box::new(operand) })
+            },
+            Some(SQLToken::Symbol(Symbol::LeftParenthesis)) => {
+                let inner = Self::parse(tokens_iterator, OR, depth)?;
+                Self::expect_symbol(tokens_iterator, Symbol::RightParenthesis)?;
+                Ok(inner)
+            },
+            Some(token) if token.is_name() => {
+                let name = token.to_string();
+                if tokens_iterator.next_if_eq(&SQLToken::Symbol(Symbol::LeftParenthesis)).is_some() {
+                    return Self::parse_function_call(tokens_iterator, name, depth);
+                }
+                if tokens_iterator.next_if_eq(&SQLToken::Symbol(Symbol::Dot)).is_some() {
+                    return match tokens_iterator.next() {
+                        Some(column) if column.is_name() => Ok(Expr::Column { table: Some(name), name: column.to_string() }),
+                        token => Err(unexpected_token(token))
+                    };
+                }
+                Ok(Expr::Column { table: None, name })
+            },
+            token => Err(unexpected_token(token))
+        }
+    }
+
+    // Everything after the operand an infix or postfix operator applies to
+    fn parse_infix(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>, left: Expr, precedence: u8, depth: usize) -> Result<Self, SQLSyntaxError> {
+        let operand = Box::new(left);
+        let token = tokens_iterator.next().unwrap();
+        if let Some(operator) = Self::binary_operator(&token) {
+            let right = Self::parse(tokens_iterator, precedence + 1, depth)?;
+            return Ok(Expr::Binary { left: operand, operator, right: Box::new(right) });
+        }
+
+        if token.is_keyword("COLLATE") {
+            return match tokens_iterator.next() {
+                Some(collation) if collation.is_name() => Ok(Expr::Collate { operand, collation: collation.to_string() }),
+                token => Err(unexpected_token(token))
+            };
+        }
+        if token.is_keyword("ISNULL") || token.is_keyword("NOTNULL") {
+            return Ok(Expr::IsNull { operand, negated: token.is_keyword("NOTNULL") });
+        }
+        if token.is_keyword("IS") {
+            let negated = Self::next_keyword_is(tokens_iterator, "NOT");
+            return match Self::parse(tokens_iterator, precedence + 1, depth)? {
+                Expr::Literal(Value::Null) => Ok(Expr::IsNull { operand, negated }),
+                right => {
+                    let operator = if negated { BinaryOperator::IsNot } else { BinaryOperator::Is };
+                    Ok(Expr::Binary { left: operand, operator, right: Box::new(right) })
+                }
+            };
+        }
+
+        // NOT IN, NOT LIKE, NOT GLOB, NOT BETWEEN and NOT NULL
+        let negated = token.is_keyword("NOT");
+        let token = if negated { tokens_iterator.next() } else { Some(token) };
+        match token {
+            Some(token) if negated && token.is_keyword("NULL") => Ok(Expr::IsNull { operand, negated }),
+            Some(token) if token.is_keyword("IN") => {
+                let list = Self::parse_list(tokens_iterator, depth)?;
+                Ok(Expr::InList { operand, list, negated })
+            },
+            Some(token) if token.is_keyword("LIKE") || token.is_keyword("GLOB") => {
+                let operator = if token.is_keyword("LIKE") { PatternOperator::Like } else { PatternOperator::Glob };
+                let pattern = Box::new(Self::parse(tokens_iterator, precedence + 1, depth)?);
+                let escape = match Self::next_keyword_is(tokens_iterator, "ESCAPE") {
+                                true => Some(Box::new(Self::parse(tokens_iterator, precedence + 1, depth)?)),
+                                false => None
+                             };
+                Ok(Expr::Like { operand, operator, pattern, escape, negated })
+            },
+            // The bounds bind tighter than AND, so the AND between them isn't taken for a conjunction
+            Some(token) if token.is_keyword("BETWEEN") => {
+                let low = Box::new(Self::parse(tokens_iterator, precedence + 1, depth)?);
+                if !Self::next_keyword_is(tokens_iterator, "AND") {
+                    return Err(unexpected_token(tokens_iterator.next()));
+                }
+                let high = Box::new(Self::parse(tokens_iterator, precedence + 1, depth)?);
+                Ok(Expr::Between { operand, low, high, negated })
+            },
+            token => Err(unexpected_token(token))
+        }
+    }
+
+    fn binary_operator(token: &SQLToken) -> Option<BinaryOperator> {
+        match token {
+            SQLToken::Operator(operator) => BinaryOperator::from_operator(*operator),
+            token if token.is_keyword("AND") => Some(BinaryOperator::And),
+            token if token.is_keyword("OR") => Some(BinaryOperator::Or),
+            _ => None
+        }
+    }
+
+    // name([DISTINCT] argument, ...), name(*) or name(), with the opening parenthesis already read
+    fn parse_function_call(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>, name: String, depth: usize) -> Result<Self, SQLSyntaxError> {
+        if tokens_iterator.next_if_eq(&SQLToken::Operator(Operator::Star)).is_some() || tokens_iterator.peek() == Some(&SQLToken::Symbol(Symbol::RightParenthesis)) {
+            Self::expect_symbol(tokens_iterator, Symbol::RightParenthesis)?;
+            return Ok(Expr::Function { name, arguments: Vec::new(), distinct: false });
+        }
+        let distinct = Self::next_keyword_is(tokens_iterator, "DISTINCT");
+        let mut arguments = vec![Self::parse(tokens_iterator, OR, depth)?];
+        while tokens_iterator.next_if_eq(&SQLToken::Symbol(Symbol::Comma)).is_some() {
+            arguments.push(Self::parse(tokens_iterator, OR, depth)?);
+        }
+        Self::expect_symbol(tokens_iterator, Symbol::RightParenthesis)?;
+        Ok(Expr::Function { name, arguments, distinct })
+    }
+
+    // The parenthesised, possibly empty, list after IN
+    fn parse_list(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>, depth: usize) -> Result<Vec<Self>, SQLSyntaxError> {
+        Self::expect_symbol(tokens_iterator, Symbol::LeftParenthesis)?;
+        let mut list = Vec::new();
+        if tokens_iterator.next_if_eq(&SQLToken::Symbol(Symbol::RightParenthesis)).is_some() {
+            return Ok(list);
+        }
+        loop {
+            list.push(Self::parse(tokens_iterator, OR, depth)?);
+            match tokens_iterator.next() {
+                Some(SQLToken::Symbol(Symbol::Comma)) => continue,
+                Some(SQLToken::Symbol(Symbol::RightParenthesis)) => return Ok(list),
+                token => return Err(unexpected_token(token))
+            }
+        }
+    }
+
+    fn expect_symbol(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>, symbol: Symbol) -> Result<(), SQLSyntaxError> {
+        match tokens_iterator.next() {
+            Some(SQLToken::Symbol(next_symbol)) if next_symbol == symbol => Ok(()),
+            token => Err(unexpected_token(token))
+        }
+    }
+
+    fn next_keyword_is(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>, keyword: &str) -> bool {
+        tokens_iterator.next_if(|token| token.is_keyword(keyword)).is_some()
+    }
+
+    // The terms ANDed together at the top of the expression; the expression itself if it isn't an AND.
+    pub fn conjuncts(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Binary { left, operator: BinaryOperator::And, right } => {
+                let mut terms = left.conjuncts();
+                terms.extend(right.conjuncts());
+                terms
+            },
+            _ => vec![self]
+        }
+    }
+
+    // Every column the expression refers to, as (table, column).
+    pub fn column_references(&self) -> Vec<(Option<&str>, &str)> {
+        let mut references = Vec::new();
+        self.collect_column_references(&mut references);
+        references
+    }
+
+    fn collect_column_references<'a>(&'a self, references: &mut Vec<(Option<&'a str>, &'a str)>) {
+        match self {
+            Expr::Column { table, name } => references.push((table.as_deref(), name)),
+            Expr::Literal(_) => (),
+            Expr::Unary { operand, .. } | Expr::IsNull { operand, .. } | Expr::Collate { operand, .. } => operand.collect_column_references(references),
+            Expr::Binary { left, right, .. } => {
+                left.collect_column_references(references);
+                right.collect_column_references(references);
+            },
+            Expr::InList { operand, list, .. } => {
+                operand.collect_column_references(references);
+                list.iter().for_each(|item| item.collect_column_references(references));
+            },
+            Expr::Between { operand, low, high, .. } => {
+                for expr in [operand, low, high] {
+                    expr.collect_column_references(references);
+                }
+            },
+            Expr::Like { operand, pattern, escape, .. } => {
+                operand.collect_column_references(references);
+                pattern.collect_column_references(references);
+                if let Some(escape) = escape {
+                    escape.collect_column_references(references);
+                }
+            },
+            Expr::Function { arguments, .. } => arguments.iter().for_each(|argument| argument.collect_column_references(references))
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary { operator, .. } => operator.precedence(),
+            Expr::Unary { operator: UnaryOperator::Not, .. } => NOT,
+            Expr::Unary { .. } => UNARY,
+            Expr::IsNull { .. } | Expr::InList { .. } | Expr::Between { .. } | Expr::Like { .. } => EQUALITY,
+            Expr::Collate { .. } => COLLATE,
+            // A negative number reads like a negation
+            Expr::Literal(Value::Integer(integer)) if *integer < 0 => UNARY,
+            Expr::Literal(Value::Real(real)) if real.is_sign_negative() => UNARY,
+            Expr::Column { .. } | Expr::Literal(_) | Expr::Function { .. } => PRIMARY
+        }
+    }
+}
+
+// Writes an operand, in parentheses if it binds more loosely than `precedence`.
+struct Operand<'a>(&'a Expr, u8);
+
+impl fmt::Display for Operand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Operand(expr, precedence) = self;
+        if expr.precedence() < *precedence {
+            return write!(f, "({})", expr);
+        }
+        write!(f, "{}", expr)
+    }
+}
+
+// Names that aren't plain words, or are keywords, are written quoted.
+fn write_name(f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+    let is_plain = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || !c.is_ascii())
+                   && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$' || !c.is_ascii());
+    if is_plain && !is_keyword(name) {
+        return write!(f, "{}", name);
+    }
+    write!(f, "\"{}\"", name.replace('"', "\"\""))
+}
+
+// Expressions as SQL text, with only the parentheses the precedence rules need.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let not = |negated: &bool| if *negated { "NOT " } else { "" };
+        match self {
+            Expr::Column { table: Some(table), name } => {
+                write_name(f, table)?;
+                write!(f, ".")?;
+                write_name(f, name)
+            },
+            Expr::Column { table: None, name } => write_name(f, name),
+            Expr::Literal(Value::Text(text)) => write!(f, "{}", SQLToken::StringLiteral(text.clone())),
+            Expr::Literal(Value::Blob(bytes)) => write!(f, "{}", SQLToken::BlobLiteral(bytes.clone())),
+            Expr::Literal(Value::Real(real)) => write!(f, "{:?}", real),
+            Expr::Literal(value) => write!(f, "{}", value),
+            Expr::Unary { operator, operand } => {
+                let symbol = match operator {
+                                UnaryOperator::Negate => "-",
+                                UnaryOperator::Plus => "+",
+                                UnaryOperator::BitNot => "~",
+                                UnaryOperator::Not => "NOT "
+                             };
+                // -(-5) and not --5, which would start a comment
+                let precedence = if *operator == UnaryOperator::Not { NOT } else { PRIMARY };
+                write!(f, "{}{}", symbol, Operand(operand, precedence))
+            },
+            Expr::Binary { left, operator, right } => {
+                write!(f, "{} {} {}", Operand(left, operator.precedence()), operator.as_str(), Operand(right, operator.precedence() + 1))
+            },
+            Expr::IsNull { operand, negated } => write!(f, "{} IS {}NULL", Operand(operand, EQUALITY), not(negated)),
+            Expr::InList { operand, list, negated } => {
+                let list: Vec<String> = list.iter().map(|item| item.to_string()).collect();
+                write!(f, "{} {}IN ({})", Operand(operand, EQUALITY), not(negated), list.join(", "))
+            },
+            Expr::Between { operand, low, high, negated } => {
+                write!(f, "{} {}BETWEEN {} AND {}", Operand(operand, EQUALITY), not(negated), Operand(low, COMPARISON), Operand(high, COMPARISON))
+            },
+            Expr::Like { operand, operator, pattern, escape, negated } => {
+                let keyword = match operator {
+                                PatternOperator::Like => "LIKE",
+                                PatternOperator::Glob => "GLOB"
+                              };
+                write!(f, "{} {}{} {}", Operand(operand, EQUALITY), not(negated), keyword, Operand(pattern, COMPARISON))?;
+                match escape {
+                    Some(escape) => write!(f, " ESCAPE {}", Operand(escape, COMPARISON)),
+                    None => Ok(())
+                }
+            },
+            Expr::Function { name, arguments, distinct } => {
+                let arguments: Vec<String> = arguments.iter().map(|argument| argument.to_string()).collect();
+                let distinct = if *distinct { "DISTINCT " } else { "" };
+                if arguments.is_empty() && name.eq_ignore_ascii_case("count") {
+                    return write!(f, "{}(*)", name);
+                }
+                write!(f, "{}({}{})", name, distinct, arguments.join(", "))
+            },
+            Expr::Collate { operand, collation } => {
+                write!(f, "{} COLLATE ", Operand(operand, COLLATE))?;
+                write_name(f, collation)
+            }
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use crate::command::sql::parser::sql_token::{is_keyword, Operator, SQLToken, Symbol};
+
+// Byte offsets of a token in the SQL text; `end` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: SQLToken,
+    pub span: Span
+}
+
+// Splits SQL text into tokens the way SQLite's tokenizer does. Whitespace only separates tokens;
+// comments are returned as tokens of their own. Text no token can start with, and literals or
+// quoted identifiers that are never closed, come back as Illegal tokens for the parser to
+// reject, so lexing itself never fails.
+pub struct Lexer<'a> {
+    sql: &'a str,
+    position: usize
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(sql: &'a str) -> Self {
+        Lexer { sql, position: 0 }
+    }
+
+    fn peek_byte(&self, ahead: usize) -> Option<u8> {
+        self.sql.as_bytes().get(self.position + ahead).copied()
+    }
+
+    // Moves past bytes while `predicate` holds. Every byte of a multi-byte character is >= 0x80,
+    // so stopping on an ASCII byte always leaves the position on a character boundary.
+    fn advance_while<F: Fn(u8) -> bool>(&mut self, predicate: F) {
+        while self.peek_byte(0).is_some_and(&predicate) {
+            self.position += 1;
+        }
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
+        self.advance_while(|byte| byte.is_ascii_whitespace());
+        let start = self.position;
+        let first_byte = self.peek_byte(0)?;
+        let second_byte = self.peek_byte(1);
+
+        let kind = match first_byte {
+                        b'-' if second_byte == Some(b'-') => self.line_comment(),
+                        b'/' if second_byte == Some(b'*') => self.block_comment(),
+                        b'\'' => self.string_literal(),
+                        b'"' | b'`' => self.quoted_identifier(first_byte),
+                        b'[' => self.bracketed_identifier(),
+                        b'x' | b'X' if second_byte == Some(b'\'') => self.blob_literal(),
+                        b'0'..=b'9' => self.number(),
+                        b'.' if second_byte.is_some_and(|byte| byte.is_ascii_digit()) => self.number(),
+                        byte if is_identifier_start(byte) => self.identifier(),
+                        _ => self.punctuation()
+                   };
+        Some(Token { kind, span: Span { start, end: self.position } })
+    }
+
+    fn text_from(&self, start: usize) -> &'a str {
+        &self.sql[start..self.position]
+    }
+
+    fn line_comment(&mut self) -> SQLToken {
+        let start = self.position;
+        self.advance_while(|byte| byte != b'\n');
+        SQLToken::Comment(self.text_from(start).to_string())
+    }
+
+    // Like SQLite, a block comment left open runs to the end of the text.
+    fn block_comment(&mut self) -> SQLToken {
+        let start = self.position;
+        self.position = match self.sql[start + 2..].find("*/") {
+                            Some(offset) => start + 2 + offset + 2,
+                            None => self.sql.len()
+                        };
+        SQLToken::Comment(self.text_from(start).to_string())
+    }
+
+    // Reads up to and including the closing `quote`, where a doubled quote stands for one
+    // quote character. Returns the unescaped text between the quotes, or None if it isn't closed.
+    fn quoted(&mut self, quote: u8) -> Option<String> {
+        let start = self.position;
+        self.position += 1;
+        loop {
+            self.advance_while(|byte| byte != quote);
+            self.peek_byte(0)?;
+            if self.peek_byte(1) == Some(quote) {
+                self.position += 2;
+                continue;
+            }
+            self.position += 1;
+            let quote = (quote as char).to_string();
+            return Some(self.sql[start + 1..self.position - 1].replace(&quote.repeat(2), &quote));
+        }
+    }
+
+    fn string_literal(&mut self) -> SQLToken {
+        let start = self.position;
+        match self.quoted(b'\'') {
+            Some(text) => SQLToken::StringLiteral(text),
+            None => SQLToken::Illegal(self.text_from(start).to_string())
+        }
+    }
+
+    fn quoted_identifier(&mut self, quote: u8) -> SQLToken {
+        let start = self.position;
+        match self.quoted(quote) {
+            Some(name) => SQLToken::Identifier(name),
+            None => SQLToken::Illegal(self.text_from(start).to_string())
+        }
+    }
+
+    // [name] has no escapes: the name ends at the first ].
+    fn bracketed_identifier(&mut self) -> SQLToken {
+        let start = self.position;
+        self.advance_while(|byte| byte != b']');
+        if self.peek_byte(0).is_none() {
+            return SQLToken::Illegal(self.text_from(start).to_string());
+        }
+        self.position += 1;
+        SQLToken::Identifier(self.sql[start + 1..self.position - 1].to_string())
+    }
+
+    // X'...' needs an even number of hex digits.
+    fn blob_literal(&mut self) -> SQLToken {
+        let start = self.position;
+        self.position += 2;
+        self.advance_while(|byte| byte.is_ascii_hexdigit());
+        let digits = &self.sql[start + 2..self.position];
+        if self.peek_byte(0) != Some(b'\'') {
+            self.advance_while(|byte| byte != b'\'');
+            self.position = (self.position + 1).min(self.sql.len());
+            return SQLToken::Illegal(self.text_from(start).to_string());
+        }
+        self.position += 1;
+        if digits.len() % 2 != 0 {
+            return SQLToken::Illegal(self.text_from(start).to_string());
+        }
+        let bytes = (0..digits.len()).step_by(2)
+                                     .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).unwrap())
+                                     .collect();
+        SQLToken::BlobLiteral(bytes)
+    }
+
+    // Decimal integers, 0x hex integers and reals with an optional fraction and exponent.
+    // A number running straight into identifier characters, as in `12abc`, is illegal.
+    fn number(&mut self) -> SQLToken {
+        let start = self.position;
+        let kind = if self.peek_byte(0) == Some(b'0') && matches!(self.peek_byte(1), Some(b'x' | b'X')) && self.peek_byte(2).is_some_and(|byte| byte.is_ascii_hexdigit()) {
+                        self.position += 2;
+                        self.advance_while(|byte| byte.is_ascii_hexdigit());
+                        // Hex literals are the bits of a 64-bit two's complement integer
+                        match u64::from_str_radix(&self.sql[start + 2..self.position], 16) {
+                            Ok(bits) => SQLToken::IntegerLiteral(bits as i64),
+                            Err(_) => SQLToken::Illegal(self.text_from(start).to_string())
+                        }
+                   } else {
+                        self.decimal_number(start)
+                   };
+
+        if self.peek_byte(0).is_some_and(is_identifier_continuation) {
+            self.advance_while(is_identifier_continuation);
+            return SQLToken::Illegal(self.text_from(start).to_string());
+        }
+        kind
+    }
+
+    fn decimal_number(&mut self, start: usize) -> SQLToken {
+        let mut is_real = false;
+        self.advance_while(|byte| byte.is_ascii_digit());
+        if self.peek_byte(0) == Some(b'.') {
+            is_real = true;
+            self.position += 1;
+            self.advance_while(|byte| byte.is_ascii_digit());
+        }
+        if matches!(self.peek_byte(0), Some(b'e' | b'E')) {
+            let exponent_digits_at = if matches!(self.peek_byte(1), Some(b'+' | b'-')) { 2 } else { 1 };
+            if !self.peek_byte(exponent_digits_at).is_some_and(|byte| byte.is_ascii_digit()) {
+                self.advance_while(is_identifier_continuation);
+                return SQLToken::Illegal(self.text_from(start).to_string());
+            }
+            is_real = true;
+            self.position += exponent_digits_at;
+            self.advance_while(|byte| byte.is_ascii_digit());
+        }
+
+        let text = self.text_from(start);
+        if !is_real {
+            if let Ok(integer) = text.parse::<i64>() {
+                return SQLToken::IntegerLiteral(integer);
+            }
+        }
+        match text.parse::<f64>() {
+            Ok(real) => SQLToken::FloatLiteral(real),
+            Err(_) => SQLToken::Illegal(text.to_string())
+        }
+    }
+
+    fn identifier(&mut self) -> SQLToken {
+        let start = self.position;
+        self.advance_while(is_identifier_continuation);
+        let word = self.text_from(start);
+        if is_keyword(word) {
+            return SQLToken::Keyword(word.to_string());
+        }
+        SQLToken::Identifier(word.to_string())
+    }
+
+    fn punctuation(&mut self) -> SQLToken {
+        let next_is = |lexer: &Self, byte: u8| lexer.peek_byte(1) == Some(byte);
+        let (kind, length) = match self.peek_byte(0).unwrap() {
+                                b'(' => (SQLToken::Symbol(Symbol::LeftParenthesis), 1),
+                                b')' => (SQLToken::Symbol(Symbol::RightParenthesis), 1),
+                                b',' => (SQLToken::Symbol(Symbol::Comma), 1),
+                                b';' => (SQLToken::Symbol(Symbol::Semicolon), 1),
+                                b'.' => (SQLToken::Symbol(Symbol::Dot), 1),
+                                b'=' if next_is(self, b'=') => (SQLToken::Operator(Operator::DoubleEquals), 2),
+                                b'=' => (SQLToken::Operator(Operator::Equals), 1),
+                                b'!' if next_is(self, b'=') => (SQLToken::Operator(Operator::NotEquals), 2),
+                                b'<' if next_is(self, b'=') => (SQLToken::Operator(Operator::LessOrEqual), 2),
+                                b'<' if next_is(self, b'>') => (SQLToken::Operator(Operator::LessOrGreater), 2),
+                                b'<' if next_is(self, b'<') => (SQLToken::Operator(Operator::ShiftLeft), 2),
+                                b'<' => (SQLToken::Operator(Operator::Less), 1),
+                                b'>' if next_is(self, b'=') => (SQLToken::Operator(Operator::GreaterOrEqual), 2),
+                                b'>' if next_is(self, b'>') => (SQLToken::Operator(Operator::ShiftRight), 2),
+                                b'>' => (SQLToken::Operator(Operator::Greater), 1),
+                                b'-' if next_is(self, b'>') && self.peek_byte(2) == Some(b'>') => (SQLToken::Operator(Operator::DoubleArrow), 3),
+                                b'-' if next_is(self, b'>') => (SQLToken::Operator(Operator::Arrow), 2),
+                                b'-' => (SQLToken::Operator(Operator::Minus), 1),
+                                b'|' if next_is(self, b'|') => (SQLToken::Operator(Operator::Concatenate), 2),
+                                b'|' => (SQLToken::Operator(Operator::BitOr), 1),
+                                b'+' => (SQLToken::Operator(Operator::Plus), 1),
+                                b'*' => (SQLToken::Operator(Operator::Star), 1),
+                                b'/' => (SQLToken::Operator(Operator::Slash), 1),
+                                b'%' => (SQLToken::Operator(Operator::Percent), 1),
+                                b'&' => (SQLToken::Operator(Operator::BitAnd), 1),
+                                b'~' => (SQLToken::Operator(Operator::BitNot), 1),
+                                // Any other ASCII character, including a lone !
+                                byte => (SQLToken::Illegal((byte as char).to_string()), 1)
+                            };
+        self.position += length;
+        kind
+    }
+}
+
+// Letters, _ and anything outside ASCII may start an identifier; digits and $ may follow.
+fn is_identifier_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte >= 0x80
+}
+
+fn is_identifier_continuation(byte: u8) -> bool {
+    is_identifier_start(byte) || byte.is_ascii_digit() || byte == b'$'
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token()
+    }
+}
@@ -1,4 +1,6 @@
 pub mod sql_statement;
+pub mod lexer;
+pub mod expr;
 
 pub mod sql_token;
-pub use sql_token::SQLToken;
\ No newline at end of file
+pub use sql_token::SQLToken;
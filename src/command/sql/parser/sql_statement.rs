@@ -1,64 +1,228 @@
 
 use std::iter::Peekable;
 
-use crate::{command::sql::parser::{sql_token::{Symbol, Tokenize}, SQLToken}, models::error::SQLSyntaxError};
+use crate::{command::sql::parser::{sql_token::{Operator, Symbol, Tokenize}, SQLToken}, models::error::SQLSyntaxError};
+use crate::command::sql::parser::expr::Expr;
+use crate::models::dbfile::dbtable::{Affinity, Value};
+
+pub(crate) fn unexpected_token(token: Option<SQLToken>) -> SQLSyntaxError {
+    match token {
+        Some(token) => SQLSyntaxError::UnexpectedToken(format!("{:?}", token)),
+        None => SQLSyntaxError::UnexpectedToken(String::from("end of statement"))
+    }
+}
+
+// The name of a table, column or index, which may be a keyword SQLite allows as a name.
+fn extract_name(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>) -> Result<String, SQLSyntaxError> {
+    match tokens_iterator.next() {
+        Some(token) if token.is_name() => Ok(token.to_string()),
+        token => Err(unexpected_token(token))
+    }
+}
 
 pub enum SQLStatement {
     Select(SelectStatement),
     CreateTable(CreateTableStatement),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CreateTableStatement {
     pub table_name: String,
     pub columns: Vec<String>,
-    pub integer_primary_key_column: Option<usize>
+    pub integer_primary_key_column: Option<usize>,
+    pub primary_key: Vec<IndexedColumn>,
+    pub without_rowid: bool,    // rows live in an index b-tree keyed by the primary key, with no rowid
+    pub column_collations: Vec<Option<String>>,     // COLLATE clause of each column definition, if any
+    pub column_types: Vec<Option<String>>,          // declared type of each column, if any
+    pub column_defaults: Vec<Value>                 // DEFAULT value of each column, NULL if none
 }
 
 impl CreateTableStatement {
     
-    pub fn from_tokens(tokens: Vec<SQLToken>) -> Self {
+    // CREATE TABLE [IF NOT EXISTS] name (column definitions [, table constraints]) [WITHOUT ROWID]
+    pub fn from_tokens(tokens: Vec<SQLToken>) -> Result<Self, SQLSyntaxError> {
         
         let mut tokens_cursor = tokens.into_iter().peekable();
 
-        if let Some(SQLToken::Identifier(second_word)) = tokens_cursor.nth(1) { assert!(second_word == "TABLE"); } 
-        else { panic!(); }
-
-        let mut table_name = String::new();
-        if let Some(SQLToken::Identifier(third_word)) = tokens_cursor.next() { table_name = third_word }
-        else { panic!(); }
+        match tokens_cursor.nth(1) {
+            Some(token) if token.is_keyword("TABLE") => (),
+            token => return Err(unexpected_token(token))
+        }
+        if CreateIndexStatement::next_word_is(&mut tokens_cursor, "IF") && !(CreateIndexStatement::next_word_is(&mut tokens_cursor, "NOT") && CreateIndexStatement::next_word_is(&mut tokens_cursor, "EXISTS")) {
+            return Err(unexpected_token(tokens_cursor.next()));
+        }
 
-        let columns_defintions = Self::extract_column_definitions(&mut tokens_cursor);
-        let integer_primary_key_column = columns_defintions.iter().position(|column_definition| {column_definition.len() >= 4 && (column_definition[1].to_uppercase() == "INTEGER" && column_definition[2].to_uppercase() == "PRIMARY" && column_definition[3].to_uppercase() == "KEY") } );
-        let mut columns: Vec<String> = columns_defintions.iter()
+        let table_name = extract_name(&mut tokens_cursor)?;
+
+        let definitions = Self::extract_column_definitions(&mut tokens_cursor)?;
+        // ROWID is not a keyword, so it comes through as an identifier
+        let without_rowid = CreateIndexStatement::next_word_is(&mut tokens_cursor, "WITHOUT")
+                            && tokens_cursor.next_if(|token| matches!(token, SQLToken::Identifier(word) if word.eq_ignore_ascii_case("ROWID"))).is_some();
+
+        // Table constraints follow the column definitions and start with one of these keywords.
+        // A column may only have such a name when it is quoted, which makes it an identifier.
+        let (constraints, columns_defintions): (Vec<Vec<SQLToken>>, Vec<Vec<SQLToken>>) = definitions.into_iter().partition(|definition| {
+            ["PRIMARY", "UNIQUE", "CHECK", "FOREIGN", "CONSTRAINT"].iter().any(|word| definition[0].is_keyword(word))
+        });
+        let to_components = |definitions: Vec<Vec<SQLToken>>| -> Vec<Vec<String>> {
+            definitions.into_iter()
+                       .map(|definition| definition.iter().map(SQLToken::to_string).collect())
+                       .collect()
+        };
+        let column_types: Vec<Option<String>> = columns_defintions.iter()
+                                        .map(|column_defintion| Self::declared_type(column_defintion))
+                                        .collect();
+        // A DEFAULT is stored with its column's affinity, so `n INTEGER DEFAULT '12'` reads as 12
+        let column_defaults: Vec<Value> = columns_defintions.iter()
+                                        .zip(column_types.iter())
+                                        .map(|(column_defintion, column_type)| Self::default_value(column_defintion).apply_affinity(Affinity::from_declared_type(column_type.as_deref())))
+                                        .collect();
+        let (constraints, columns_defintions) = (to_components(constraints), to_components(columns_defintions));
+        let columns: Vec<String> = columns_defintions.iter()
                                         .map(|column_defintion| column_defintion[0].clone())
                                         .collect();
-        
+        let column_collations: Vec<Option<String>> = columns_defintions.iter()
+                                        .map(|column_defintion| CreateIndexStatement::collation(column_defintion))
+                                        .collect();
 
-        Self { table_name, columns, integer_primary_key_column }
+        let mut primary_key: Vec<IndexedColumn> = Vec::new();
+        for (column_defintion, collation) in columns_defintions.iter().zip(column_collations.iter()) {
+            if let Some(key_position) = Self::primary_key_position(column_defintion) {
+                let descending = column_defintion.get(key_position + 1).is_some_and(|order| order.eq_ignore_ascii_case("DESC"));
+                primary_key.push(IndexedColumn { name: column_defintion[0].clone(), descending, collation: collation.clone() });
+            }
+        }
+        for constraint in constraints.iter() {
+            if let Some(key_position) = Self::primary_key_position(constraint) {
+                primary_key = Self::extract_primary_key_columns(&constraint[key_position + 1..])?;
+            }
+        }
+
+        // Only a rowid table has a rowid for an INTEGER PRIMARY KEY column to stand in for
+        let integer_primary_key_column = match primary_key.as_slice() {
+                                            [key_column] if !without_rowid && !key_column.descending => {
+                                                let column_index = columns.iter().position(|column| column.eq_ignore_ascii_case(&key_column.name));
+                                                column_index.filter(|&column_index| columns_defintions[column_index].get(1).is_some_and(|column_type| column_type.eq_ignore_ascii_case("INTEGER")))
+                                            },
+                                            _ => None
+                                         };
+
+        Ok(Self { table_name, columns, integer_primary_key_column, primary_key, without_rowid, column_collations, column_types, column_defaults })
     }
 
-    fn extract_column_definitions(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>) -> Vec<Vec<String>> {
-        let mut column_definitions: Vec<Vec<String>> = Vec::new();
-        if let Some(SQLToken::Symbol(Symbol::LeftParenthesis)) = tokens_iterator.next() {
+    // Rows written before an ALTER TABLE ADD COLUMN don't store the added column, which reads as its DEFAULT.
+    // ADD COLUMN only allows a literal there: a signed number, a string or blob, NULL, TRUE or FALSE, or a
+    // name, which stands for its own text.
+    fn default_value(column_defintion: &[SQLToken]) -> Value {
+        let mut tokens = column_defintion.iter().skip(1).skip_while(|token| !token.is_keyword("DEFAULT")).skip(1);
+        match tokens.next() {
+            Some(SQLToken::StringLiteral(text)) => Value::Text(text.clone()),
+            Some(SQLToken::BlobLiteral(bytes)) => Value::Blob(bytes.clone()),
+            Some(SQLToken::IntegerLiteral(integer)) => Value::Integer(*integer),
+            Some(SQLToken::FloatLiteral(float)) => Value::Real(*float),
+            Some(SQLToken::Operator(sign @ (Operator::Minus | Operator::Plus))) => {
+                let negate = *sign == Operator::Minus;
+                match tokens.next() {
+                    Some(SQLToken::IntegerLiteral(integer)) => Value::Integer(if negate { -integer } else { *integer }),
+                    Some(SQLToken::FloatLiteral(float)) => Value::Real(if negate { -float } else { *float }),
+                    _ => Value::Null
+                }
+            },
+            Some(SQLToken::Identifier(name)) if name.eq_ignore_ascii_case("TRUE") => Value::Integer(1),
+            Some(SQLToken::Identifier(name)) if name.eq_ignore_ascii_case("FALSE") => Value::Integer(0),
+            Some(token) if token.is_name() => Value::Text(token.to_string()),
+            _ => Value::Null
+        }
+    }
 
-            while !matches!(tokens_iterator.peek(), Some(&SQLToken::Symbol(Symbol::RightParenthesis))) & !matches!(tokens_iterator.peek(), None){
-                let mut column_defintion_components: Vec<String> = Vec::new();
+    // The words after the column name up to its first constraint, as in `UNSIGNED BIG INT`.
+    // Sizes such as the `(10, 2)` of `DECIMAL(10, 2)` don't change the affinity and are left out.
+    fn declared_type(column_defintion: &[SQLToken]) -> Option<String> {
+        let mut words: Vec<String> = Vec::new();
+        let mut depth = 0;
+        for token in column_defintion.iter().skip(1) {
+            match token {
+                SQLToken::Symbol(Symbol::LeftParenthesis) => depth += 1,
+                SQLToken::Symbol(Symbol::RightParenthesis) => depth -= 1,
+                _ if depth > 0 => (),
+                token if ["CONSTRAINT", "PRIMARY", "NOT", "NULL", "UNIQUE", "CHECK", "DEFAULT", "COLLATE", "REFERENCES", "GENERATED", "AS"].iter().any(|word| token.is_keyword(word)) => break,
+                token => words.push(token.to_string())
+            }
+        }
+        if words.is_empty() {
+            return None;
+        }
+        Some(words.join(" "))
+    }
+
+    // The affinity a column's values are compared and converted with.
+    pub fn column_affinity(&self, column_index: usize) -> Affinity {
+        Affinity::from_declared_type(self.column_types[column_index].as_deref())
+    }
+
+    // Names that refer to the rowid unless the table declares a column with that name.
+    pub const ROWID_ALIASES: [&'static str; 3] = ["rowid", "oid", "_rowid_"];
+
+    pub fn is_rowid_alias(&self, column_name: &str) -> bool {
+        !self.without_rowid && self.column_index(column_name).is_none() && Self::ROWID_ALIASES.iter().any(|alias| alias.eq_ignore_ascii_case(column_name))
+    }
+
+    // Position of a column in the table. Column names are case-insensitive in SQL.
+    pub fn column_index(&self, column_name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column.eq_ignore_ascii_case(column_name))
+    }
+
+    fn primary_key_position(definition: &[String]) -> Option<usize> {
+        definition.windows(2)
+                  .position(|words| words[0].eq_ignore_ascii_case("PRIMARY") && words[1].eq_ignore_ascii_case("KEY"))
+                  .map(|position| position + 1)
+    }
+
+    // The parenthesised column list of a PRIMARY KEY table constraint
+    fn extract_primary_key_columns(components: &[String]) -> Result<Vec<IndexedColumn>, SQLSyntaxError> {
+        let Some(closing_position) = components.iter().position(|component| component == ")") else {
+            return Err(unexpected_token(None));
+        };
+        if components.first().map(String::as_str) != Some("(") {
+            return Err(SQLSyntaxError::UnexpectedToken(components[0].clone()));
+        }
+        components[1..closing_position].split(|component| component == ",")
+                                       .map(|column_components| CreateIndexStatement::to_indexed_column(column_components.to_vec()))
+                                       .collect()
+    }
+
+    // Splits the parenthesised body on its top-level commas. Parentheses nested inside a
+    // definition, as in `decimal(10, 2)` or `PRIMARY KEY (a, b)`, are kept as tokens.
+    fn extract_column_definitions(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>) -> Result<Vec<Vec<SQLToken>>, SQLSyntaxError> {
+        match tokens_iterator.next() {
+            Some(SQLToken::Symbol(Symbol::LeftParenthesis)) => (),
+            token => return Err(unexpected_token(token))
+        }
 
-                loop {
-                    let token = tokens_iterator.next_if(|t| !matches!(t, SQLToken::Symbol(Symbol::RightParenthesis)));
+        let mut column_definitions: Vec<Vec<SQLToken>> = Vec::new();
+        let mut column_defintion_components: Vec<SQLToken> = Vec::new();
+        let mut depth = 0;
+        loop {
+            match tokens_iterator.next() {
+                Some(SQLToken::Symbol(Symbol::RightParenthesis)) if depth == 0 => break,
+                Some(SQLToken::Symbol(Symbol::Comma)) if depth == 0 => {
+                    column_definitions.push(std::mem::take(&mut column_defintion_components));
+                },
+                Some(token) => {
                     match token {
-                        Some(SQLToken::Identifier(column_defintion_component)) => column_defintion_components.push(column_defintion_component.to_string()),
-                        Some(SQLToken::Symbol(Symbol::Comma)) => { break; }
-                        _ => break
+                        SQLToken::Symbol(Symbol::LeftParenthesis) => depth += 1,
+                        SQLToken::Symbol(Symbol::RightParenthesis) => depth -= 1,
+                        _ => ()
                     }
-                }
-                column_definitions.push(column_defintion_components);
+                    column_defintion_components.push(token);
+                },
+                None => break
             }
+        }
+        column_definitions.push(column_defintion_components);
+        column_definitions.retain(|column_defintion| !column_defintion.is_empty());
 
-        } else { panic!() }
-
-        return column_definitions;
+        return Ok(column_definitions);
     }
 
 }
@@ -66,13 +230,29 @@ impl CreateTableStatement {
 pub struct SelectStatement {
     pub table_name: String,
     pub columns: Option<Vec<String>>,
-    pub where_clause: Option<Condition>,
+    pub column_aliases: Vec<Option<String>>,    // AS name of each selected column, if any
+    pub where_clause: Option<Expr>,
+    pub order_by: Vec<OrderingTerm>,
+    pub limit: Option<Limit>,
     pub aggregator_function: Option<AggregatorFunction>
 }
-pub struct Condition {
-    pub left: String,
-    pub operator: String,
-    pub right: String
+
+// A term of ORDER BY: an expression, the name a selected column is given with AS, or the
+// 1-based position of a selected column. NULLs come first in ascending order and last in
+// descending order unless NULLS FIRST or NULLS LAST says otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderingTerm {
+    pub expr: Expr,
+    pub descending: bool,
+    pub nulls_first: bool
+}
+
+// LIMIT count [OFFSET skipped], or LIMIT skipped, count. Both are expressions that have to
+// come out as integers; a negative count means no limit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Limit {
+    pub count: Expr,
+    pub offset: Option<Expr>
 }
 
 #[derive(Debug, PartialEq)]
@@ -82,74 +262,150 @@ pub enum AggregatorFunction {
 }
 
 impl SelectStatement {
-    pub fn from_tokens(tokens: Vec<SQLToken>) -> Self {
+    pub fn from_tokens(tokens: Vec<SQLToken>) -> Result<Self, SQLSyntaxError> {
         let mut tokens_cursor = tokens.into_iter().peekable();
 
-        if let Some(SQLToken::Keyword(first_word)) = tokens_cursor.nth(0) { assert!(first_word == "SELECT") }
-        else { panic!() }
+        match tokens_cursor.next() {
+            Some(token) if token.is_keyword("SELECT") => (),
+            token => return Err(unexpected_token(token))
+        }
                 
         tokens_cursor.next_if(|t| matches!(t, SQLToken::Symbol(Symbol::LeftParenthesis)));
 
-        let aggregator_function = if let Some(SQLToken::Identifier(token)) = tokens_cursor.peek() {
-                                        match token.as_str() {
-                                            "COUNT" => {
+        // Function names are case-insensitive. Without a parenthesis after it, count is a column name.
+        let calls_function = matches!(tokens_cursor.clone().nth(1), Some(SQLToken::Symbol(Symbol::LeftParenthesis)));
+        let aggregator_function = match tokens_cursor.peek() {
+                                        Some(SQLToken::Operator(Operator::Star)) => None,
+                                        Some(SQLToken::Identifier(token)) if calls_function => match token.as_str() {
+                                            name if name.eq_ignore_ascii_case("COUNT") => {
                                                 tokens_cursor.next();
                                                 Some(AggregatorFunction::COUNT)
                                             },
-                                            "SUM" => {
+                                            name if name.eq_ignore_ascii_case("SUM") => {
                                                 tokens_cursor.next();
                                                 Some(AggregatorFunction::SUM)
                                             },
                                             _ => None
-                                        }                
-                                  } else { panic!("Was expecting Indetifier token.") };
+                                        },
+                                        Some(token) if token.is_name() => None,
+                                        _ => return Err(unexpected_token(tokens_cursor.next()))
+                                  };
 
         tokens_cursor.next_if(|t| matches!(t, SQLToken::Symbol(Symbol::LeftParenthesis)));
 
-        let columns: Option<Vec<String>> = if let Some(SQLToken::Identifier(token)) = tokens_cursor.peek() {
-                                                match token.as_str() {
-                                                    "*" => {
-                                                        tokens_cursor.next();
-                                                        None
-                                                    },
-                                                    _ => Self::extract_columns(&mut tokens_cursor)
-                                                }
-                                            } else { panic!() };
+        let (columns, column_aliases) = match tokens_cursor.peek() {
+                                            Some(SQLToken::Operator(Operator::Star)) => {
+                                                tokens_cursor.next();
+                                                (None, Vec::new())
+                                            },
+                                            Some(token) if token.is_name() => {
+                                                let (columns, column_aliases) = Self::extract_columns(&mut tokens_cursor)?.into_iter().unzip();
+                                                (Some(columns), column_aliases)
+                                            },
+                                            _ => return Err(unexpected_token(tokens_cursor.next()))
+                                        };
 
         tokens_cursor.next_if(|t| matches!(t, SQLToken::Symbol(Symbol::RightParenthesis)));
 
-        if let Some(SQLToken::Keyword(first_word_after_columns)) = tokens_cursor.next() { assert!(first_word_after_columns == "FROM") }
-        else { panic!() }
+        match tokens_cursor.next() {
+            Some(token) if token.is_keyword("FROM") => (),
+            token => return Err(unexpected_token(token))
+        }
 
-        let table_name = match tokens_cursor.next() {
-                            Some(SQLToken::Identifier(tablename)) => tablename,
-                            _ => panic!()
-                         };
+        let table_name = extract_name(&mut tokens_cursor)?;
+
+        let where_clause = Self::extract_where_clause(&mut tokens_cursor)?;
+        let order_by = Self::extract_order_by(&mut tokens_cursor)?;
+        let limit = Self::extract_limit(&mut tokens_cursor)?;
+
+        match tokens_cursor.next() {
+            Some(SQLToken::Symbol(Symbol::Semicolon)) | None => (),
+            token => return Err(unexpected_token(token))
+        }
+
+        Ok(Self { columns, column_aliases, table_name, where_clause, order_by, limit, aggregator_function })
+    }
+
+    // ORDER BY expr [ASC | DESC] [NULLS FIRST | NULLS LAST], ...
+    fn extract_order_by(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>) -> Result<Vec<OrderingTerm>, SQLSyntaxError> {
+        let mut order_by: Vec<OrderingTerm> = Vec::new();
+        if !CreateIndexStatement::next_word_is(tokens_iterator, "ORDER") {
+            return Ok(order_by);
+        }
+        if !CreateIndexStatement::next_word_is(tokens_iterator, "BY") {
+            return Err(unexpected_token(tokens_iterator.next()));
+        }
+
+        loop {
+            let expr = Expr::from_tokens(tokens_iterator)?;
+            let descending = match tokens_iterator.next_if(|token| token.is_keyword("ASC") || token.is_keyword("DESC")) {
+                                Some(order) => order.is_keyword("DESC"),
+                                None => false
+                             };
+            let nulls_first = match CreateIndexStatement::next_word_is(tokens_iterator, "NULLS") {
+                                true => match tokens_iterator.next() {
+                                            Some(token) if token.is_keyword("FIRST") => true,
+                                            Some(token) if token.is_keyword("LAST") => false,
+                                            token => return Err(unexpected_token(token))
+                                        },
+                                false => !descending
+                              };
+            order_by.push(OrderingTerm { expr, descending, nulls_first });
+
+            if tokens_iterator.next_if(|token| matches!(token, SQLToken::Symbol(Symbol::Comma))).is_none() {
+                return Ok(order_by);
+            }
+        }
+    }
 
-        Self { columns, table_name, where_clause: None, aggregator_function }
+    fn extract_limit(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>) -> Result<Option<Limit>, SQLSyntaxError> {
+        if tokens_iterator.next_if(|token| token.is_keyword("LIMIT")).is_none() {
+            return Ok(None);
+        }
+        let first = Expr::from_tokens(tokens_iterator)?;
+        if tokens_iterator.next_if(|token| matches!(token, SQLToken::Symbol(Symbol::Comma))).is_some() {
+            let count = Expr::from_tokens(tokens_iterator)?;
+            return Ok(Some(Limit { count, offset: Some(first) }));
+        }
+        let offset = match tokens_iterator.next_if(|token| token.is_keyword("OFFSET")) {
+                        Some(_) => Some(Expr::from_tokens(tokens_iterator)?),
+                        None => None
+                     };
+        Ok(Some(Limit { count: first, offset }))
     }
 
-    fn extract_columns(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>) -> Option<Vec<String>> {
-        let mut columns: Vec<String> = Vec::new();
+    fn extract_where_clause(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>) -> Result<Option<Expr>, SQLSyntaxError> {
+        if tokens_iterator.next_if(|token| token.is_keyword("WHERE")).is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Expr::from_tokens(tokens_iterator)?))
+    }
+
+    // Column names, each optionally followed by [AS] alias
+    fn extract_columns(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>) -> Result<Vec<(String, Option<String>)>, SQLSyntaxError> {
+        let mut columns: Vec<(String, Option<String>)> = Vec::new();
 
         while !matches!(tokens_iterator.peek(), Some(&SQLToken::Symbol(Symbol::RightParenthesis)))
-        & !matches!(tokens_iterator.peek(), Some(&SQLToken::Keyword(_))) 
+        & !tokens_iterator.peek().is_some_and(|token| token.is_keyword("FROM"))
         & !matches!(tokens_iterator.peek(), None) {
 
             let token = tokens_iterator.next();
             match token {
-                Some(SQLToken::Identifier(column)) => columns.push(column),
+                Some(token) if token.is_name() => {
+                    let alias = match tokens_iterator.next_if(|token| token.is_keyword("AS")) {
+                                    Some(_) => Some(extract_name(tokens_iterator)?),
+                                    None => tokens_iterator.next_if(SQLToken::is_name).map(|alias| alias.to_string())
+                                };
+                    columns.push((token.to_string(), alias));
+                },
                 Some(SQLToken::Symbol(Symbol::Comma)) => continue,
                 Some(SQLToken::Symbol(Symbol::RightParenthesis)) => break,
-                _ => panic!()
+                token => return Err(unexpected_token(token))
             }
             
         }
 
-        match columns.len() {
-            0 => return None,
-            _ => return Some(columns)
-        }
+        return Ok(columns);
     }
 }
 
@@ -163,9 +419,9 @@ impl ToSQLStatement for &str {
     fn to_sql_statment(&self) -> Result<SQLStatement, SQLSyntaxError> {
         let tokens: Vec<SQLToken> = self.tokenize();
                             
-        match &tokens[0] {
-            SQLToken::Keyword(s) if s == "CREATE" => Ok(SQLStatement::CreateTable(CreateTableStatement::from_tokens(tokens))),
-            SQLToken::Keyword(s) if s == "SELECT" => Ok(SQLStatement::Select(SelectStatement::from_tokens(tokens))),            
+        match tokens.first() {
+            Some(token) if token.is_keyword("CREATE") => Ok(SQLStatement::CreateTable(CreateTableStatement::from_tokens(tokens)?)),
+            Some(token) if token.is_keyword("SELECT") => Ok(SQLStatement::Select(SelectStatement::from_tokens(tokens)?)),
             _ => Err(SQLSyntaxError::UnexpectedToken(String::new()))
         }
     }
@@ -180,16 +436,111 @@ impl ToSQLStatement for Vec<&str> {
 
 
 
+#[derive(Debug, Clone)]
+pub struct IndexedColumn {
+    pub name: String,
+    pub descending: bool,
+    pub collation: Option<String>   // COLLATE clause, if the column has one
+}
+
+#[derive(Debug)]
 pub struct CreateIndexStatement {
-    tablename: String,
-    columns: Vec<String>
+    pub index_name: String,
+    pub table_name: String,
+    pub columns: Vec<IndexedColumn>,
+    pub unique: bool,
+    pub partial: bool   // has a WHERE clause, so it only covers some of the table's rows
 }
 
 impl CreateIndexStatement {
-    pub fn from_tokens(tokens: Vec<SQLToken>) -> Self {
-        let tablename = String::new();
-        let columns = Vec::new();
+    // CREATE [UNIQUE] INDEX [IF NOT EXISTS] name ON table (column [COLLATE x] [ASC|DESC], ...) [WHERE expr]
+    pub fn from_tokens(tokens: Vec<SQLToken>) -> Result<Self, SQLSyntaxError> {
+        let mut tokens_cursor = tokens.into_iter().peekable();
+
+        match tokens_cursor.next() {
+            Some(token) if token.is_keyword("CREATE") => (),
+            token => return Err(unexpected_token(token))
+        }
+
+        let unique = Self::next_word_is(&mut tokens_cursor, "UNIQUE");
+        if !Self::next_word_is(&mut tokens_cursor, "INDEX") {
+            return Err(unexpected_token(tokens_cursor.next()));
+        }
+        if Self::next_word_is(&mut tokens_cursor, "IF") && !(Self::next_word_is(&mut tokens_cursor, "NOT") && Self::next_word_is(&mut tokens_cursor, "EXISTS")) {
+            return Err(unexpected_token(tokens_cursor.next()));
+        }
+
+        let index_name = extract_name(&mut tokens_cursor)?;
+        if !Self::next_word_is(&mut tokens_cursor, "ON") {
+            return Err(unexpected_token(tokens_cursor.next()));
+        }
+        let table_name = extract_name(&mut tokens_cursor)?;
+
+        let columns = Self::extract_indexed_columns(&mut tokens_cursor)?;
+        let partial = Self::next_word_is(&mut tokens_cursor, "WHERE");
+
+        Ok(Self { index_name, table_name, columns, unique, partial })
+    }
+
+    fn next_word_is(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>, word: &str) -> bool {
+        tokens_iterator.next_if(|token| token.is_keyword(word)).is_some()
+    }
+
+    // Each indexed column is a name or an expression, optionally followed by COLLATE and a sort order.
+    // Expressions are kept as their joined text, so they never match a plain column name.
+    fn extract_indexed_columns(tokens_iterator: &mut Peekable<std::vec::IntoIter<SQLToken>>) -> Result<Vec<IndexedColumn>, SQLSyntaxError> {
+        match tokens_iterator.next() {
+            Some(SQLToken::Symbol(Symbol::LeftParenthesis)) => (),
+            token => return Err(unexpected_token(token))
+        }
 
-        CreateIndexStatement { tablename, columns }
+        let mut columns: Vec<IndexedColumn> = Vec::new();
+        let mut components: Vec<String> = Vec::new();
+        let mut depth = 0;
+        loop {
+            match tokens_iterator.next() {
+                Some(SQLToken::Symbol(Symbol::RightParenthesis)) if depth == 0 => break,
+                Some(SQLToken::Symbol(Symbol::Comma)) if depth == 0 => {
+                    columns.push(Self::to_indexed_column(std::mem::take(&mut components))?);
+                },
+                Some(SQLToken::Symbol(symbol)) => {
+                    match symbol {
+                        Symbol::LeftParenthesis => depth += 1,
+                        Symbol::RightParenthesis => depth -= 1,
+                        _ => ()
+                    }
+                    components.push(symbol.as_str().to_string());
+                },
+                Some(token) => components.push(token.to_string()),
+                None => return Err(unexpected_token(None))
+            }
+        }
+        columns.push(Self::to_indexed_column(components)?);
+
+        return Ok(columns);
     }
-}
\ No newline at end of file
+
+    fn to_indexed_column(mut components: Vec<String>) -> Result<IndexedColumn, SQLSyntaxError> {
+        let descending = match components.last() {
+                            // A lone DESC or ASC is a column with that name
+                            Some(order) if components.len() > 1 && order.eq_ignore_ascii_case("DESC") => { components.pop(); true },
+                            Some(order) if components.len() > 1 && order.eq_ignore_ascii_case("ASC") => { components.pop(); false },
+                            _ => false
+                         };
+        let collation = Self::collation(&components);
+        if let Some(collate_position) = components.iter().position(|component| component.eq_ignore_ascii_case("COLLATE")) {
+            components.truncate(collate_position);
+        }
+        if components.is_empty() {
+            return Err(unexpected_token(None));
+        }
+
+        Ok(IndexedColumn { name: components.concat(), descending, collation })
+    }
+
+    // The collating sequence named after COLLATE in a column definition or indexed column.
+    fn collation(components: &[String]) -> Option<String> {
+        let collate_position = components.iter().position(|component| component.eq_ignore_ascii_case("COLLATE"))?;
+        components.get(collate_position + 1).cloned()
+    }
+}
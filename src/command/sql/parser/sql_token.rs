@@ -1,16 +1,105 @@
-#[derive(Debug)]
+use std::fmt;
+use crate::command::sql::parser::lexer::Lexer;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum SQLToken {
     Keyword(String),
-    Identifier(String),
-    Symbol(Symbol)
+    Identifier(String),         // bare or quoted with "x", [x] or `x`; the quotes are removed
+    StringLiteral(String),      // 'x', with '' unescaped
+    IntegerLiteral(i64),
+    FloatLiteral(f64),          // also integers too large for an i64, as in SQLite
+    BlobLiteral(Vec<u8>),       // X'hex'
+    Operator(Operator),
+    Symbol(Symbol),
+    Comment(String),            // -- to the end of the line or /* ... */, as written
+    Illegal(String)             // text no token can start with, or a literal that is never closed
+}
+
+// Every SQLite keyword, sorted so a word can be looked up with a binary search. Keywords are
+// matched case-insensitively; anything else is an identifier.
+pub const KEYWORDS: [&str; 147] = [
+    "ABORT", "ACTION", "ADD", "AFTER", "ALL", "ALTER", "ALWAYS", "ANALYZE", "AND", "AS", "ASC", "ATTACH",
+    "AUTOINCREMENT", "BEFORE", "BEGIN", "BETWEEN", "BY", "CASCADE", "CASE", "CAST", "CHECK", "COLLATE",
+    "COLUMN", "COMMIT", "CONFLICT", "CONSTRAINT", "CREATE", "CROSS", "CURRENT", "CURRENT_DATE",
+    "CURRENT_TIME", "CURRENT_TIMESTAMP", "DATABASE", "DEFAULT", "DEFERRABLE", "DEFERRED", "DELETE", "DESC",
+    "DETACH", "DISTINCT", "DO", "DROP", "EACH", "ELSE", "END", "ESCAPE", "EXCEPT", "EXCLUDE", "EXCLUSIVE",
+    "EXISTS", "EXPLAIN", "FAIL", "FILTER", "FIRST", "FOLLOWING", "FOR", "FOREIGN", "FROM", "FULL",
+    "GENERATED", "GLOB", "GROUP", "GROUPS", "HAVING", "IF", "IGNORE", "IMMEDIATE", "IN", "INDEX", "INDEXED",
+    "INITIALLY", "INNER", "INSERT", "INSTEAD", "INTERSECT", "INTO", "IS", "ISNULL", "JOIN", "KEY", "LAST",
+    "LEFT", "LIKE", "LIMIT", "MATCH", "MATERIALIZED", "NATURAL", "NO", "NOT", "NOTHING", "NOTNULL", "NULL",
+    "NULLS", "OF", "OFFSET", "ON", "OR", "ORDER", "OTHERS", "OUTER", "OVER", "PARTITION", "PLAN", "PRAGMA",
+    "PRECEDING", "PRIMARY", "QUERY", "RAISE", "RANGE", "RECURSIVE", "REFERENCES", "REGEXP", "REINDEX",
+    "RELEASE", "RENAME", "REPLACE", "RESTRICT", "RETURNING", "RIGHT", "ROLLBACK", "ROW", "ROWS", "SAVEPOINT",
+    "SELECT", "SET", "TABLE", "TEMP", "TEMPORARY", "THEN", "TIES", "TO", "TRANSACTION", "TRIGGER",
+    "UNBOUNDED", "UNION", "UNIQUE", "UPDATE", "USING", "VACUUM", "VALUES", "VIEW", "VIRTUAL", "WHEN",
+    "WHERE", "WINDOW", "WITH", "WITHOUT"
+];
+
+// Keywords SQLite also accepts as a table, column or index name, as in `CREATE TABLE t (key, value)`.
+// The rest, such as SELECT, WHERE or ORDER, only name something when quoted.
+pub const NAME_KEYWORDS: [&str; 89] = [
+    "ABORT", "ACTION", "AFTER", "ALWAYS", "ANALYZE", "ASC", "ATTACH", "BEFORE", "BEGIN", "BY", "CASCADE",
+    "CAST", "COLUMN", "CONFLICT", "CROSS", "CURRENT", "CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP",
+    "DATABASE", "DEFERRED", "DESC", "DETACH", "DO", "EACH", "END", "EXCLUDE", "EXCLUSIVE", "EXPLAIN", "FAIL",
+    "FILTER", "FIRST", "FOLLOWING", "FOR", "FULL", "GENERATED", "GLOB", "GROUPS", "IF", "IGNORE",
+    "IMMEDIATE", "INDEXED", "INITIALLY", "INNER", "INSTEAD", "KEY", "LAST", "LEFT", "LIKE", "MATCH",
+    "MATERIALIZED", "NATURAL", "NO", "NULLS", "OF", "OFFSET", "OTHERS", "OUTER", "OVER", "PARTITION", "PLAN",
+    "PRAGMA", "PRECEDING", "QUERY", "RAISE", "RANGE", "RECURSIVE", "REGEXP", "REINDEX", "RELEASE", "RENAME",
+    "REPLACE", "RESTRICT", "RIGHT", "ROLLBACK", "ROW", "ROWS", "SAVEPOINT", "TEMP", "TEMPORARY", "TIES",
+    "TRIGGER", "UNBOUNDED", "VACUUM", "VIEW", "VIRTUAL", "WINDOW", "WITH", "WITHOUT"
+];
+
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS.binary_search(&word.to_ascii_uppercase().as_str()).is_ok()
+}
+
+// Tokens as they would be written in SQL. Identifiers are written without quotes, since the
+// statement parsers use this to rebuild names and expressions.
+impl fmt::Display for SQLToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SQLToken::Keyword(word) | SQLToken::Identifier(word) => write!(f, "{}", word),
+            SQLToken::StringLiteral(text) => write!(f, "'{}'", text.replace('\'', "''")),
+            SQLToken::IntegerLiteral(integer) => write!(f, "{}", integer),
+            SQLToken::FloatLiteral(float) => write!(f, "{:?}", float),
+            SQLToken::BlobLiteral(bytes) => {
+                write!(f, "X'")?;
+                for byte in bytes {
+                    write!(f, "{:02X}", byte)?;
+                }
+                write!(f, "'")
+            },
+            SQLToken::Operator(operator) => write!(f, "{}", operator.as_str()),
+            SQLToken::Symbol(symbol) => write!(f, "{}", symbol.as_str()),
+            SQLToken::Comment(text) | SQLToken::Illegal(text) => write!(f, "{}", text)
+        }
+    }
 }
 
-#[derive(Debug)]
+impl SQLToken {
+    // Keywords keep the spelling they were written with, so compare them through this.
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, SQLToken::Keyword(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    // Whether the token can name a table, column or index: an identifier, or a keyword
+    // SQLite lets stand in for one. The name is the token's `to_string()`.
+    pub fn is_name(&self) -> bool {
+        match self {
+            SQLToken::Identifier(_) => true,
+            SQLToken::Keyword(word) => NAME_KEYWORDS.binary_search(&word.to_ascii_uppercase().as_str()).is_ok(),
+            _ => false
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Symbol {
     LeftParenthesis,
     RightParenthesis,
     Comma,
-    Semicolon
+    Semicolon,
+    Dot
 }
 
 impl Symbol {
@@ -19,78 +108,101 @@ impl Symbol {
             Symbol::LeftParenthesis => "(",
             Symbol::RightParenthesis => ")",
             Symbol::Comma => ",",
-            Symbol::Semicolon => ";"
+            Symbol::Semicolon => ";",
+            Symbol::Dot => "."
         }
     }
 
-    pub const ALL: [Symbol; 4] = [
+    pub const ALL: [Symbol; 5] = [
         Symbol::LeftParenthesis,
         Symbol::RightParenthesis,
         Symbol::Comma,
         Symbol::Semicolon,
+        Symbol::Dot,
     ];
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Equals,
+    DoubleEquals,
+    NotEquals,
+    LessOrGreater,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Concatenate,
+    BitAnd,
+    BitOr,
+    BitNot,
+    ShiftLeft,
+    ShiftRight,
+    Arrow,
+    DoubleArrow
+}
+
+impl Operator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operator::Equals => "=",
+            Operator::DoubleEquals => "==",
+            Operator::NotEquals => "!=",
+            Operator::LessOrGreater => "<>",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+            Operator::Plus => "+",
+            Operator::Minus => "-",
+            Operator::Star => "*",
+            Operator::Slash => "/",
+            Operator::Percent => "%",
+            Operator::Concatenate => "||",
+            Operator::BitAnd => "&",
+            Operator::BitOr => "|",
+            Operator::BitNot => "~",
+            Operator::ShiftLeft => "<<",
+            Operator::ShiftRight => ">>",
+            Operator::Arrow => "->",
+            Operator::DoubleArrow => "->>"
+        }
+    }
+
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, Operator::Equals | Operator::DoubleEquals | Operator::NotEquals | Operator::LessOrGreater |
+                       Operator::Less | Operator::LessOrEqual | Operator::Greater | Operator::GreaterOrEqual)
+    }
+}
+
 
 
 pub trait Tokenize {
     fn tokenize(&self) -> Vec<SQLToken>;
-} 
+}
+// Comments are dropped here; `Lexer` keeps them and gives the span of every token.
 impl Tokenize for str {
     fn tokenize(&self) -> Vec<SQLToken> {
-        let mut tokens: Vec<SQLToken> = Vec::new();
-        let binding = self.trim().replace("\n", " ");
-        let components = binding.split_whitespace();
-        for component in components.into_iter() {
-            let mut component_tokens = match component {
-                                        s if (s == "SELECT") | (s == "CREATE") | (s == "FROM") => vec![SQLToken::Keyword((*s).to_string())],
-                                        s if s.len() == 1 => vec![s.chars().next().unwrap().to_sql_token()], //this is ugly as hell but rust std doesnt support character indexing🤷🏾‍♂️ 
-                                        _ => tokenize_component(component)
-                                        };
-            tokens.append(&mut component_tokens);
-        }
-        return tokens;
+        Lexer::new(self).map(|token| token.kind)
+                        .filter(|token| !matches!(token, SQLToken::Comment(_)))
+                        .collect()
     }
 }
 
 pub trait ToSQLToken {
     fn to_sql_token(&self) -> SQLToken;
-} 
+}
 impl ToSQLToken for char {
     fn to_sql_token(&self) -> SQLToken {
-        let token = match self {
-                        '(' => SQLToken::Symbol(Symbol::LeftParenthesis),
-                        ')' => SQLToken::Symbol(Symbol::RightParenthesis),
-                        ',' => SQLToken::Symbol(Symbol::Comma),
-                        ';' => SQLToken::Symbol(Symbol::Semicolon),
-                        _ => SQLToken::Identifier(self.to_string())
-                    };
-        return token;
-    }
-}
-
-pub fn tokenize_component(value: &str) -> Vec<SQLToken> {
-    let mut tokens: Vec<SQLToken> = Vec::new();
-    let mut current = String::new();
-
-    for character in value.chars() {
-        // Check if character is any known symbol
-        if Symbol::ALL.iter().any(|s| s.as_str() == character.to_string()) {
-            if !current.is_empty() {
-                tokens.push(SQLToken::Identifier(current.clone()));
-                current.clear();
-            }
-            tokens.push(character.to_sql_token());
-        } else {
-            current.push(character);
+        let text = self.to_string();
+        match text.tokenize().pop() {
+            Some(token) => token,
+            None => SQLToken::Illegal(text)
         }
     }
-
-    if !current.is_empty() {
-        tokens.push(SQLToken::Identifier(current));
-    }
-
-    
-
-    return tokens
 }
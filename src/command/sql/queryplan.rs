@@ -0,0 +1,259 @@
+use std::cmp::Ordering;
+use std::fmt;
+use crate::command::sql::parser::expr::{BinaryOperator, Expr};
+use crate::command::sql::parser::sql_statement::{CreateTableStatement, IndexedColumn, SelectStatement};
+use crate::models::dbfile::dbtable::{Affinity, Value};
+use crate::models::dbfile::header::TextEncoding;
+use crate::models::dbfile::schema::SchemaIndex;
+use crate::models::dbfile::table::DBTable;
+use crate::models::error::{SQLCommandError, SQLError};
+
+// A `column operator constant` term ANDed into the WHERE clause, which the rowid, an index or
+// the primary key can narrow the rows by. Every row read is still checked against the whole clause.
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub column: String,
+    pub operator: BinaryOperator,
+    pub value: Value
+}
+
+impl Constraint {
+    // The constraints of every top-level term of the WHERE clause that has them.
+    pub fn from_where_clause(where_clause: Option<&Expr>) -> Vec<Constraint> {
+        let Some(where_clause) = where_clause else {
+            return Vec::new();
+        };
+        where_clause.conjuncts()
+                    .into_iter()
+                    .filter_map(Self::from_term)
+                    .flatten()
+                    .collect()
+    }
+
+    // A comparison between a column and a literal, or a BETWEEN with literal bounds, which becomes
+    // a >= and a <= constraint. `5 < age` is read as `age > 5`.
+    pub fn from_term(term: &Expr) -> Option<Vec<Constraint>> {
+        let constraint = |column: &str, operator: BinaryOperator, value: &Value| Constraint { column: column.to_string(), operator, value: value.clone() };
+        match term {
+            Expr::Binary { left, operator, right } => match (left.as_ref(), right.as_ref()) {
+                (Expr::Column { name, .. }, Expr::Literal(value)) if Self::is_comparison(*operator) => Some(vec![constraint(name, *operator, value)]),
+                (Expr::Literal(value), Expr::Column { name, .. }) if Self::is_comparison(*operator) => Some(vec![constraint(name, operator.flipped()?, value)]),
+                _ => None
+            },
+            Expr::Between { operand, low, high, negated: false } => match (operand.as_ref(), low.as_ref(), high.as_ref()) {
+                (Expr::Column { name, .. }, Expr::Literal(low), Expr::Literal(high)) => {
+                    Some(vec![constraint(name, BinaryOperator::GreaterOrEqual, low), constraint(name, BinaryOperator::LessOrEqual, high)])
+                },
+                _ => None
+            },
+            _ => None
+        }
+    }
+
+    fn is_comparison(operator: BinaryOperator) -> bool {
+        matches!(operator, BinaryOperator::Equals | BinaryOperator::NotEquals |
+                           BinaryOperator::Less | BinaryOperator::LessOrEqual | BinaryOperator::Greater | BinaryOperator::GreaterOrEqual)
+    }
+
+    // Comparisons against NULL are never true, as in SQL. Text compares in the database encoding,
+    // the order index keys are stored in.
+    pub fn matches(&self, value: &Value, text_encoding: TextEncoding) -> bool {
+        if value.is_null() || self.value.is_null() {
+            return false;
+        }
+        let ordering = value.compare_encoded(&self.value, text_encoding);
+        match self.operator {
+            BinaryOperator::Equals => ordering == Ordering::Equal,
+            BinaryOperator::NotEquals => ordering != Ordering::Equal,
+            BinaryOperator::Less => ordering == Ordering::Less,
+            BinaryOperator::LessOrEqual => ordering != Ordering::Greater,
+            BinaryOperator::Greater => ordering == Ordering::Greater,
+            BinaryOperator::GreaterOrEqual => ordering != Ordering::Less,
+            _ => false
+        }
+    }
+
+    pub fn is_equality(&self) -> bool {
+        self.operator == BinaryOperator::Equals
+    }
+
+    pub fn is_lower_bound(&self) -> bool {
+        matches!(self.operator, BinaryOperator::Greater | BinaryOperator::GreaterOrEqual)
+    }
+
+    pub fn is_upper_bound(&self) -> bool {
+        matches!(self.operator, BinaryOperator::Less | BinaryOperator::LessOrEqual)
+    }
+
+    // Equality and range constraints can be answered from an index; != has to look at every row.
+    pub fn is_indexable(&self) -> bool {
+        self.is_equality() || self.is_lower_bound() || self.is_upper_bound()
+    }
+}
+
+// How a SELECT reads its table: every row in rowid order, only the rowids in a range,
+// or only the rows an index points at for the WHERE constraints on its leading columns.
+// A WITHOUT ROWID table is scanned or searched in primary key order instead.
+#[derive(Debug)]
+pub enum QueryPlan {
+    Scan { table_name: String },
+    RowidSearch { table_name: String, first_row_id: i64, last_row_id: i64, constraints: Vec<String> },
+    // `prefix` holds the values the leading index columns are equal to, `range` the bounds on the column after them
+    IndexSearch { table_name: String, index: SchemaIndex, prefix: Vec<Value>, range: Vec<Constraint>, constraints: Vec<String> },
+    PrimaryKeySearch { table_name: String, prefix: Vec<Value>, range: Vec<Constraint>, constraints: Vec<String> }
+}
+
+impl QueryPlan {
+    pub fn for_statement(table: &DBTable, statement: &SelectStatement) -> Result<Self, SQLError> {
+        let table_name = table.description.table_name.clone();
+        let table_description = &table.description.sql;
+        if let Some(where_clause) = &statement.where_clause {
+            Self::check_column_references(table_description, where_clause)?;
+        }
+        // A constraint's value is compared the way the column's affinity converts it, so `age = '40'`
+        // finds the integer 40 in an INTEGER column's index
+        let where_constraints: Vec<Constraint> = Constraint::from_where_clause(statement.where_clause.as_ref())
+                                                            .into_iter()
+                                                            .filter(Constraint::is_indexable)
+                                                            .map(|constraint| {
+                                                                let affinity = match table_description.column_index(&constraint.column) {
+                                                                                    Some(column_index) => table_description.column_affinity(column_index),
+                                                                                    None => Affinity::Integer
+                                                                               };
+                                                                Constraint { value: constraint.value.apply_affinity(affinity), ..constraint }
+                                                            })
+                                                            .collect();
+
+        // The table b-tree is keyed by rowid, which an INTEGER PRIMARY KEY column is another name for
+        let rowid_constraints: Vec<&Constraint> = where_constraints.iter()
+                                                                   .filter(|constraint| table_description.is_rowid_alias(&constraint.column) || (table_description.integer_primary_key_column.is_some() && table_description.column_index(&constraint.column) == table_description.integer_primary_key_column))
+                                                                   .collect();
+        if !rowid_constraints.is_empty() {
+            let (first_row_id, last_row_id) = Self::rowid_range(&rowid_constraints);
+            let constraints = rowid_constraints.iter().map(|constraint| Self::describe("rowid", constraint)).collect();
+            return Ok(QueryPlan::RowidSearch { table_name, first_row_id, last_row_id, constraints });
+        }
+
+        // Secondary indexes of a WITHOUT ROWID table point at primary keys rather than rowids
+        if table_description.without_rowid {
+            let (prefix, range, constraints) = Self::key_constraints(table_description, &table_description.primary_key, &where_constraints);
+            if constraints.is_empty() {
+                return Ok(QueryPlan::Scan { table_name });
+            }
+            return Ok(QueryPlan::PrimaryKeySearch { table_name, prefix, range, constraints });
+        }
+
+        // Partial indexes don't hold every row. Indexes constraining more columns win, then unique ones.
+        let mut best_plan: Option<((usize, bool, bool), QueryPlan)> = None;
+        for index in table.indexes()?.into_iter().filter(|index| !index.sql.partial) {
+            let (prefix, range, constraints) = Self::key_constraints(table_description, &index.sql.columns, &where_constraints);
+            if constraints.is_empty() {
+                continue;
+            }
+
+            let score = (prefix.len(), !range.is_empty(), index.sql.unique);
+            let is_better = match &best_plan {
+                                Some((best_score, _)) => score > *best_score,
+                                None => true
+                            };
+            if is_better {
+                best_plan = Some((score, QueryPlan::IndexSearch { table_name: table_name.clone(), index, prefix, range, constraints }));
+            }
+        }
+
+        match best_plan {
+            Some((_, plan)) => Ok(plan),
+            None => Ok(QueryPlan::Scan { table_name })
+        }
+    }
+
+    // Every column an expression names has to be one of the table's, or the rowid.
+    pub fn check_column_references(table_description: &CreateTableStatement, expr: &Expr) -> Result<(), SQLCommandError> {
+        for (table_qualifier, column) in expr.column_references() {
+            let is_other_table = table_qualifier.is_some_and(|table_qualifier| !table_qualifier.eq_ignore_ascii_case(&table_description.table_name));
+            if is_other_table || (table_description.column_index(column).is_none() && !table_description.is_rowid_alias(column)) {
+                let column = match table_qualifier {
+                                Some(table_qualifier) => format!("{}.{}", table_qualifier, column),
+                                None => column.to_string()
+                             };
+                return Err(SQLCommandError::UnknownColumn(column));
+            }
+        }
+        Ok(())
+    }
+
+    // The values the leading key columns are equal to, then the bounds on the column after them.
+    // Keys are searched byte by byte, so only columns that are compared and indexed that way count.
+    fn key_constraints(table_description: &CreateTableStatement, columns: &[IndexedColumn], where_constraints: &[Constraint]) -> (Vec<Value>, Vec<Constraint>, Vec<String>) {
+        let mut prefix: Vec<Value> = Vec::new();
+        let mut range: Vec<Constraint> = Vec::new();
+        let mut constraints: Vec<String> = Vec::new();
+        for column in columns.iter() {
+            let table_collation = table_description.column_index(&column.name).and_then(|column_index| table_description.column_collations[column_index].as_ref());
+            if !Self::is_binary(column.collation.as_ref()) || !Self::is_binary(table_collation) {
+                break;
+            }
+            let column_constraints: Vec<&Constraint> = where_constraints.iter()
+                                                                        .filter(|constraint| constraint.column.eq_ignore_ascii_case(&column.name))
+                                                                        .collect();
+            if let Some(equality) = column_constraints.iter().find(|constraint| constraint.is_equality()) {
+                prefix.push(equality.value.clone());
+                constraints.push(Self::describe(&column.name, equality));
+                continue;
+            }
+            // The cursor walks the index in ascending key order, so ranges need an ascending column
+            if !column.descending {
+                range = column_constraints.into_iter()
+                                          .filter(|constraint| constraint.is_lower_bound() || constraint.is_upper_bound())
+                                          .cloned()
+                                          .collect();
+                constraints.extend(range.iter().map(|constraint| Self::describe(&column.name, constraint)));
+            }
+            break;
+        }
+        (prefix, range, constraints)
+    }
+
+    fn is_binary(collation: Option<&String>) -> bool {
+        match collation {
+            Some(collation) => collation.eq_ignore_ascii_case("BINARY"),
+            None => true
+        }
+    }
+
+    // Narrows the rowids worth reading. Bounds are inclusive and may be loose, since every row
+    // read is still checked against the WHERE clause; text and blobs never narrow the range.
+    fn rowid_range(constraints: &[&Constraint]) -> (i64, i64) {
+        let (mut first_row_id, mut last_row_id) = (i64::MIN, i64::MAX);
+        for constraint in constraints {
+            let (low, high) = match &constraint.value {
+                                Value::Integer(n) => (*n, *n),
+                                Value::Real(n) => (n.floor() as i64, n.ceil() as i64),
+                                _ => continue
+                              };
+            if constraint.is_equality() || constraint.is_lower_bound() {
+                first_row_id = first_row_id.max(low);
+            }
+            if constraint.is_equality() || constraint.is_upper_bound() {
+                last_row_id = last_row_id.min(high);
+            }
+        }
+        (first_row_id, last_row_id)
+    }
+
+    fn describe(column: &str, constraint: &Constraint) -> String {
+        format!("{}{}?", column, constraint.operator.as_str())
+    }
+}
+
+// Mirrors the wording of SQLite's EXPLAIN QUERY PLAN.
+impl fmt::Display for QueryPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryPlan::Scan { table_name } => write!(f, "SCAN {}", table_name),
+            QueryPlan::RowidSearch { table_name, constraints, .. } => write!(f, "SEARCH {} USING INTEGER PRIMARY KEY ({})", table_name, constraints.join(" AND ")),
+            QueryPlan::IndexSearch { table_name, index, constraints, .. } => write!(f, "SEARCH {} USING INDEX {} ({})", table_name, index.name, constraints.join(" AND ")),
+            QueryPlan::PrimaryKeySearch { table_name, constraints, .. } => write!(f, "SEARCH {} USING PRIMARY KEY ({})", table_name, constraints.join(" AND "))
+        }
+    }
+}
@@ -1,35 +1,328 @@
-
-use crate::command::sql::parser::sql_statement::{SelectStatement, AggregatorFunction};
-use crate::models::dbfile::dbtable::TableRow;
+use crate::command::sql::parser::sql_statement::{SelectStatement, AggregatorFunction, CreateTableStatement, Limit};
+use crate::models::dbfile::dbtable::tablepage::Record;
+use crate::command::sql::parser::expr::Expr;
+use crate::models::dbfile::dbtable::{Affinity, TableCursor, TableRow, Value, WithoutRowidCursor};
 use crate::models::dbfile::table::DBTable;
+use crate::models::dbfile::header::TextEncoding;
+use crate::models::error::{DatabaseError, SQLCommandError, SQLError};
+use crate::command::sql::{Constraint, Evaluator, QueryPlan, SortKey, SortedRows, Sorter};
+
+pub fn select(table: DBTable, statement: SelectStatement) -> Result<Vec<TableRow>, SQLError> {
+    select_rows(table, statement)?.collect()
+}
+
+// Plans the statement and returns an iterator that reads the matching rows one at a time.
+pub fn select_rows(mut table: DBTable, statement: SelectStatement) -> Result<SelectRows, SQLError> {
+    if let Some(unsupported @ AggregatorFunction::SUM) = statement.aggregator_function {
+        return Err(SQLCommandError::UnsupportedCommand(format!("{:?}", unsupported)))?;
+    }
+
+    let (source, first_row_id) = match QueryPlan::for_statement(&table, &statement)? {
+                                    QueryPlan::Scan { .. } => (RowSource::Scan, None),
+                                    QueryPlan::RowidSearch { first_row_id, last_row_id, .. } => (RowSource::Range { last_row_id }, Some(first_row_id)),
+                                    QueryPlan::IndexSearch { index, prefix, range, .. } => (RowSource::RowIds(table.index_row_ids(&index, &prefix, &range)?.into_iter()), None),
+                                    QueryPlan::PrimaryKeySearch { prefix, range, .. } => (RowSource::PrimaryKeyRange { prefix, range }, None)
+                                 };
+
+    // A REAL column may store a whole number as an integer to save space; it reads back as a real
+    let real_column_indexes: Vec<usize> = (0..table.description.sql.columns.len()).filter(|&column_index| table.description.sql.column_affinity(column_index) == Affinity::Real)
+                                                                                 .collect();
+
+    // Selected columns come back in table order. Column names are case-insensitive in SQL.
+    if let Some(unknown_column) = statement.columns.iter().flatten().find(|selected| table.description.sql.column_index(selected).is_none()) {
+        return Err(SQLError::Command(SQLCommandError::UnknownColumn(unknown_column.clone())));
+    }
+    let selected_column_indexes: Option<Vec<usize>> = statement.columns.as_ref().map(|statement_columns| {
+        table.description.sql.columns.iter()
+                                     .enumerate()
+                                     .filter(|(_, column_name)| statement_columns.iter().any(|selected| selected.eq_ignore_ascii_case(column_name)))
+                                     .map(|(column_index, _)| column_index)
+                                     .collect()
+    });
+
+    let text_encoding = table.text_encoding();
+    let (order_by, sort_keys) = resolve_order_by(&table.description.sql, &statement, text_encoding)?;
+    let sort_budget = table.sort_budget();
+    let (offset, limit) = resolve_limit(&table.description.sql, statement.limit.as_ref(), text_encoding)?;
+
+    let cursor = match (table.description.sql.without_rowid, &source) {
+                    (true, RowSource::PrimaryKeyRange { prefix, range }) => {
+                        let mut cursor = table.into_without_rowid_cursor();
+                        cursor.seek_range(prefix, range)?;
+                        RowCursor::WithoutRowid(cursor)
+                    },
+                    (true, _) => RowCursor::WithoutRowid(table.into_without_rowid_cursor()),
+                    (false, _) => {
+                        let mut cursor = table.into_cursor();
+                        if let Some(first_row_id) = first_row_id {
+                            cursor.seek(first_row_id)?;
+                        }
+                        RowCursor::Table(cursor)
+                    }
+                 };
+
+    Ok(SelectRows { cursor, source, where_clause: statement.where_clause, text_encoding, real_column_indexes, selected_column_indexes, order_by, sort_keys, sort_budget, sorted_rows: None,
+                    aggregator_function: statement.aggregator_function, offset, limit, finished: false })
+}
+
+// The expression each ORDER BY term sorts by and how. A position counts the columns as the SELECT
+// names them, a column named twice taking two positions, and a name given with AS stands for its
+// column, ahead of any table column of that name.
+fn resolve_order_by(table_description: &CreateTableStatement, statement: &SelectStatement, text_encoding: TextEncoding) -> Result<(Vec<Expr>, Vec<SortKey>), SQLError> {
+    let output_column_indexes: Vec<usize> = match &statement.columns {
+                                                Some(statement_columns) => statement_columns.iter().filter_map(|selected| table_description.column_index(selected)).collect(),
+                                                None => (0..table_description.columns.len()).collect()
+                                            };
+    let evaluator = Evaluator::new(table_description, text_encoding);
+    let mut order_by: Vec<Expr> = Vec::new();
+    let mut sort_keys: Vec<SortKey> = Vec::new();
+    for (term_number, term) in statement.order_by.iter().enumerate() {
+        let column = |column_name: &str| Expr::Column { table: None, name: column_name.to_string() };
+        let expr = match &term.expr {
+                        Expr::Literal(Value::Integer(position)) => {
+                            match usize::try_from(*position).ok().filter(|&position| position >= 1).and_then(|position| output_column_indexes.get(position - 1)) {
+                                Some(&column_index) => column(&table_description.columns[column_index]),
+                                None => return Err(SQLCommandError::InvalidExpression(format!("ORDER BY term {} out of range - should be between 1 and {}", term_number + 1, output_column_indexes.len())))?
+                            }
+                        },
+                        Expr::Column { table: None, name } => {
+                            let aliased_column = statement.columns.iter()
+                                                                  .flatten()
+                                                                  .zip(statement.column_aliases.iter())
+                                                                  .find(|(_, alias)| alias.as_ref().is_some_and(|alias| alias.eq_ignore_ascii_case(name)));
+                            match aliased_column {
+                                Some((column_name, _)) => column(column_name),
+                                None => term.expr.clone()
+                            }
+                        },
+                        expr => expr.clone()
+                   };
+        QueryPlan::check_column_references(table_description, &expr)?;
+        sort_keys.push(SortKey { collation: evaluator.ordering_collation(&expr)?, descending: term.descending, nulls_first: term.nulls_first, text_encoding });
+        order_by.push(expr);
+    }
+    Ok((order_by, sort_keys))
+}
+
+// How many rows LIMIT skips and how many it returns after them, None for all of them. A negative
+// offset skips nothing. Like SQLite, the expressions may not refer to columns.
+fn resolve_limit(table_description: &CreateTableStatement, limit: Option<&Limit>, text_encoding: TextEncoding) -> Result<(usize, Option<usize>), SQLError> {
+    let Some(limit) = limit else {
+        return Ok((0, None));
+    };
+    let evaluator = Evaluator::new(table_description, text_encoding);
+    let to_integer = |expr: &Expr| -> Result<i64, SQLError> {
+        if let Some((_, column_name)) = expr.column_references().first() {
+            return Err(SQLError::Command(SQLCommandError::UnknownColumn(column_name.to_string())));
+        }
+        match evaluator.evaluate(expr, &Record { row_id: 0, column_values: Vec::new() })?.apply_affinity(Affinity::Integer) {
+            Value::Integer(integer) => Ok(integer),
+            Value::Real(real) if real.fract() == 0.0 && real.abs() < i64::MAX as f64 => Ok(real as i64),
+            _ => Err(SQLCommandError::InvalidExpression(format!("datatype mismatch in LIMIT term {}", expr)))?
+        }
+    };
+    let count = usize::try_from(to_integer(&limit.count)?).ok();
+    let offset = match &limit.offset {
+                    Some(offset) => usize::try_from(to_integer(offset)?).unwrap_or(0),
+                    None => 0
+                 };
+    Ok((offset, count))
+}
+
+// Where the candidate rows of a SELECT come from, as chosen by the query plan.
+enum RowSource {
+    Scan,
+    Range { last_row_id: i64 },
+    RowIds(std::vec::IntoIter<i64>),
+    PrimaryKeyRange { prefix: Vec<Value>, range: Vec<Constraint> }
+}
+
+// Rowid tables are read from their table b-tree, WITHOUT ROWID tables from their primary key b-tree.
+enum RowCursor<'a> {
+    Table(TableCursor<'a>),
+    WithoutRowid(WithoutRowidCursor<'a>)
+}
+
+impl RowCursor<'_> {
+    fn table_description(&self) -> &CreateTableStatement {
+        match self {
+            RowCursor::Table(cursor) => &cursor.table_description,
+            RowCursor::WithoutRowid(cursor) => &cursor.table_description
+        }
+    }
+
+    fn next_record(&mut self) -> Result<Option<Record>, DatabaseError> {
+        match self {
+            RowCursor::Table(cursor) => cursor.next_record(),
+            RowCursor::WithoutRowid(cursor) => cursor.next_record()
+        }
+    }
+}
+
+pub struct SelectRows<'a> {
+    cursor: RowCursor<'a>,
+    source: RowSource,
+    where_clause: Option<Expr>,
+    text_encoding: TextEncoding,
+    real_column_indexes: Vec<usize>,
+    selected_column_indexes: Option<Vec<usize>>,
+    order_by: Vec<Expr>,
+    sort_keys: Vec<SortKey>,
+    sort_budget: usize,
+    sorted_rows: Option<SortedRows>,
+    aggregator_function: Option<AggregatorFunction>,
+    offset: usize,          // rows LIMIT still has to skip
+    limit: Option<usize>,   // rows LIMIT still lets through, None for no limit
+    finished: bool
+}
 
-pub fn select(table: &mut DBTable, mut statement: SelectStatement) -> Vec<TableRow> {
+impl SelectRows<'_> {
+    fn next_candidate(&mut self) -> Result<Option<Record>, DatabaseError> {
+        match (&mut self.source, &mut self.cursor) {
+            (RowSource::PrimaryKeyRange { prefix, range }, RowCursor::WithoutRowid(cursor)) => cursor.next_in_range(prefix, range),
+            (_, RowCursor::WithoutRowid(cursor)) => cursor.next_record(),
+            (RowSource::Scan, cursor) => cursor.next_record(),
+            (RowSource::Range { last_row_id }, RowCursor::Table(cursor)) => {
+                let last_row_id = *last_row_id;
+                Ok(cursor.next_record()?.filter(|record| record.row_id <= last_row_id))
+            },
+            (RowSource::RowIds(row_ids), RowCursor::Table(cursor)) => {
+                for row_id in row_ids.by_ref() {
+                    cursor.seek(row_id)?;
+                    if let Some(record) = cursor.next_record()?.filter(|record| record.row_id == row_id) {
+                        return Ok(Some(record));
+                    }
+                }
+                Ok(None)
+            },
+            // The planner only searches the primary key of WITHOUT ROWID tables
+            (RowSource::PrimaryKeyRange { .. }, RowCursor::Table(cursor)) => cursor.next_record()
+        }
+    }
 
-    let records = table.to_table_rows(&statement);
-    let mut table_rows: Vec<TableRow> = records.into_iter()
-                                               .map(|record| TableRow::from(record))
-                                               .collect();
-                                            
-    let aggregator = statement.aggregator_function.take();
-    if let Some(aggregator_function) = aggregator {
-        table_rows = aggregate_table_rows(table_rows, aggregator_function);
+    fn next_matching_record(&mut self) -> Result<Option<Record>, SQLError> {
+        while let Some(mut record) = self.next_candidate()? {
+            for &column_index in self.real_column_indexes.iter() {
+                if let Some(value) = record.column_values.get_mut(column_index) {
+                    *value = std::mem::replace(value, Value::Null).apply_affinity(Affinity::Real);
+                }
+            }
+            if self.matches_where_clause(&record)? {
+                return Ok(Some(record));
+            }
+        }
+        Ok(None)
     }
 
-    table_rows
+    fn matches_where_clause(&self, record: &Record) -> Result<bool, SQLError> {
+        match &self.where_clause {
+            Some(where_clause) => Ok(Evaluator::new(self.cursor.table_description(), self.text_encoding).is_true(where_clause, record)?),
+            None => Ok(true)
+        }
+    }
+
+    // Once LIMIT has let its rows through no more are read, so a query without ORDER BY or an
+    // aggregate stops scanning the table there.
+    pub fn next_row(&mut self) -> Result<Option<TableRow>, SQLError> {
+        if self.finished {
+            return Ok(None);
+        }
+        if self.limit == Some(0) {
+            self.finished = true;
+            return Ok(None);
+        }
+
+        while self.offset > 0 {
+            self.offset -= 1;
+            if self.next_result_row()?.is_none() {
+                return Ok(None);
+            }
+        }
+        let row = self.next_result_row()?;
+        if let (Some(_), Some(limit)) = (&row, self.limit.as_mut()) {
+            *limit -= 1;
+        }
+        Ok(row)
+    }
+
+    fn next_result_row(&mut self) -> Result<Option<TableRow>, SQLError> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        if let Some(aggregator_function) = self.aggregator_function.take() {
+            let mut row_count = 0;
+            while self.next_matching_record()?.is_some() {
+                row_count += 1;
+            }
+            self.finished = true;
+            return Ok(Some(aggregate_row_count(row_count, aggregator_function)?));
+        }
+
+        if !self.order_by.is_empty() {
+            return self.next_sorted_row();
+        }
+
+        let Some(record) = self.next_matching_record()? else {
+            self.finished = true;
+            return Ok(None);
+        };
+        return Ok(Some(self.to_table_row(record)));
+    }
+
+    // Every matching row has to be read before the first one in order is known. Rows past the
+    // sort budget wait in temporary files.
+    fn next_sorted_row(&mut self) -> Result<Option<TableRow>, SQLError> {
+        if self.sorted_rows.is_none() {
+            let mut sorter = Sorter::new(std::mem::take(&mut self.sort_keys), self.sort_budget);
+            while let Some(record) = self.next_matching_record()? {
+                let evaluator = Evaluator::new(self.cursor.table_description(), self.text_encoding);
+                let key_values = self.order_by.iter()
+                                              .map(|expr| evaluator.evaluate(expr, &record))
+                                              .collect::<Result<Vec<Value>, SQLCommandError>>()?;
+                sorter.push(key_values, self.to_table_row(record)).map_err(SQLError::TemporaryFile)?;
+            }
+            self.sorted_rows = Some(sorter.into_sorted().map_err(SQLError::TemporaryFile)?);
+        }
+
+        let row = self.sorted_rows.as_mut().and_then(Iterator::next).transpose().map_err(SQLError::TemporaryFile)?;
+        if row.is_none() {
+            self.finished = true;
+        }
+        Ok(row)
+    }
+
+    fn to_table_row(&self, record: Record) -> TableRow {
+        let column_values = match &self.selected_column_indexes {
+                                Some(column_indexes) => column_indexes.iter().map(|&column_index| record.column_values[column_index].clone()).collect(),
+                                None => record.column_values
+                            };
+        TableRow { row_id: record.row_id, column_values }
+    }
+}
+
+impl Iterator for SelectRows<'_> {
+    type Item = Result<TableRow, SQLError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_row() {
+            Ok(table_row) => table_row.map(Ok),
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
 }
 
 
-pub fn aggregate_table_rows(table_rows: Vec<TableRow>, aggregator_function: AggregatorFunction) -> Vec<TableRow>{
-    let mut aggregated_rows: Vec<TableRow> = Vec::new();
+pub fn aggregate_row_count(row_count: usize, aggregator_function: AggregatorFunction) -> Result<TableRow, SQLError> {
     match aggregator_function {
         AggregatorFunction::COUNT => {
-            let aggegated_row_count = table_rows.len();
             let row_id = 1;
-            let column_values = vec![aggegated_row_count.to_string()];
-            let table_row = TableRow { row_id, column_values };
-            aggregated_rows.push(table_row);
+            let column_values = vec![Value::Integer(row_count as i64)];
+            return Ok(TableRow { row_id, column_values });
         }
-        _ => panic!()
+        unsupported => return Err(SQLCommandError::UnsupportedCommand(format!("{:?}", unsupported)))?
     }
-    return aggregated_rows;
-}
\ No newline at end of file
+}
@@ -0,0 +1,200 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::io;
+use std::rc::Rc;
+use crate::command::sql::sortrun::{SortRun, SortRunWriter};
+use crate::command::sql::evaluator::{Collation, Evaluator};
+use crate::models::dbfile::dbtable::{TableRow, Value};
+use crate::models::dbfile::header::TextEncoding;
+
+// How the values of one ORDER BY term are ordered.
+#[derive(Debug, Clone, Copy)]
+pub struct SortKey {
+    pub collation: Collation,
+    pub descending: bool,
+    pub nulls_first: bool,
+    pub text_encoding: TextEncoding     // BINARY orders text by its bytes in the database encoding
+}
+
+impl SortKey {
+    // Values of different storage classes order NULL < numbers < text < blobs. DESC reverses
+    // that, but NULLs stay wherever NULLS FIRST or NULLS LAST put them.
+    pub fn compare(&self, a: &Value, b: &Value) -> Ordering {
+        let nulls = if self.nulls_first { Ordering::Less } else { Ordering::Greater };
+        match (a.is_null(), b.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => nulls,
+            (false, true) => nulls.reverse(),
+            _ if self.descending => Evaluator::compare_values(a, b, self.collation, self.text_encoding).reverse(),
+            _ => Evaluator::compare_values(a, b, self.collation, self.text_encoding)
+        }
+    }
+}
+
+// How many bytes of rows a sort holds in memory before it writes them to a temporary file.
+pub const DEFAULT_SORT_BUDGET: usize = 64 * 1024 * 1024;
+
+// The most runs merged at once, which keeps the number of open files bounded.
+const MERGE_WIDTH: usize = 64;
+
+// Collects the rows of an ORDER BY query along with the values of its terms, and hands them
+// back in order. Rows the terms can't tell apart keep the order they were read in.
+// Once the rows held in memory outgrow the budget they are sorted and written out to a
+// temporary file as a run; the runs are merged when the rows are read back.
+pub struct Sorter {
+    sort_keys: Vec<SortKey>,
+    rows: Vec<(Vec<Value>, TableRow)>,
+    rows_size: usize,       // estimated bytes of memory `rows` takes up
+    memory_budget: usize,
+    runs: Vec<SortRun>
+}
+
+impl Sorter {
+    pub fn new(sort_keys: Vec<SortKey>, memory_budget: usize) -> Self {
+        Self { sort_keys, rows: Vec::new(), rows_size: 0, memory_budget, runs: Vec::new() }
+    }
+
+    pub fn push(&mut self, key_values: Vec<Value>, row: TableRow) -> io::Result<()> {
+        self.rows_size += Self::estimated_size(&key_values, &row);
+        self.rows.push((key_values, row));
+        if self.rows_size > self.memory_budget {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    // The number of runs written to temporary files so far.
+    pub fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn compare(sort_keys: &[SortKey], a: &[Value], b: &[Value]) -> Ordering {
+        sort_keys.iter()
+                 .zip(a.iter().zip(b.iter()))
+                 .map(|(sort_key, (a, b))| sort_key.compare(a, b))
+                 .find(|ordering| ordering.is_ne())
+                 .unwrap_or(Ordering::Equal)
+    }
+
+    pub fn into_sorted(mut self) -> io::Result<SortedRows> {
+        if self.runs.is_empty() {
+            self.sort_rows();
+            return Ok(SortedRows::InMemory(self.rows.into_iter()));
+        }
+
+        self.spill()?;
+        let sort_keys: Rc<[SortKey]> = Rc::from(self.sort_keys);
+        let mut runs = self.runs;
+        // Merge the earliest runs into one until few enough are left to merge at once.
+        // Runs stay in the order they were written, so equal rows keep their order.
+        while runs.len() > MERGE_WIDTH {
+            let mut merge = Merge::new(runs.drain(..MERGE_WIDTH).collect(), sort_keys.clone())?;
+            let mut writer = SortRunWriter::new(sort_keys.len())?;
+            while let Some((key_values, row)) = merge.next_entry()? {
+                writer.write(&key_values, &row)?;
+            }
+            runs.insert(0, writer.finish()?);
+        }
+        Ok(SortedRows::Merged(Merge::new(runs, sort_keys)?))
+    }
+
+    fn sort_rows(&mut self) {
+        let sort_keys = &self.sort_keys;
+        self.rows.sort_by(|(a, _), (b, _)| Self::compare(sort_keys, a, b));
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.sort_rows();
+        let mut writer = SortRunWriter::new(self.sort_keys.len())?;
+        for (key_values, row) in self.rows.drain(..) {
+            writer.write(&key_values, &row)?;
+        }
+        self.runs.push(writer.finish()?);
+        self.rows_size = 0;
+        Ok(())
+    }
+
+    fn estimated_size(key_values: &[Value], row: &TableRow) -> usize {
+        let value_size = |value: &Value| std::mem::size_of::<Value>() + match value {
+                                                                            Value::Text(text) => text.len(),
+                                                                            Value::Blob(bytes) => bytes.len(),
+                                                                            _ => 0
+                                                                        };
+        std::mem::size_of::<(Vec<Value>, TableRow)>() + key_values.iter().chain(row.column_values.iter()).map(value_size).sum::<usize>()
+    }
+}
+
+// The rows of a Sorter in order, straight from memory or merged from its runs.
+pub enum SortedRows {
+    InMemory(std::vec::IntoIter<(Vec<Value>, TableRow)>),
+    Merged(Merge)
+}
+
+impl Iterator for SortedRows {
+    type Item = io::Result<TableRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            SortedRows::InMemory(rows) => rows.next().map(|(_, row)| Ok(row)),
+            SortedRows::Merged(merge) => merge.next_entry().transpose().map(|entry| entry.map(|(_, row)| row))
+        }
+    }
+}
+
+// A k-way merge of sorted runs: a heap holds the next row of every run, and the smallest
+// is replaced by the row after it in the same run.
+pub struct Merge {
+    runs: Vec<SortRun>,
+    heap: BinaryHeap<MergeEntry>,
+    sort_keys: Rc<[SortKey]>
+}
+
+impl Merge {
+    fn new(mut runs: Vec<SortRun>, sort_keys: Rc<[SortKey]>) -> io::Result<Self> {
+        let mut heap = BinaryHeap::with_capacity(runs.len());
+        for (run_index, run) in runs.iter_mut().enumerate() {
+            if let Some((key_values, row)) = run.next_entry()? {
+                heap.push(MergeEntry { key_values, row, run_index, sort_keys: sort_keys.clone() });
+            }
+        }
+        Ok(Self { runs, heap, sort_keys })
+    }
+
+    fn next_entry(&mut self) -> io::Result<Option<(Vec<Value>, TableRow)>> {
+        let Some(entry) = self.heap.pop() else {
+            return Ok(None);
+        };
+        if let Some((key_values, row)) = self.runs[entry.run_index].next_entry()? {
+            self.heap.push(MergeEntry { key_values, row, run_index: entry.run_index, sort_keys: self.sort_keys.clone() });
+        }
+        Ok(Some((entry.key_values, entry.row)))
+    }
+}
+
+struct MergeEntry {
+    key_values: Vec<Value>,
+    row: TableRow,
+    run_index: usize,
+    sort_keys: Rc<[SortKey]>
+}
+
+// BinaryHeap pops the greatest entry, so the entry that sorts first, from the earliest run on a tie, is the greatest.
+impl Ord for MergeEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        Sorter::compare(&self.sort_keys, &other.key_values, &self.key_values).then(other.run_index.cmp(&self.run_index))
+    }
+}
+
+impl PartialOrd for MergeEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MergeEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MergeEntry {}
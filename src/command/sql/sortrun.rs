@@ -0,0 +1,110 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::models::dbfile::dbtable::tablepage::Record;
+use crate::models::dbfile::dbtable::{TableRow, Value};
+use crate::models::dbfile::header::TextEncoding;
+use crate::utils::varint::{encode_varint, parse_varint};
+
+static RUN_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// A temporary file holding sorted rows, deleted once it is dropped. Each row is written as the
+// varint size of a record, then the record: the values of the ORDER BY terms, the rowid and the
+// selected columns, in the format table b-tree cells use.
+pub struct SortRun {
+    path: PathBuf,
+    reader: BufReader<File>,
+    key_count: usize
+}
+
+pub struct SortRunWriter {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    key_count: usize
+}
+
+impl SortRunWriter {
+    pub fn new(key_count: usize) -> io::Result<Self> {
+        let file_number = RUN_FILE_COUNT.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("sqlite-fsr-sort-{}-{}", std::process::id(), file_number));
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        Ok(Self { path, writer: BufWriter::new(file), key_count })
+    }
+
+    pub fn write(&mut self, key_values: &[Value], row: &TableRow) -> io::Result<()> {
+        let values: Vec<Value> = key_values.iter()
+                                           .cloned()
+                                           .chain(std::iter::once(Value::Integer(row.row_id)))
+                                           .chain(row.column_values.iter().cloned())
+                                           .collect();
+        let record = Record::encode_column_values(&values);
+        self.writer.write_all(&encode_varint(record.len() as i64))?;
+        self.writer.write_all(&record)
+    }
+
+    // Rewinds the file to read back what was written.
+    pub fn finish(mut self) -> io::Result<SortRun> {
+        self.writer.flush()?;
+        let mut file = self.writer.get_ref().try_clone()?;
+        file.seek(SeekFrom::Start(0))?;
+        // The run deletes the file from now on
+        let path = std::mem::take(&mut self.path);
+        Ok(SortRun { path, reader: BufReader::new(file), key_count: self.key_count })
+    }
+}
+
+impl Drop for SortRunWriter {
+    fn drop(&mut self) {
+        if !self.path.as_os_str().is_empty() {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl SortRun {
+    // The next row in the run with the values of its ORDER BY terms, or None at its end.
+    pub fn next_entry(&mut self) -> io::Result<Option<(Vec<Value>, TableRow)>> {
+        let Some(record_size) = self.read_varint()? else {
+            return Ok(None);
+        };
+        let mut record = vec![0u8; record_size as usize];
+        self.reader.read_exact(&mut record)?;
+
+        let mut values = Record::decode_column_values(&record, TextEncoding::Utf8).map_err(|reason| io::Error::new(io::ErrorKind::InvalidData, reason))?;
+        if values.len() <= self.key_count {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "sorted row is missing its rowid"));
+        }
+        let column_values = values.split_off(self.key_count + 1);
+        let row_id = match values.pop() {
+                        Some(Value::Integer(row_id)) => row_id,
+                        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "sorted row has no integer rowid"))
+                     };
+        Ok(Some((values, TableRow { row_id, column_values })))
+    }
+
+    // None at the end of the file, which may only come before a varint starts.
+    fn read_varint(&mut self) -> io::Result<Option<i64>> {
+        let mut varint_bytes: Vec<u8> = Vec::with_capacity(9);
+        let mut byte = [0u8; 1];
+        while varint_bytes.len() < 9 {
+            if self.reader.read(&mut byte)? == 0 {
+                if varint_bytes.is_empty() {
+                    return Ok(None);
+                }
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+            }
+            varint_bytes.push(byte[0]);
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(Some(parse_varint(&varint_bytes).0))
+    }
+}
+
+impl Drop for SortRun {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
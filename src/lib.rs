@@ -2,6 +2,5 @@ pub mod utils;
 pub mod models;
 pub mod command;
 use crate::models::error::*;
-use crate::command::sql;
 pub use models::DBFile;
 
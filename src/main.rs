@@ -2,16 +2,20 @@ pub mod utils;
 pub mod models;
 pub mod command;
 use crate::models::error::*;
-use crate::command::sql;
 use std::path::PathBuf;
+use std::io::{BufWriter, ErrorKind, Write};
 use crate::models::DBFile;
 
 
 fn main()  {
     let args = std::env::args().collect::<Vec<String>>();
-    let output = run(&args);
-    match output {
-        Ok(o) => println!("{}", o),
+    let mut output = BufWriter::new(std::io::stdout().lock());
+    let result = run_to(&args, &mut output).and_then(|_| output.flush().map_err(RunError::from));
+    drop(output);
+    match result {
+        Ok(()) => (),
+        // The reader went away, e.g. the output was piped into head
+        Err(RunError::Output(e)) if e.kind() == ErrorKind::BrokenPipe => (),
         Err(e) => println!("ERROR: {}", e)
     }
     
@@ -19,8 +23,15 @@ fn main()  {
 
 
 
-
+#[cfg(test)]
 fn run(args: &[String]) -> Result<String, RunError> {
+    let mut output: Vec<u8> = Vec::new();
+    run_to(args, &mut output)?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+// Rows of a SELECT are written as they are read, so large results never sit in memory.
+fn run_to<W: Write>(args: &[String], output: &mut W) -> Result<(), RunError> {
     if args.len() <= 1 {
         return Err(CommandArgsError::MissingArgs)?;
     }
@@ -36,28 +47,42 @@ fn run(args: &[String]) -> Result<String, RunError> {
     
     let mut file = match DBFile::open(db_path) {
                         Ok(file) => file,
-                        Err(e) => return Err(CommandArgsError::Io(e))?
+                        Err(DatabaseError::Io(e)) => return Err(CommandArgsError::Io(e))?,
+                        Err(e) => return Err(e)?
                     };
 
-    let output = match command[0] {
-                        ".dbinfo" => {
-                            let (page_size, table_count) = file.get_dbinfo();
-                            Ok(format!("database page size: {}\nnumber of tables: {}", page_size, table_count))
-                        }
-                        ".tables" => {
-                            let tables = file.get_table_names();
-                            Ok(format!("{}", tables.join(" ")))
-                        }
-                        "SELECT" => {
-                            let result = file.execute(command);
-                            match result {
-                                Ok(rows) => Ok(format!("{}", rows)),
-                                Err(e) => Err(e)?
-                            }
-                        }
-                        _ => Err(CommandArgsError::InvalidCommand(command[0].to_owned()))?
-                    };
-    return output;
+    match command[0] {
+        ".dbinfo" => {
+            let (page_size, table_count) = file.get_dbinfo();
+            writeln!(output, "database page size: {}\nnumber of tables: {}", page_size, table_count)?;
+        }
+        ".tables" => {
+            let tables = file.get_table_names();
+            writeln!(output, "{}", tables.join(" "))?;
+        }
+        ".check" => {
+            let problems = file.integrity_check()?;
+            if problems.is_empty() {
+                writeln!(output, "ok")?;
+            }
+            for problem in problems {
+                writeln!(output, "{}", problem)?;
+            }
+        }
+        // SQL keywords are case-insensitive
+        first_word if first_word.eq_ignore_ascii_case("SELECT") => {
+            for row in file.execute_streaming(command)? {
+                writeln!(output, "{}", row?)?;
+            }
+        }
+        first_word if first_word.eq_ignore_ascii_case("EXPLAIN")
+                      && command.get(1..3).is_some_and(|words| words[0].eq_ignore_ascii_case("QUERY") && words[1].eq_ignore_ascii_case("PLAN")) => {
+            let plan = file.query_plan(command[3..].to_vec())?;
+            writeln!(output, "QUERY PLAN\n`--{}", plan)?;
+        }
+        _ => return Err(CommandArgsError::InvalidCommand(command[0].to_owned()))?
+    }
+    return Ok(());
 }
 
 
@@ -102,6 +127,37 @@ fn test_run_returns_correct_output() {
     assert_eq!(result, String::from("1 Granny Smith Light Green\n2 Fuji Red\n3 Honeycrisp Blush Red\n4 Golden Delicious Yellow\n"));
 }
 
+#[test]
+fn test_run_accepts_lowercase_queries() {
+    let args = vec![String::new(), String::from("./tests/assets/sample.db"), String::from("select * from apples where id = 2")];
+    let result = run(&args).unwrap();
+    assert_eq!(result, String::from("2 Fuji Red\n"));
+
+    let args = vec![String::new(), String::from("./tests/assets/indexed.db"), String::from("explain Query plan select name from people where age = 40")];
+    let result = run(&args).unwrap();
+    assert_eq!(result, String::from("QUERY PLAN\n`--SEARCH people USING INDEX people_by_age (age=?)\n"));
+}
+
+#[test]
+fn test_run_explains_query_plan() {
+    let args = vec![String::new(), String::from("./tests/assets/indexed.db"), String::from("EXPLAIN QUERY PLAN SELECT name FROM people WHERE age = 40")];
+    let result = run(&args).unwrap();
+    assert_eq!(result, String::from("QUERY PLAN\n`--SEARCH people USING INDEX people_by_age (age=?)\n"));
+}
+
+#[test]
+fn test_run_checks_database_integrity() {
+    let args = vec![String::new(), String::from("./tests/assets/sample.db"), String::from(".check")];
+    let result = run(&args).unwrap();
+    assert_eq!(result, String::from("ok\n"));
+}
+
+#[test]
+fn test_run_fails_when_file_is_not_a_database() {
+    let args = vec![String::new(), String::from("./Cargo.toml"), String::from(".dbinfo")];
+    let result = run(&args).unwrap_err();
+    assert!(matches!(result, RunError::Database(DatabaseError::NotADatabase)));
+}
 
 #[test]
 fn test_run_fails_when_invalid_filepath() {
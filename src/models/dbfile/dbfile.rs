@@ -1,49 +1,89 @@
+use std::borrow::Cow;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
 use std::path::{PathBuf, Path};
-use crate::models::dbfile::dbtable::TableRows;
-use crate::models::dbfile::schema::SchemaRAW;
+use crate::models::dbfile::dbtable::{TableRow, TableRows};
+use crate::models::dbfile::schema::{self, SchemaRAW};
 use crate::command::sql::parser::sql_statement::{SQLStatement, ToSQLStatement};
 use crate::models::dbfile::schema::schemarow::SchemaRow;
 use crate::command::sql;
+use crate::command::sql::{QueryPlan, SelectRows, DEFAULT_SORT_BUDGET};
 use crate::models::dbfile::dbtable::DBTable;
-use crate::models::error::SQLError;
-use std::ops::Deref;
-use std::ops::DerefMut;
-use std::io;
+use crate::models::dbfile::dbindex::DBIndex;
+use crate::models::dbfile::schema::SchemaIndex;
+use crate::models::dbfile::pager::Pager;
+use crate::models::dbfile::storage::Storage;
+use crate::models::dbfile::wal::Wal;
+use crate::models::dbfile::freelist::Freelist;
+use crate::models::dbfile::pagemap::PageMap;
+use crate::models::dbfile::integrity::IntegrityCheck;
+use crate::models::error::{DatabaseError, SQLError};
+use crate::models::dbfile::header::DatabaseHeader;
+use crate::models::dbfile::header::databaseheader::HEADER_SIZE;
 use crate::SQLCommandError;
 
 pub struct DBFile {
-    pub file: File,
-    pub schema: SchemaRAW
+    pub header: DatabaseHeader,
+    pub(crate) pager: Pager,
+    pub schema: SchemaRAW,
+    pub(crate) sort_budget: usize
 }
 
 impl DBFile {
-    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        let mut file = File::open(path)?;
-        let schema = Self::extract_raw_schema_data(&mut file);
-        Ok(Self { file, schema })
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DatabaseError> {
+        let file = File::open(path.as_ref())?;
+        Self::with_storage(file, Some(path.as_ref()))
     }
 
-    fn extract_raw_schema_data<R: Read + Seek>(file: &mut R) -> SchemaRAW {
-        // Read the 2-byte page size at offset 16
-        let mut page_size_buffer = [0u8; 2];
-        file.seek(SeekFrom::Start(16)).expect("seek failed");
-        file.read_exact(&mut page_size_buffer).expect("failed to read page size");
-        let page_size = u16::from_be_bytes(page_size_buffer);
+    // Like `open`, but the file is mapped into memory instead of read a page at a time.
+    // Reading a mapped file that another process truncates crashes the reader, so this is only
+    // for files nothing else writes to while they are open.
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> Result<Self, DatabaseError> {
+        let file = File::open(path.as_ref())?;
+        // SAFETY: the map is only ever read, and the caller rules out concurrent truncation
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Self::with_storage(mmap, Some(path.as_ref()))
+    }
+
+    // A database that is already in memory, e.g. downloaded bytes or an `include_bytes!` asset.
+    pub fn from_bytes<B: Into<Cow<'static, [u8]>>>(bytes: B) -> Result<Self, DatabaseError> {
+        Self::from_storage(bytes.into())
+    }
+
+    // A database read from any `Storage`. Without a path there is no WAL to find, so a
+    // database in WAL mode is read as of its last checkpoint.
+    pub fn from_storage<S: Storage + 'static>(storage: S) -> Result<Self, DatabaseError> {
+        Self::with_storage(storage, None)
+    }
 
-        // Read the full schema page
-        let mut schema_raw_buffer = vec![0; page_size as usize];
-        file.seek(SeekFrom::Start(0)).expect("seek failed");
-        file.read_exact(&mut schema_raw_buffer).expect("failed to read schema page");
+    fn with_storage<S: Storage + 'static>(mut storage: S, path: Option<&Path>) -> Result<Self, DatabaseError> {
+        let mut header = Self::extract_header(&mut storage)?;
+        let mut pager = Pager::new(storage, header.page_size as usize, header.usable_page_size(), header.text_encoding)?;
+
+        // In WAL mode the newest committed copy of page 1, header included, may only be in the WAL
+        let wal_path = path.filter(|_| header.is_wal_mode()).map(Wal::path_for);
+        if let Some(wal_path) = wal_path {
+            if let Some(wal) = Wal::open(&wal_path, header.page_size as usize)? {
+                pager.attach_wal(wal);
+                header = DatabaseHeader::from_bytes(&pager.get_page(1)?[..HEADER_SIZE])?;
+            }
+        }
+        let schema = schema::extract_raw_schema_data(&mut pager)?;
+        Ok(Self { header, pager, schema, sort_budget: DEFAULT_SORT_BUDGET })
+    }
 
-        SchemaRAW::from_bytes(&schema_raw_buffer)
+    fn extract_header(storage: &mut dyn Storage) -> Result<DatabaseHeader, DatabaseError> {
+        // Anything shorter than the 100-byte header can't be a database
+        let header_size = storage.size()?.min(HEADER_SIZE as u64) as usize;
+        let mut header_buffer = vec![0u8; header_size];
+        storage.read_exact_at(0, &mut header_buffer)?;
+        DatabaseHeader::from_bytes(&header_buffer)
     }
 
 
-    fn get_table(&mut self, table_name: &String) -> Result<DBTable, SQLCommandError> {
+    pub fn get_table(&mut self, table_name: &String) -> Result<DBTable<'_>, SQLError> {
         let target_table_schema_entry: SchemaRow = self.schema
-                                                        .to_schema_rows()
+                                                        .to_schema_rows()?
                                                         .into_iter()
                                                         .find(|entry| entry.table_name == *table_name)
                                                         .ok_or_else(|| SQLCommandError::UnknownTable(table_name.to_string()))?;
@@ -52,14 +92,48 @@ impl DBFile {
         return Ok(table);
     }
 
+    pub fn get_index(&mut self, index_name: &String) -> Result<DBIndex<'_>, SQLError> {
+        let target_index_schema_entry: SchemaIndex = self.schema
+                                                         .to_schema_indexes()?
+                                                         .into_iter()
+                                                         .find(|entry| entry.name == *index_name)
+                                                         .ok_or_else(|| SQLCommandError::UnknownIndex(index_name.to_string()))?;
+
+        return Ok(DBIndex::new(target_index_schema_entry, self));
+    }
+
+    // Reads pages of the file, through the write-ahead log and the page cache.
+    pub fn pager(&mut self) -> &mut Pager {
+        &mut self.pager
+    }
+
+    pub fn freelist(&mut self) -> Result<Freelist, DatabaseError> {
+        Freelist::read(&mut self.pager, self.header.freelist_trunk_page)
+    }
+
+    // Walks the whole file to find what every page is used for.
+    pub fn page_map(&mut self) -> Result<PageMap, DatabaseError> {
+        PageMap::build(&mut self.pager, &self.header, &self.schema)
+    }
+
+    // Like sqlite3's PRAGMA integrity_check: every problem found in the file, or none if it is sound.
+    pub fn integrity_check(&mut self) -> Result<Vec<String>, DatabaseError> {
+        IntegrityCheck::run(&mut self.pager, &self.header, &self.schema, self.sort_budget)
+    }
 
-    pub fn get_dbinfo(&self) -> (u16, usize) {
-        return (self.schema.page_size, self.schema.cells.len());
+    // How many bytes of rows an ORDER BY holds in memory before it writes them to a temporary file.
+    pub fn set_sort_budget(&mut self, budget: usize) {
+        self.sort_budget = budget;
+    }
+
+    pub fn get_dbinfo(&self) -> (u32, usize) {
+        return (self.schema.page_size, self.schema.records.len());
     }
 
     pub fn get_table_names(&self) -> Vec<String> {
         let mut table_names: Vec<String>  = Vec::from([]); 
-        for schemarow_header in self.schema.to_schema_rows() {
+        // The schema rows were already parsed successfully when the file was opened
+        for schemarow_header in self.schema.to_schema_rows().unwrap_or_default() {
             if !schemarow_header.name.starts_with("sqlite_") { 
                 table_names.push(schemarow_header.table_name); 
             }
@@ -68,28 +142,32 @@ impl DBFile {
     }
 
     pub fn execute<T: ToSQLStatement>(&mut self, sql_statement_string: T) -> Result<TableRows, SQLError> {
+        let results = self.execute_streaming(sql_statement_string)?.collect::<Result<Vec<TableRow>, SQLError>>()?;
+        Ok(TableRows::from(results))
+    }
+
+    // Like `execute`, but rows are read from the file only as the returned iterator is advanced.
+    pub fn execute_streaming<T: ToSQLStatement>(&mut self, sql_statement_string: T) -> Result<SelectRows<'_>, SQLError> {
         let sql_statement = sql_statement_string.to_sql_statment()?;
         match sql_statement {
             SQLStatement::Select(statement) => {
-                let mut table = self.get_table(&statement.table_name)?;
-                let results = sql::select(&mut table, statement);
-                Ok(TableRows::from(results))
+                let table = self.get_table(&statement.table_name)?;
+                sql::select_rows(table, statement)
             },
             _ => Err(SQLError::Command(SQLCommandError::UnsupportedCommand("Unsupported Command in statement".to_string())))
         }
     }
 
-}
-
-impl Deref for DBFile {
-    type Target = File;
-    fn deref(&self) -> &Self::Target {
-        &self.file
+    // Describes how `execute` would read the table for a SELECT, without running it.
+    pub fn query_plan<T: ToSQLStatement>(&mut self, sql_statement_string: T) -> Result<QueryPlan, SQLError> {
+        let sql_statement = sql_statement_string.to_sql_statment()?;
+        match sql_statement {
+            SQLStatement::Select(statement) => {
+                let table = self.get_table(&statement.table_name)?;
+                QueryPlan::for_statement(&table, &statement)
+            },
+            _ => Err(SQLError::Command(SQLCommandError::UnsupportedCommand("Unsupported Command in statement".to_string())))
+        }
     }
-}
 
-impl DerefMut for DBFile {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.file
-    }
-}
\ No newline at end of file
+}
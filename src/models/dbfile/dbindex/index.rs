@@ -0,0 +1,21 @@
+use crate::models::dbfile::dbindex::IndexCursor;
+use crate::models::dbfile::schema::SchemaIndex;
+use crate::DBFile;
+
+pub struct DBIndex <'a>{
+    pub description: SchemaIndex,
+    dbfile: &'a mut DBFile
+}
+
+impl DBIndex <'_> {
+    pub fn new(description: SchemaIndex, dbfile: &mut DBFile) -> DBIndex<'_> {
+        DBIndex { description, dbfile }
+    }
+
+    pub fn cursor(&mut self) -> IndexCursor<'_> {
+        let descending: Vec<bool> = self.description.sql.columns.iter()
+                                                        .map(|column| column.descending)
+                                                        .collect();
+        IndexCursor::new(&mut self.dbfile.pager, self.description.rootpage, descending)
+    }
+}
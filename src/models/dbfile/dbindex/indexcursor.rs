@@ -0,0 +1,199 @@
+use std::cmp::Ordering;
+use crate::command::sql::Constraint;
+use crate::models::dbfile::dbindex::indexpage::{IndexPage, IndexRecord};
+use crate::models::dbfile::dbtable::tablepage::{Value, MAX_BTREE_DEPTH};
+use crate::models::dbfile::header::TextEncoding;
+use crate::models::dbfile::pager::Pager;
+use crate::models::error::DatabaseError;
+
+// One page on the path from the root to the cursor position. For interior pages `descended`
+// records whether the child left of `cell_index` has been visited yet.
+struct CursorFrame {
+    page: IndexPage,
+    cell_index: usize,
+    descended: bool
+}
+
+// Walks the entries of an index b-tree in key order, starting either at the first entry
+// or at the first entry whose key is greater than or equal to a sought key.
+pub struct IndexCursor<'a> {
+    pager: &'a mut Pager,
+    rootpage: u32,
+    descending: Vec<bool>,
+    has_rowid: bool,
+    text_encoding: TextEncoding,
+    stack: Vec<CursorFrame>,
+    positioned: bool
+}
+
+impl<'a> IndexCursor<'a> {
+    // `descending` holds the sort order of each indexed column, as declared in CREATE INDEX.
+    pub fn new(pager: &'a mut Pager, rootpage: u32, descending: Vec<bool>) -> Self {
+        let text_encoding = pager.text_encoding;
+        IndexCursor { pager, rootpage, descending, has_rowid: true, text_encoding, stack: Vec::new(), positioned: false }
+    }
+
+    // For the b-tree of a WITHOUT ROWID table, whose entries are whole rows keyed by the primary key.
+    pub fn without_rowid(pager: &'a mut Pager, rootpage: u32, descending: Vec<bool>) -> Self {
+        IndexCursor { has_rowid: false, ..Self::new(pager, rootpage, descending) }
+    }
+
+    pub fn rewind(&mut self) -> Result<(), DatabaseError> {
+        self.seek(&[])
+    }
+
+    // Positions the cursor so the next entry returned is the first one whose leading key
+    // columns are greater than or equal to `key`. A shorter key matches on a prefix of the columns.
+    pub fn seek(&mut self, key: &[Value]) -> Result<(), DatabaseError> {
+        self.stack.clear();
+        self.positioned = true;
+
+        let mut page_number = self.rootpage;
+        loop {
+            let page = self.load_page(page_number)?;
+            let cell_index = self.lower_bound(&page, key);
+            match &page {
+                IndexPage::Leaf(_) => {
+                    self.stack.push(CursorFrame { page, cell_index, descended: false });
+                    return Ok(());
+                },
+                IndexPage::Interior(interior_page) => {
+                    page_number = interior_page.child_page_number(cell_index);
+                    self.stack.push(CursorFrame { page, cell_index, descended: true });
+                }
+            }
+        }
+    }
+
+    // Every entry whose leading key columns equal `key`.
+    pub fn lookup(&mut self, key: &[Value]) -> Result<Vec<IndexRecord>, DatabaseError> {
+        self.seek(key)?;
+        let mut records = Vec::new();
+        while let Some(record) = self.next_record()? {
+            if self.compare_key(&record, key) != Ordering::Equal {
+                break;
+            }
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    // Positions the cursor at the first entry that can match `prefix` and `range` (see `next_in_range`),
+    // starting from the highest lower bound in `range`.
+    pub fn seek_range(&mut self, prefix: &[Value], range: &[Constraint]) -> Result<(), DatabaseError> {
+        let lower_bound = range.iter()
+                               .filter(|constraint| constraint.is_lower_bound())
+                               .map(|constraint| &constraint.value)
+                               .max_by(|a, b| a.compare(b));
+        let mut seek_key = prefix.to_vec();
+        seek_key.extend(lower_bound.cloned());
+        self.seek(&seek_key)
+    }
+
+    // The next entry whose leading key columns equal `prefix` and whose following column satisfies
+    // every constraint in `range`, or None once the cursor has moved past all such entries.
+    pub fn next_in_range(&mut self, prefix: &[Value], range: &[Constraint]) -> Result<Option<IndexRecord>, DatabaseError> {
+        while let Some(record) = self.next_record()? {
+            if self.compare_key(&record, prefix) != Ordering::Equal {
+                return Ok(None);
+            }
+            if range.is_empty() {
+                return Ok(Some(record));
+            }
+
+            // NULLs sort first and never match, so they are stepped over
+            let value = record.key.get(prefix.len()).unwrap_or(&Value::Null);
+            if value.is_null() {
+                continue;
+            }
+            let failed_constraints: Vec<&Constraint> = range.iter().filter(|constraint| !constraint.matches(value, self.text_encoding)).collect();
+            if failed_constraints.iter().any(|constraint| constraint.is_upper_bound()) {
+                // Past the end of the matching range
+                return Ok(None);
+            }
+            if failed_constraints.is_empty() {
+                return Ok(Some(record));
+            }
+            // Entries equal to a > bound
+        }
+        Ok(None)
+    }
+
+    pub fn compare_key(&self, record: &IndexRecord, key: &[Value]) -> Ordering {
+        for (column_index, (record_value, key_value)) in record.key.iter().zip(key.iter()).enumerate() {
+            let ordering = record_value.compare_encoded(key_value, self.text_encoding);
+            let ordering = if self.descending.get(column_index) == Some(&true) { ordering.reverse() } else { ordering };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+
+    fn lower_bound(&self, page: &IndexPage, key: &[Value]) -> usize {
+        let (mut low, mut high) = (0, page.cell_count());
+        while low < high {
+            let middle = (low + high) / 2;
+            if self.compare_key(page.record(middle), key) == Ordering::Less {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    }
+
+    fn load_page(&mut self, page_number: u32) -> Result<IndexPage, DatabaseError> {
+        if self.stack.len() > MAX_BTREE_DEPTH {
+            return Err(DatabaseError::corrupt(page_number, 0, "b-tree is too deep"));
+        }
+        IndexPage::from_page(page_number, self.pager, self.has_rowid)
+    }
+
+    pub fn next_record(&mut self) -> Result<Option<IndexRecord>, DatabaseError> {
+        if !self.positioned {
+            self.rewind()?;
+        }
+
+        loop {
+            let Some(frame) = self.stack.last_mut() else { return Ok(None) };
+            let cell_count = frame.page.cell_count();
+
+            let child_page_number = match &frame.page {
+                IndexPage::Interior(interior_page) if !frame.descended => {
+                    frame.descended = true;
+                    interior_page.child_page_number(frame.cell_index)
+                },
+                _ if frame.cell_index < cell_count => {
+                    // Interior entries come after their left subtree, leaf entries in cell order
+                    frame.descended = false;
+                    frame.cell_index += 1;
+                    return Ok(Some(frame.page.record(frame.cell_index - 1).clone()));
+                },
+                _ => {
+                    self.stack.pop();
+                    continue;
+                }
+            };
+
+            let child_page = self.load_page(child_page_number)?;
+            self.stack.push(CursorFrame { page: child_page, cell_index: 0, descended: false });
+        }
+    }
+}
+
+impl Iterator for IndexCursor<'_> {
+    type Item = Result<IndexRecord, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_record() {
+            Ok(record) => record.map(Ok),
+            Err(e) => {
+                // Stop after the first error rather than returning it forever
+                self.stack.clear();
+                self.positioned = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
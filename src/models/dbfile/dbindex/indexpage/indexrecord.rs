@@ -0,0 +1,25 @@
+use crate::models::dbfile::dbtable::tablepage::{Record, Value};
+use crate::models::dbfile::header::TextEncoding;
+
+// An index entry: the indexed column values followed by the rowid of the table row they belong to.
+// The entries of a WITHOUT ROWID table have no rowid; their key is the whole row and `row_id` is 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexRecord {
+    pub key: Vec<Value>,
+    pub row_id: i64
+}
+
+impl IndexRecord {
+    pub fn from_bytes(record_data: &[u8], has_rowid: bool, text_encoding: TextEncoding) -> Result<Self, String> {
+        let mut key = Record::decode_column_values(record_data, text_encoding)?;
+        if !has_rowid {
+            return Ok(Self { key, row_id: 0 });
+        }
+        let row_id = match key.pop() {
+                        Some(Value::Integer(row_id)) => row_id,
+                        Some(value) => return Err(format!("index record ends with {:?} instead of a rowid", value)),
+                        None => return Err("index record is empty".to_string())
+                     };
+        Ok(Self { key, row_id })
+    }
+}
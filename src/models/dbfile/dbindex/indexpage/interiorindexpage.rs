@@ -0,0 +1,59 @@
+use crate::utils::varint::parse_varint;
+use crate::models::dbfile::dbindex::indexpage::IndexRecord;
+use crate::models::dbfile::dbtable::tablepage::overflow::{index_local_payload_size, read_cell_payload};
+use crate::models::dbfile::pager::Pager;
+use crate::models::error::DatabaseError;
+
+#[derive(Debug)]
+pub struct InteriorIndexPage {
+    pub page_number: u32,
+    pub header: [u8; 12],
+    pub cell_pointer_array: Vec<u16>,
+    pub cells: Vec<(u32, IndexRecord)>,     // left child page and the entry that follows its subtree
+    pub right_most_page_number: u32
+}
+
+impl InteriorIndexPage {
+    pub fn from_bytes(data: &[u8], page_number: u32, pager: &mut Pager, has_rowid: bool) -> Result<Self, DatabaseError> {
+        let header: [u8; 12] = data[0..12].try_into().unwrap();
+        let cell_count = u16::from_be_bytes([header[3], header[4]]);
+        let right_most_page_number = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+
+        let cell_pointer_array_end = 12 + (cell_count as usize * 2);
+        let cell_pointer_array: Vec<u16> = data.get(12..cell_pointer_array_end)
+                                            .ok_or_else(|| DatabaseError::corrupt(page_number, 0, "cell pointer array runs past the end of the page"))?
+                                            .chunks_exact(2)
+                                            .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+                                            .collect();
+
+        let mut cells: Vec<(u32, IndexRecord)> = Vec::new();
+        for &cell_pointer in cell_pointer_array.iter() {
+            let cell_pointer = cell_pointer as usize;
+            if cell_pointer < cell_pointer_array_end || cell_pointer + 4 >= pager.usable_page_size {
+                return Err(DatabaseError::corrupt(page_number, cell_pointer, "cell pointer out of bounds"));
+            }
+            let mut offset = cell_pointer;
+
+            let left_child_page_number = u32::from_be_bytes([data[offset], data[offset+1], data[offset+2], data[offset+3]]);
+            offset += 4;
+
+            let (payload_size, payload_varint_len) = parse_varint(&data[offset..]);
+            offset += payload_varint_len;
+
+            let payload = read_cell_payload(data, page_number, cell_pointer, offset, payload_size, index_local_payload_size, pager)?;
+            let index_record = IndexRecord::from_bytes(&payload, has_rowid, pager.text_encoding)
+                                           .map_err(|reason| DatabaseError::corrupt(page_number, cell_pointer, reason))?;
+            cells.push((left_child_page_number, index_record));
+        }
+        Ok(Self { page_number, header, cell_pointer_array, cells, right_most_page_number })
+    }
+
+    // Page holding the entries that sort before the cell at `cell_index`, or after every cell
+    // when `cell_index` is one past the last cell.
+    pub fn child_page_number(&self, cell_index: usize) -> u32 {
+        match self.cells.get(cell_index) {
+            Some((left_child_page_number, _)) => *left_child_page_number,
+            None => self.right_most_page_number
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::utils::varint::parse_varint;
+use crate::models::dbfile::dbindex::indexpage::IndexRecord;
+use crate::models::dbfile::dbtable::tablepage::overflow::{index_local_payload_size, read_cell_payload};
+use crate::models::dbfile::pager::Pager;
+use crate::models::error::DatabaseError;
+
+#[derive(Debug)]
+pub struct LeafIndexPage {
+    pub page_number: u32,
+    pub header: [u8; 8],
+    pub cell_pointer_array: Vec<u16>,
+    pub cells: Vec<IndexRecord>
+}
+
+impl LeafIndexPage {
+    pub fn from_bytes(data: &[u8], page_number: u32, pager: &mut Pager, has_rowid: bool) -> Result<Self, DatabaseError> {
+        let header: [u8; 8] = data[0..8].try_into().unwrap();
+        let cell_count = u16::from_be_bytes([header[3], header[4]]);
+
+        let cell_pointer_array_end = 8 + (cell_count as usize * 2);
+        let cell_pointer_array: Vec<u16> = data.get(8..cell_pointer_array_end)
+                                            .ok_or_else(|| DatabaseError::corrupt(page_number, 0, "cell pointer array runs past the end of the page"))?
+                                            .chunks_exact(2)
+                                            .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+                                            .collect();
+
+        let mut cells: Vec<IndexRecord> = Vec::new();
+        for &cell_pointer in cell_pointer_array.iter() {
+            let cell_pointer = cell_pointer as usize;
+            if cell_pointer < cell_pointer_array_end || cell_pointer >= pager.usable_page_size {
+                return Err(DatabaseError::corrupt(page_number, cell_pointer, "cell pointer out of bounds"));
+            }
+
+            // An index leaf cell is the payload size followed by the payload, with no rowid in between
+            let (payload_size, payload_varint_len) = parse_varint(&data[cell_pointer..]);
+            let payload = read_cell_payload(data, page_number, cell_pointer, cell_pointer + payload_varint_len, payload_size, index_local_payload_size, pager)?;
+            let index_record = IndexRecord::from_bytes(&payload, has_rowid, pager.text_encoding)
+                                           .map_err(|reason| DatabaseError::corrupt(page_number, cell_pointer, reason))?;
+            cells.push(index_record);
+        }
+        Ok(Self { page_number, header, cell_pointer_array, cells })
+    }
+}
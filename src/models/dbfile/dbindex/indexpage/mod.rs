@@ -0,0 +1,45 @@
+use crate::models::dbfile::pager::Pager;
+use crate::models::error::DatabaseError;
+
+pub mod interiorindexpage;
+pub use interiorindexpage::InteriorIndexPage;
+
+pub mod leafindexpage;
+pub use leafindexpage::LeafIndexPage;
+
+pub mod indexrecord;
+pub use indexrecord::IndexRecord;
+
+#[derive(Debug)]
+pub enum IndexPage {
+    Leaf(LeafIndexPage),
+    Interior(InteriorIndexPage),
+}
+
+impl IndexPage {
+    // Index b-trees never start on page 1, so there is no file header to skip.
+    // `has_rowid` is false for the b-tree of a WITHOUT ROWID table.
+    pub fn from_page(page_number: u32, pager: &mut Pager, has_rowid: bool) -> Result<Self, DatabaseError> {
+        let page_buffer = pager.get_page(page_number)?;
+
+        match page_buffer[0] {
+            0x0A => Ok(IndexPage::Leaf(LeafIndexPage::from_bytes(&page_buffer, page_number, pager, has_rowid)?)),
+            0x02 => Ok(IndexPage::Interior(InteriorIndexPage::from_bytes(&page_buffer, page_number, pager, has_rowid)?)),
+            e => Err(DatabaseError::corrupt(page_number, 0, format!("page type {} is not an index b-tree page", e))),
+        }
+    }
+
+    pub fn cell_count(&self) -> usize {
+        match self {
+            IndexPage::Leaf(page) => page.cells.len(),
+            IndexPage::Interior(page) => page.cells.len(),
+        }
+    }
+
+    pub fn record(&self, cell_index: usize) -> &IndexRecord {
+        match self {
+            IndexPage::Leaf(page) => &page.cells[cell_index],
+            IndexPage::Interior(page) => &page.cells[cell_index].1,
+        }
+    }
+}
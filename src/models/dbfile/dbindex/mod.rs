@@ -0,0 +1,6 @@
+pub mod indexpage;
+pub use indexpage::{IndexPage, IndexRecord};
+pub mod indexcursor;
+pub use indexcursor::IndexCursor;
+pub mod index;
+pub use index::DBIndex;
@@ -1,6 +1,11 @@
 pub mod tablepage;
+pub use tablepage::{Affinity, Value};
 pub mod table;
 pub use table::DBTable;
+pub mod tablecursor;
+pub use tablecursor::TableCursor;
+pub mod withoutrowidcursor;
+pub use withoutrowidcursor::WithoutRowidCursor;
 
 pub mod tablerow;
 pub use tablerow::{TableRow, TableRows};
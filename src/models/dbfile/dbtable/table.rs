@@ -1,7 +1,11 @@
-use crate::{command::sql::parser::sql_statement::{SelectStatement}, models::dbfile::dbtable::tablepage::Record, models::dbfile::schema::SchemaRow, DBFile};
-use crate::models::dbfile::dbtable::tablepage::*;
-use crate::models::dbfile::dbtable::tablepage::Table;
-use std::io::{Seek, SeekFrom, Read};
+use crate::{models::dbfile::schema::SchemaRow, DBFile};
+use crate::command::sql::Constraint;
+use crate::models::dbfile::dbtable::tablepage::Value;
+use crate::models::dbfile::dbtable::{TableCursor, WithoutRowidCursor};
+use crate::models::dbfile::dbindex::IndexCursor;
+use crate::models::dbfile::schema::SchemaIndex;
+use crate::models::dbfile::header::TextEncoding;
+use crate::models::error::DatabaseError;
 
 pub struct DBTable <'a>{
     pub description: SchemaRow,
@@ -9,28 +13,57 @@ pub struct DBTable <'a>{
 }
 
 
-impl DBTable <'_> {
+impl<'a> DBTable <'a> {
     pub fn new(description: SchemaRow, dbfile: &mut DBFile) -> DBTable {
         DBTable { description, dbfile }
     }
 
-    pub fn to_table_rows(&mut self, statement: &SelectStatement) -> Vec<Record> {
-        
-        let table_rootpage_offset = self.dbfile.schema.page_size as u64 * (self.description.rootpage-1) as u64;
-        self.dbfile.seek(SeekFrom::Start(table_rootpage_offset)).expect("seek failed");
+    pub fn sort_budget(&self) -> usize {
+        self.dbfile.sort_budget
+    }
 
-        let mut table_page_buffer = vec![0; self.dbfile.schema.page_size as usize];
-        self.dbfile.read_exact(&mut table_page_buffer).expect("failed to read table page into buffer");
+    pub fn text_encoding(&self) -> TextEncoding {
+        self.dbfile.pager.text_encoding
+    }
 
-        let mut table_page: TablePage = match table_page_buffer[0] {
-                                            0x0D => TablePage::Leaf(LeafTablePage::from_bytes(&table_page_buffer)),
-                                            0x05 => TablePage::Interior(InteriorTablePage::from_bytes(&table_page_buffer, self.dbfile)),
-                                            _    => panic!("unsupported page type"),
-                                        };
+    pub fn indexes(&self) -> Result<Vec<SchemaIndex>, DatabaseError> {
+        let indexes = self.dbfile.schema.to_schema_indexes()?
+                                        .into_iter()
+                                        .filter(|index| index.table_name == self.description.table_name)
+                                        .collect();
+        return Ok(indexes);
+    }
 
-        
-        let records: Vec<Record> = table_page.to_table_records(&statement, &self.description.sql);
+    pub fn cursor(&mut self) -> TableCursor<'_> {
+        TableCursor::new(&mut self.dbfile.pager, self.description.rootpage, self.description.sql.clone())
+    }
 
-        return records;
+    // Hands the table over to a cursor that can outlive this DBTable, e.g. to stream rows to the caller.
+    pub fn into_cursor(self) -> TableCursor<'a> {
+        TableCursor::new(&mut self.dbfile.pager, self.description.rootpage, self.description.sql)
     }
-}
\ No newline at end of file
+
+    // WITHOUT ROWID tables are stored in an index b-tree, which a TableCursor cannot read.
+    pub fn without_rowid_cursor(&mut self) -> WithoutRowidCursor<'_> {
+        WithoutRowidCursor::new(&mut self.dbfile.pager, self.description.rootpage, self.description.sql.clone())
+    }
+
+    pub fn into_without_rowid_cursor(self) -> WithoutRowidCursor<'a> {
+        WithoutRowidCursor::new(&mut self.dbfile.pager, self.description.rootpage, self.description.sql)
+    }
+
+    // Rowids of the rows whose leading index columns equal `prefix` and whose next column
+    // satisfies every constraint in `range`, in index order.
+    pub fn index_row_ids(&mut self, index: &SchemaIndex, prefix: &[Value], range: &[Constraint]) -> Result<Vec<i64>, DatabaseError> {
+        let descending: Vec<bool> = index.sql.columns.iter().map(|column| column.descending).collect();
+        let mut cursor = IndexCursor::new(&mut self.dbfile.pager, index.rootpage, descending);
+
+        cursor.seek_range(prefix, range)?;
+
+        let mut row_ids: Vec<i64> = Vec::new();
+        while let Some(index_record) = cursor.next_in_range(prefix, range)? {
+            row_ids.push(index_record.row_id);
+        }
+        return Ok(row_ids);
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+use crate::command::sql::parser::sql_statement::CreateTableStatement;
+use crate::models::dbfile::dbtable::tablepage::{InteriorTablePage, LeafTablePage, Record, TablePage, MAX_BTREE_DEPTH};
+use crate::models::dbfile::pager::Pager;
+use crate::models::error::DatabaseError;
+
+// Walks the rows of a table b-tree in rowid order, holding only the pages on the path
+// from the root to the current leaf, so a scan never has more than one leaf in memory.
+pub struct TableCursor<'a> {
+    pager: &'a mut Pager,
+    rootpage: u32,
+    pub table_description: CreateTableStatement,
+    stack: Vec<(InteriorTablePage, usize)>,     // interior pages above the leaf, with the next child to visit
+    leaf: Option<(LeafTablePage, usize)>,       // current leaf and the next cell to return
+    visited_pages: HashSet<u32>,
+    positioned: bool
+}
+
+impl<'a> TableCursor<'a> {
+    pub fn new(pager: &'a mut Pager, rootpage: u32, table_description: CreateTableStatement) -> Self {
+        TableCursor { pager, rootpage, table_description, stack: Vec::new(), leaf: None, visited_pages: HashSet::new(), positioned: false }
+    }
+
+    pub fn rewind(&mut self) -> Result<(), DatabaseError> {
+        self.seek(i64::MIN)
+    }
+
+    // Positions the cursor so the next row returned is the first one whose rowid is
+    // greater than or equal to `row_id`, reading one page per level of the b-tree.
+    pub fn seek(&mut self, row_id: i64) -> Result<(), DatabaseError> {
+        self.stack.clear();
+        self.leaf = None;
+        self.visited_pages.clear();
+        self.positioned = true;
+
+        let mut page_number = self.rootpage;
+        loop {
+            match self.load_page(page_number)? {
+                TablePage::Leaf(leaf_page) => {
+                    let cell_index = leaf_page.cell_index_for(row_id);
+                    self.leaf = Some((leaf_page, cell_index));
+                    return Ok(());
+                },
+                TablePage::Interior(interior_page) => {
+                    let child_index = interior_page.child_index_for(row_id);
+                    let child_page_number = interior_page.child_page_number(child_index);
+                    self.stack.push((interior_page, child_index + 1));
+                    match child_page_number {
+                        Some(child_page_number) => page_number = child_page_number,
+                        None => return Ok(())
+                    }
+                }
+            }
+        }
+    }
+
+    // A page showing up twice in one walk means the child pointers form a cycle.
+    fn load_page(&mut self, page_number: u32) -> Result<TablePage, DatabaseError> {
+        if self.stack.len() > MAX_BTREE_DEPTH {
+            return Err(DatabaseError::corrupt(page_number, 0, "b-tree is too deep"));
+        }
+        if !self.visited_pages.insert(page_number) {
+            return Err(DatabaseError::corrupt(page_number, 0, "page appears more than once in the b-tree"));
+        }
+        TablePage::from_page(page_number, self.pager)
+    }
+
+    pub fn next_record(&mut self) -> Result<Option<Record>, DatabaseError> {
+        if !self.positioned {
+            self.rewind()?;
+        }
+
+        loop {
+            if let Some((leaf_page, cell_index)) = &mut self.leaf {
+                if *cell_index < leaf_page.cells.len() {
+                    *cell_index += 1;
+                    return leaf_page.to_table_record(*cell_index - 1, &self.table_description).map(Some);
+                }
+                self.leaf = None;
+            }
+
+            // Climb to the nearest interior page with a child left, then go down its left-most path
+            let Some((interior_page, child_index)) = self.stack.last_mut() else { return Ok(None) };
+            let Some(child_page_number) = interior_page.child_page_number(*child_index) else {
+                self.stack.pop();
+                continue;
+            };
+            *child_index += 1;
+
+            match self.load_page(child_page_number)? {
+                TablePage::Leaf(leaf_page) => self.leaf = Some((leaf_page, 0)),
+                TablePage::Interior(interior_page) => self.stack.push((interior_page, 0))
+            }
+        }
+    }
+}
+
+impl Iterator for TableCursor<'_> {
+    type Item = Result<Record, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_record() {
+            Ok(record) => record.map(Ok),
+            Err(e) => {
+                // Stop after the first error rather than returning it forever
+                self.stack.clear();
+                self.leaf = None;
+                self.positioned = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
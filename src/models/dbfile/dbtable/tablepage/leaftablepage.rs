@@ -1,27 +1,40 @@
-use crate::command::sql::parser::sql_statement::{CreateTableStatement, SelectStatement};
+use crate::command::sql::parser::sql_statement::CreateTableStatement;
 use crate::utils::varint::parse_varint;
 use crate::models::dbfile::dbtable::tablepage::Record;
-use crate::models::dbfile::dbtable::tablepage::Table;
+use crate::models::dbfile::dbtable::tablepage::Value;
+use crate::models::dbfile::dbtable::tablepage::overflow::{local_payload_size, read_cell_payload};
+use crate::models::dbfile::header::TextEncoding;
+use crate::models::dbfile::pager::Pager;
+use crate::models::error::DatabaseError;
 
 #[derive(Debug)]
 pub struct LeafTablePage {
+    pub page_number: u32,
     pub header: [u8; 8],
-    pub cells: Vec<Vec<u8>>
+    pub cell_pointer_array: Vec<u16>,
+    pub cells: Vec<(i64, Vec<u8>)>,
+    pub text_encoding: TextEncoding     // cells are decoded lazily, so the page keeps the database encoding
 }
 
 impl LeafTablePage {
-    pub fn from_bytes(data: &[u8]) -> Self {
-        let header: [u8; 8] = data[0..8].try_into().unwrap();
+    pub fn from_bytes(data: &[u8], page_number: u32, pager: &mut Pager) -> Result<Self, DatabaseError> {
+        let header_offset = if page_number == 1 { 100 } else { 0 };
+        let header: [u8; 8] = data[header_offset..header_offset+8].try_into().unwrap();
         let cell_count = u16::from_be_bytes([header[3], header[4]]);
 
-        let cell_pointer_array: Vec<u16> = data[8..(8 + (cell_count * 2) as usize)]
+        let cell_pointer_array_end = header_offset + 8 + (cell_count as usize * 2);
+        let cell_pointer_array: Vec<u16> = data.get(header_offset+8..cell_pointer_array_end)
+                                            .ok_or_else(|| DatabaseError::corrupt(page_number, header_offset, "cell pointer array runs past the end of the page"))?
                                             .chunks_exact(2)
                                             .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
                                             .collect();
         
-        let mut cells: Vec<Vec<u8>> = Vec::new();
-        for index in 0..cell_pointer_array.len() {
-            let cell_pointer = cell_pointer_array[index] as usize;
+        let mut cells: Vec<(i64, Vec<u8>)> = Vec::new();
+        for &cell_pointer in cell_pointer_array.iter() {
+            let cell_pointer = cell_pointer as usize;
+            if cell_pointer < cell_pointer_array_end || cell_pointer >= pager.usable_page_size {
+                return Err(DatabaseError::corrupt(page_number, cell_pointer, "cell pointer out of bounds"));
+            }
             let mut offset = cell_pointer;
 
             // 1) parse payload size
@@ -29,102 +42,37 @@ impl LeafTablePage {
             offset += payload_varint_len;
 
             // 2) parse rowid
-            let (_rowid, rowid_varint_len) = parse_varint(&data[offset..]);
+            let (row_id, rowid_varint_len) = parse_varint(&data[offset..]);
             offset += rowid_varint_len;
 
-            // 3) now offset points to start of payload
-            let cell_end = offset + payload_size as usize;
-
-            // 4) slice the whole cell (from cell_pointer, not just payload)
-            let cell = data[cell_pointer .. cell_end].to_vec();
-            cells.push(cell);
+            // 3) now offset points to start of payload, the rest of which may live on overflow pages
+            let payload = read_cell_payload(data, page_number, cell_pointer, offset, payload_size, local_payload_size, pager)?;
+            cells.push((row_id, payload));
         }
-        Self { header, cells }
+        Ok(Self { page_number, header, cell_pointer_array, cells, text_encoding: pager.text_encoding })
     }
 }
 
-impl Table for LeafTablePage {
-
-    fn to_table_records(&mut self, statement: &SelectStatement, table_description: &CreateTableStatement) -> Vec<Record> {
-        let mut table_rows: Vec<Record> = Vec::new();
-        for cells_index in 0..self.cells.len() {
-            let cell = &self.cells[cells_index];
-            let (_, cell_size_varint_size) = parse_varint(&cell[0..9]);
-            let (row_id, row_id_varint_size) = parse_varint(&cell[cell_size_varint_size..cell_size_varint_size+9]);
-            
-            let cell_content_offset = cell_size_varint_size + row_id_varint_size;
-            let record_data = &cell[cell_content_offset..];
-
-            let (record_header_size, record_body_size, mut column_value_sizes): (usize, usize, Vec<i64>) = Self::extract_record_description(record_data);
-            
-            let record_body: &[u8] = &record_data[record_header_size..];
-            let mut column_values = Self::extract_record_column_values(&column_value_sizes, record_body_size, record_body);
-            if let Some(integer_id_column_index) = table_description.integer_primary_key_column {
-                column_value_sizes[integer_id_column_index] = row_id_varint_size as i64;
-                column_values[integer_id_column_index] = row_id.to_string().as_bytes().to_vec()
-            }
-
-            let column_descriptions: Vec<(&String, &i64)> = table_description.columns.iter().zip(column_value_sizes.iter()).collect();
-            let mut column_descriptions_with_values: Vec<_>  = column_descriptions.iter().zip(column_values.iter()).collect();
-            if let Some(statement_columns) = &statement.columns {
-                let selected_columns: Vec<_> = column_descriptions_with_values.iter()
-                                                                              .filter(|(column_description, _)| statement_columns.contains(column_description.0))
-                                                                              .collect();
-               
-                let selected_columns_values: Vec<Vec<u8>> = selected_columns.iter().map(|column| column.1.clone()).collect();
-
-                let table_row = Record { row_id, column_values: selected_columns_values};
-                table_rows.push(table_row);        
-            } else {
-                let table_row = Record { row_id, column_values};
-                table_rows.push(table_row);        
-            }
-
-
-
-        }
-        return table_rows;
-    }
-
-
-}
-
-
 impl LeafTablePage {
-    fn extract_record_description(record_data: &[u8]) -> (usize, usize, Vec<i64>) {
-        let mut record_header_values: Vec<i64> = Vec::new();
-        let (record_header_size, record_header_varint_size) = parse_varint(&record_data[0..9]);
-        let mut record_body_size = 0;
-        let mut record_header_value_index = record_header_varint_size;
-        while record_header_value_index < record_header_size as usize {
-            let end = (record_header_value_index+9).min(record_data.len());
-            let (mut record_header_value_size, record_header_value_varint_size)  = parse_varint(&record_data[record_header_value_index..end]);
-            if [0, 8, 9, 12, 13].contains(&record_header_value_size) {
-                record_header_value_size = 0;
-            } else if (record_header_value_size >= 12) & (record_header_value_size % 2 == 0) {
-                record_header_value_size = (record_header_value_size-12)/2
-            } else if (record_header_value_size >= 13) & (record_header_value_size % 2 != 0) {
-                record_header_value_size = (record_header_value_size-13)/2
-            }
-            record_header_values.push(record_header_value_size);
-            record_body_size += record_header_value_size;
-            record_header_value_index += record_header_value_varint_size;
+    // Decodes the cell at `cell_index` into a record holding every column of the table.
+    pub fn to_table_record(&self, cell_index: usize, table_description: &CreateTableStatement) -> Result<Record, DatabaseError> {
+        let (row_id, record_data) = &self.cells[cell_index];
+        let row_id = *row_id;
+        let mut column_values = Record::decode_column_values(record_data, self.text_encoding)
+                                       .map_err(|reason| DatabaseError::corrupt(self.page_number, self.cell_pointer_array[cell_index] as usize, reason))?;
+        // Rows written before an ALTER TABLE ADD COLUMN have fewer values than the table has columns;
+        // the columns they lack read as their DEFAULT.
+        if column_values.len() < table_description.columns.len() {
+            column_values.extend_from_slice(&table_description.column_defaults[column_values.len()..]);
         }
-
-        (record_header_size as usize, record_body_size as usize, record_header_values)
+        if let Some(integer_id_column_index) = table_description.integer_primary_key_column {
+            column_values[integer_id_column_index] = Value::Integer(row_id)
+        }
+        return Ok(Record { row_id, column_values });
     }
 
-    fn extract_record_column_values(record_column_value_sizes: &Vec<i64>, record_body_size: usize, record_body: &[u8]) -> Vec<Vec<u8>> {
-        let mut column_values = Vec::new();
-        let mut record_body_offset = 0; 
-        let mut index = 0;
-        while record_body_offset < record_body_size {
-            let column_value_size = record_column_value_sizes[index] as usize;
-            let column_value = record_body[record_body_offset..record_body_offset+column_value_size].to_vec();
-            column_values.push(column_value);
-            record_body_offset += column_value_size;
-            index += 1;
-        }
-        return column_values;
+    // Index of the first cell whose rowid is not smaller than `row_id`.
+    pub fn cell_index_for(&self, row_id: i64) -> usize {
+        self.cells.partition_point(|(cell_row_id, _)| *cell_row_id < row_id)
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,5 @@
-use crate::{command::sql::parser::sql_statement::{CreateTableStatement, SelectStatement}};
+use crate::models::dbfile::pager::Pager;
+use crate::models::error::DatabaseError;
 
 pub mod interiortablepage;
 pub use interiortablepage::InteriorTablePage;
@@ -6,24 +7,34 @@ pub use interiortablepage::InteriorTablePage;
 pub mod leaftablepage;
 pub use leaftablepage::LeafTablePage;
 
+pub mod overflow;
+
 pub mod record;
 pub use record::Record;
 
-pub trait Table {
-    fn to_table_records(&mut self, statement: &SelectStatement, table_description: &CreateTableStatement) -> Vec<Record>;
-}
+pub mod value;
+pub use value::{Affinity, Value};
 
+// SQLite refuses to descend more than 20 levels into a b-tree; anything deeper
+// is a corrupt file, most likely one whose child pointers form a cycle.
+pub const MAX_BTREE_DEPTH: usize = 20;
 
-pub enum TablePage<'a> {
+pub enum TablePage {
     Leaf(LeafTablePage),
-    Interior(InteriorTablePage<'a>),
+    Interior(InteriorTablePage),
 }
 
-impl Table for TablePage<'_> {
-    fn to_table_records(&mut self, statement: &SelectStatement, table_description: &CreateTableStatement) -> Vec<Record> {
-        match self {
-            TablePage::Leaf(p) => p.to_table_records(statement, table_description),
-            TablePage::Interior(p) => p.to_table_records(statement, table_description),
+impl TablePage {
+    // Reads a table b-tree page through the pager. Page 1 starts with the 100-byte
+    // file header, so its b-tree page header (and the root of sqlite_schema) comes after it.
+    pub fn from_page(page_number: u32, pager: &mut Pager) -> Result<Self, DatabaseError> {
+        let page_buffer = pager.get_page(page_number)?;
+        let header_offset = if page_number == 1 { 100 } else { 0 };
+
+        match page_buffer[header_offset] {
+            0x0D => Ok(TablePage::Leaf(LeafTablePage::from_bytes(&page_buffer, page_number, pager)?)),
+            0x05 => Ok(TablePage::Interior(InteriorTablePage::from_bytes(&page_buffer, page_number, pager)?)),
+            e => Err(DatabaseError::corrupt(page_number, header_offset, format!("page type {} is not a table b-tree page", e))),
         }
     }
-}
\ No newline at end of file
+}
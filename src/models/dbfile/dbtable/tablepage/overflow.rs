@@ -0,0 +1,71 @@
+use crate::models::dbfile::pager::Pager;
+use crate::models::error::DatabaseError;
+
+// Number of payload bytes stored directly on a table b-tree leaf page, as laid out in
+// https://www.sqlite.org/fileformat.html#cell_payload. Whatever doesn't fit spills into a
+// linked list of overflow pages whose first page number follows the local bytes in the cell.
+pub fn local_payload_size(payload_size: usize, usable_page_size: usize) -> usize {
+    spill_local_payload_size(payload_size, usable_page_size, usable_page_size - 35)
+}
+
+// Index b-tree cells, leaf and interior alike, keep less of their payload on the page.
+pub fn index_local_payload_size(payload_size: usize, usable_page_size: usize) -> usize {
+    spill_local_payload_size(payload_size, usable_page_size, ((usable_page_size - 12) * 64 / 255) - 23)
+}
+
+fn spill_local_payload_size(payload_size: usize, usable_page_size: usize, max_local: usize) -> usize {
+    if payload_size <= max_local {
+        return payload_size;
+    }
+
+    let min_local = ((usable_page_size - 12) * 32 / 255) - 23;
+    let local = min_local + ((payload_size - min_local) % (usable_page_size - 4));
+    if local <= max_local { local } else { min_local }
+}
+
+// Reads the full payload of the cell at `cell_pointer` whose local bytes start at `offset`.
+// `local_size` tells how much of a payload of the given size is stored on the page itself.
+pub fn read_cell_payload(data: &[u8], page_number: u32, cell_pointer: usize, offset: usize, payload_size: i64, local_size: fn(usize, usize) -> usize, pager: &mut Pager) -> Result<Vec<u8>, DatabaseError> {
+    if payload_size < 0 || payload_size as u64 > pager.page_count as u64 * pager.usable_page_size as u64 {
+        return Err(DatabaseError::corrupt(page_number, cell_pointer, format!("payload size {} is larger than the database", payload_size)));
+    }
+    let payload_size = payload_size as usize;
+    let local_size = local_size(payload_size, pager.usable_page_size);
+    let cell_end = offset + local_size;
+
+    let local_payload = data.get(offset .. cell_end)
+                            .ok_or_else(|| DatabaseError::corrupt(page_number, cell_pointer, "cell payload runs past the end of the page"))?;
+    let mut payload = local_payload.to_vec();
+
+    if local_size < payload_size {
+        let first_overflow_page = data.get(cell_end..cell_end+4)
+                                      .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                                      .ok_or_else(|| DatabaseError::corrupt(page_number, cell_pointer, "overflow page number runs past the end of the page"))?;
+        let overflow_payload = read_overflow_payload(pager, first_overflow_page, payload_size - local_size)?;
+        payload.extend_from_slice(&overflow_payload);
+    }
+    Ok(payload)
+}
+
+// Follows the overflow chain starting at `first_page_number` and collects the remaining
+// `overflow_size` bytes of a payload. Each overflow page starts with the 4-byte number of
+// the next page in the chain (0 on the last page) followed by up to usable_page_size - 4 bytes.
+pub fn read_overflow_payload(pager: &mut Pager, first_page_number: u32, overflow_size: usize) -> Result<Vec<u8>, DatabaseError> {
+    let usable_page_size = pager.usable_page_size;
+    let mut payload: Vec<u8> = Vec::new();
+    let mut page_number = first_page_number;
+
+    while payload.len() < overflow_size {
+        if page_number == 0 {
+            return Err(DatabaseError::corrupt(first_page_number, 0, "overflow chain ends before the payload is complete"));
+        }
+        let page_buffer = pager.get_page(page_number)?;
+
+        let next_page_number = u32::from_be_bytes([page_buffer[0], page_buffer[1], page_buffer[2], page_buffer[3]]);
+        let chunk_size = (overflow_size - payload.len()).min(usable_page_size - 4);
+        payload.extend_from_slice(&page_buffer[4..4 + chunk_size]);
+        page_number = next_page_number;
+    }
+
+    Ok(payload)
+}
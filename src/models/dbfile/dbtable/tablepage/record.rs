@@ -1,8 +1,72 @@
-
+use crate::utils::varint::{encode_varint, parse_varint};
+use crate::models::dbfile::dbtable::tablepage::Value;
+use crate::models::dbfile::header::TextEncoding;
 
 #[derive(Debug)]
 pub struct Record {
     pub row_id: i64,
-    pub column_values: Vec<Vec<u8>>
+    pub column_values: Vec<Value>
 }
 
+impl Record {
+    // Decodes a record (header of serial types followed by the body) into typed column values.
+    // Errors carry the reason the record is malformed; callers know which page it came from.
+    pub fn decode_column_values(record_data: &[u8], text_encoding: TextEncoding) -> Result<Vec<Value>, String> {
+        let (record_header_size, column_serial_types): (usize, Vec<i64>) = Self::extract_record_description(record_data)?;
+        let record_body: &[u8] = &record_data[record_header_size..];
+        Self::extract_record_column_values(&column_serial_types, record_body, text_encoding)
+    }
+
+    // The record `decode_column_values` reads back: a header holding its own size and the serial
+    // type of every value, then the values. Text is encoded as UTF-8.
+    pub fn encode_column_values(column_values: &[Value]) -> Vec<u8> {
+        let (serial_types, bodies): (Vec<i64>, Vec<Vec<u8>>) = column_values.iter().map(Value::to_serial_type).unzip();
+        let serial_type_bytes: Vec<u8> = serial_types.into_iter().flat_map(encode_varint).collect();
+
+        // The header size counts the varint it is written in
+        let mut header_size = serial_type_bytes.len() + 1;
+        while encode_varint(header_size as i64).len() + serial_type_bytes.len() != header_size {
+            header_size = encode_varint(header_size as i64).len() + serial_type_bytes.len();
+        }
+
+        let mut record_data = encode_varint(header_size as i64);
+        record_data.extend(serial_type_bytes);
+        record_data.extend(bodies.into_iter().flatten());
+        record_data
+    }
+
+    // Returns the record header size and the serial type of every column in the record.
+    pub fn extract_record_description(record_data: &[u8]) -> Result<(usize, Vec<i64>), String> {
+        let mut column_serial_types: Vec<i64> = Vec::new();
+        let (record_header_size, record_header_varint_size) = parse_varint(&record_data[0..9.min(record_data.len())]);
+        if record_header_varint_size == 0 || record_header_size < 0 || record_header_size as usize > record_data.len() {
+            return Err(format!("record header size {} is out of bounds", record_header_size));
+        }
+
+        let mut record_header_value_index = record_header_varint_size;
+        while record_header_value_index < record_header_size as usize {
+            let end = (record_header_value_index+9).min(record_header_size as usize);
+            let (serial_type, serial_type_varint_size)  = parse_varint(&record_data[record_header_value_index..end]);
+            if serial_type < 0 || serial_type == 10 || serial_type == 11 {
+                return Err(format!("invalid serial type {}", serial_type));
+            }
+            column_serial_types.push(serial_type);
+            record_header_value_index += serial_type_varint_size;
+        }
+
+        Ok((record_header_size as usize, column_serial_types))
+    }
+
+    pub fn extract_record_column_values(column_serial_types: &[i64], record_body: &[u8], text_encoding: TextEncoding) -> Result<Vec<Value>, String> {
+        let mut column_values = Vec::new();
+        let mut record_body_offset = 0; 
+        for &serial_type in column_serial_types {
+            let column_value_size = Value::serial_type_size(serial_type);
+            let column_value_bytes = record_body.get(record_body_offset..record_body_offset+column_value_size)
+                                                .ok_or_else(|| format!("column value of serial type {} runs past the end of the record", serial_type))?;
+            column_values.push(Value::from_serial_type_with_encoding(serial_type, column_value_bytes, text_encoding));
+            record_body_offset += column_value_size;
+        }
+        return Ok(column_values);
+    }
+}
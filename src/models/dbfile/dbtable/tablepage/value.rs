@@ -0,0 +1,257 @@
+use std::cmp::Ordering;
+use std::fmt;
+use crate::models::dbfile::header::TextEncoding;
+
+// A single column value decoded from a record, typed according to its
+// SQLite serial type (https://www.sqlite.org/fileformat.html#record_format).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>)
+}
+
+impl Value {
+    // Number of bytes a value of the given serial type occupies in the record body.
+    pub fn serial_type_size(serial_type: i64) -> usize {
+        match serial_type {
+            0 | 8 | 9 | 10 | 11 => 0,
+            1 => 1,
+            2 => 2,
+            3 => 3,
+            4 => 4,
+            5 => 6,
+            6 | 7 => 8,
+            n if n % 2 == 0 => ((n - 12) / 2) as usize,
+            n => ((n - 13) / 2) as usize
+        }
+    }
+
+    pub fn from_serial_type(serial_type: i64, bytes: &[u8]) -> Self {
+        Self::from_serial_type_with_encoding(serial_type, bytes, TextEncoding::Utf8)
+    }
+
+    pub fn from_serial_type_with_encoding(serial_type: i64, bytes: &[u8], text_encoding: TextEncoding) -> Self {
+        match serial_type {
+            0 => Value::Null,
+            1..=6 => Value::Integer(Self::decode_big_endian_integer(bytes)),
+            7 => Value::Real(f64::from_be_bytes(bytes[0..8].try_into().unwrap())),
+            8 => Value::Integer(0),
+            9 => Value::Integer(1),
+            10 | 11 => Value::Null,
+            n if n % 2 == 0 => Value::Blob(bytes.to_vec()),
+            _ => Value::Text(text_encoding.decode(bytes))
+        }
+    }
+
+    // The serial type and body bytes the value is stored as in a record. Integers take the fewest
+    // bytes that hold them, 0 and 1 none at all; text is written as UTF-8.
+    pub fn to_serial_type(&self) -> (i64, Vec<u8>) {
+        match self {
+            Value::Null => (0, Vec::new()),
+            Value::Integer(0) => (8, Vec::new()),
+            Value::Integer(1) => (9, Vec::new()),
+            Value::Integer(n) => {
+                let (serial_type, size) = match n {
+                                            -0x80..=0x7F => (1, 1),
+                                            -0x8000..=0x7FFF => (2, 2),
+                                            -0x80_0000..=0x7F_FFFF => (3, 3),
+                                            -0x8000_0000..=0x7FFF_FFFF => (4, 4),
+                                            -0x8000_0000_0000..=0x7FFF_FFFF_FFFF => (5, 6),
+                                            _ => (6, 8)
+                                          };
+                (serial_type, n.to_be_bytes()[8 - size..].to_vec())
+            },
+            Value::Real(n) => (7, n.to_be_bytes().to_vec()),
+            Value::Text(text) => (text.len() as i64 * 2 + 13, text.as_bytes().to_vec()),
+            Value::Blob(bytes) => (bytes.len() as i64 * 2 + 12, bytes.clone())
+        }
+    }
+
+    // Integers are stored as big-endian two's complement of 1, 2, 3, 4, 6 or 8 bytes,
+    // so the first byte has to be sign extended before the rest are shifted in.
+    fn decode_big_endian_integer(bytes: &[u8]) -> i64 {
+        let mut value: i64 = if !bytes.is_empty() && bytes[0] & 0x80 != 0 { -1 } else { 0 };
+        for &byte in bytes {
+            value = (value << 8) | byte as i64;
+        }
+        value
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    // Orders values the way SQLite sorts them in an index: NULL first, then numbers
+    // (integers and reals compared by value), then text and finally blobs, both byte by byte.
+    pub fn compare(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+            (Value::Real(a), Value::Real(b)) => a.total_cmp(b),
+            (Value::Integer(a), Value::Real(b)) => (*a as f64).total_cmp(b),
+            (Value::Real(a), Value::Integer(b)) => a.total_cmp(&(*b as f64)),
+            (Value::Text(a), Value::Text(b)) => a.as_bytes().cmp(b.as_bytes()),
+            (Value::Blob(a), Value::Blob(b)) => a.cmp(b),
+            _ => self.type_rank().cmp(&other.type_rank())
+        }
+    }
+
+    // Index keys in a UTF-16 database are compared in their stored form, see TextEncoding::compare.
+    pub fn compare_encoded(&self, other: &Value, text_encoding: TextEncoding) -> Ordering {
+        match (self, other) {
+            (Value::Text(a), Value::Text(b)) => text_encoding.compare(a, b),
+            _ => self.compare(other)
+        }
+    }
+
+    // Converts the value the way a column of the given affinity stores it
+    // (https://www.sqlite.org/datatype3.html#type_affinity). Text that isn't a well-formed
+    // number stays text, and blobs are never converted.
+    pub fn apply_affinity(self, affinity: Affinity) -> Value {
+        match (affinity, self) {
+            (Affinity::Text, number @ (Value::Integer(_) | Value::Real(_))) => Value::Text(number.to_string()),
+            (Affinity::Real, Value::Integer(n)) => Value::Real(n as f64),
+            (Affinity::Real, Value::Text(text)) => match Self::parse_number(&text) {
+                                                      Some(Value::Integer(n)) => Value::Real(n as f64),
+                                                      Some(number) => number,
+                                                      None => Value::Text(text)
+                                                   },
+            (Affinity::Integer | Affinity::Numeric, Value::Text(text)) => match Self::parse_number(&text) {
+                                                                             // A real that is a whole number becomes an integer
+                                                                             Some(Value::Real(n)) if n.fract() == 0.0 && n.abs() < 9.0e18 => Value::Integer(n as i64),
+                                                                             Some(number) => number,
+                                                                             None => Value::Text(text)
+                                                                          },
+            (_, value) => value
+        }
+    }
+
+    // The number `text` spells out in full, allowing surrounding spaces, or None if it isn't one.
+    fn parse_number(text: &str) -> Option<Value> {
+        let text = text.trim_matches(|c: char| c.is_ascii_whitespace());
+        let number_length = Self::number_prefix_length(text);
+        if number_length == 0 || number_length != text.len() {
+            return None;
+        }
+        Some(Self::to_number(text))
+    }
+
+    // The numeric value of the longest prefix of `text` that is a number, or 0 when none is,
+    // as when SQLite uses text in arithmetic.
+    pub fn numeric_prefix(text: &str) -> Value {
+        let text = text.trim_start_matches(|c: char| c.is_ascii_whitespace());
+        match Self::number_prefix_length(text) {
+            0 => Value::Integer(0),
+            number_length => Self::to_number(&text[..number_length])
+        }
+    }
+
+    // Length of the `[+-]digits[.digits][e[+-]digits]` at the start of `text`.
+    fn number_prefix_length(text: &str) -> usize {
+        let bytes = text.as_bytes();
+        let digits_from = |position: usize| bytes[position.min(bytes.len())..].iter().take_while(|byte| byte.is_ascii_digit()).count();
+        let mut length = if matches!(bytes.first(), Some(b'+' | b'-')) { 1 } else { 0 };
+        let integer_digits = digits_from(length);
+        length += integer_digits;
+        let mut fraction_digits = 0;
+        if bytes.get(length) == Some(&b'.') {
+            fraction_digits = digits_from(length + 1);
+            if integer_digits + fraction_digits > 0 {
+                length += 1 + fraction_digits;
+            }
+        }
+        if integer_digits + fraction_digits == 0 {
+            return 0;
+        }
+        if matches!(bytes.get(length), Some(b'e' | b'E')) {
+            let sign_length = if matches!(bytes.get(length + 1), Some(b'+' | b'-')) { 1 } else { 0 };
+            let exponent_digits = digits_from(length + 1 + sign_length);
+            if exponent_digits > 0 {
+                length += 1 + sign_length + exponent_digits;
+            }
+        }
+        length
+    }
+
+    // An integer if `number` is one that fits in 64 bits, a real otherwise.
+    fn to_number(number: &str) -> Value {
+        match number.parse::<i64>() {
+            Ok(n) => Value::Integer(n),
+            Err(_) => Value::Real(number.parse::<f64>().unwrap_or(0.0))
+        }
+    }
+
+    fn type_rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Integer(_) | Value::Real(_) => 1,
+            Value::Text(_) => 2,
+            Value::Blob(_) => 3
+        }
+    }
+}
+
+// The kind of value a column prefers to store, worked out from its declared type
+// the way SQLite does (https://www.sqlite.org/datatype3.html#determination_of_column_affinity).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Affinity {
+    Integer,
+    Text,
+    Blob,
+    Real,
+    Numeric
+}
+
+impl Affinity {
+    pub fn from_declared_type(declared_type: Option<&str>) -> Self {
+        let Some(declared_type) = declared_type else {
+            return Affinity::Blob;
+        };
+        let declared_type = declared_type.to_ascii_uppercase();
+        let contains = |words: &[&str]| words.iter().any(|word| declared_type.contains(word));
+        if contains(&["INT"]) {
+            return Affinity::Integer;
+        }
+        if contains(&["CHAR", "CLOB", "TEXT"]) {
+            return Affinity::Text;
+        }
+        if contains(&["BLOB"]) {
+            return Affinity::Blob;
+        }
+        if contains(&["REAL", "FLOA", "DOUB"]) {
+            return Affinity::Real;
+        }
+        Affinity::Numeric
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Affinity::Integer | Affinity::Real | Affinity::Numeric)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::Real(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{:.1}", n),
+            Value::Real(n) => write!(f, "{}", n),
+            Value::Text(s) => write!(f, "{}", s),
+            Value::Blob(b) => write!(f, "{}", String::from_utf8_lossy(b))
+        }
+    }
+}
+
+impl PartialEq<str> for Value {
+    fn eq(&self, other: &str) -> bool {
+        matches!(self, Value::Text(s) if s == other)
+    }
+}
+
+impl PartialEq<&str> for Value {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
@@ -1,31 +1,22 @@
 use std::fmt;
 use std::ops::{Index, IndexMut};
-use crate::models::dbfile::dbtable::tablepage::Record;
+use crate::models::dbfile::dbtable::tablepage::{Record, Value};
 
 #[derive(Debug)]
 pub struct TableRow {
     pub row_id: i64,
-    pub column_values: Vec<String>
+    pub column_values: Vec<Value>
 }
 
 impl From<Record> for TableRow {
     fn from(record: Record) -> Self {
-        let column_values: Vec<String> = record.column_values.iter()
-                                                             .map(|bytes| {
-                                                                 if bytes.is_empty() {
-                                                                     "NULL".to_string()
-                                                                 } else {
-                                                                     String::from_utf8_lossy(&bytes).to_string()
-                                                                 }
-                                                             })
-                                                             .collect();
-        let tablerow = TableRow { row_id: record.row_id, column_values };
+        let tablerow = TableRow { row_id: record.row_id, column_values: record.column_values };
         return tablerow;
     }
 }
 
 impl Index<usize> for TableRow {
-    type Output = String;
+    type Output = Value;
     
     fn index(&self, index: usize) -> &Self::Output {
         &self.column_values[index]
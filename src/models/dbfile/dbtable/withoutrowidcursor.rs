@@ -0,0 +1,79 @@
+use crate::command::sql::Constraint;
+use crate::command::sql::parser::sql_statement::CreateTableStatement;
+use crate::models::dbfile::dbindex::{IndexCursor, IndexRecord};
+use crate::models::dbfile::dbtable::tablepage::{Record, Value};
+use crate::models::dbfile::pager::Pager;
+use crate::models::error::DatabaseError;
+
+// Walks the rows of a WITHOUT ROWID table in primary key order. Those rows live in an index
+// b-tree, stored as the primary key columns followed by the remaining columns in table order;
+// the cursor puts the values back in table order. The rows have no rowid, so `row_id` is 0.
+pub struct WithoutRowidCursor<'a> {
+    cursor: IndexCursor<'a>,
+    pub table_description: CreateTableStatement,
+    stored_positions: Vec<usize>    // for each table column, the position of its value in a stored row
+}
+
+impl<'a> WithoutRowidCursor<'a> {
+    pub fn new(pager: &'a mut Pager, rootpage: u32, table_description: CreateTableStatement) -> Self {
+        let descending: Vec<bool> = table_description.primary_key.iter().map(|column| column.descending).collect();
+
+        let mut stored_columns: Vec<usize> = Vec::new();
+        for key_column in table_description.primary_key.iter() {
+            if let Some(column_index) = table_description.column_index(&key_column.name) {
+                if !stored_columns.contains(&column_index) {
+                    stored_columns.push(column_index);
+                }
+            }
+        }
+        for column_index in 0..table_description.columns.len() {
+            if !stored_columns.contains(&column_index) {
+                stored_columns.push(column_index);
+            }
+        }
+        let mut stored_positions = vec![0; stored_columns.len()];
+        for (stored_position, &column_index) in stored_columns.iter().enumerate() {
+            stored_positions[column_index] = stored_position;
+        }
+
+        let cursor = IndexCursor::without_rowid(pager, rootpage, descending);
+        WithoutRowidCursor { cursor, table_description, stored_positions }
+    }
+
+    pub fn rewind(&mut self) -> Result<(), DatabaseError> {
+        self.cursor.rewind()
+    }
+
+    // Positions the cursor at the first row whose leading primary key columns can match
+    // `prefix` and `range`; see IndexCursor::seek_range.
+    pub fn seek_range(&mut self, prefix: &[Value], range: &[Constraint]) -> Result<(), DatabaseError> {
+        self.cursor.seek_range(prefix, range)
+    }
+
+    pub fn next_in_range(&mut self, prefix: &[Value], range: &[Constraint]) -> Result<Option<Record>, DatabaseError> {
+        Ok(self.cursor.next_in_range(prefix, range)?.map(|index_record| self.to_table_record(index_record)))
+    }
+
+    pub fn next_record(&mut self) -> Result<Option<Record>, DatabaseError> {
+        Ok(self.cursor.next_record()?.map(|index_record| self.to_table_record(index_record)))
+    }
+
+    // Rows written before an ALTER TABLE ADD COLUMN have fewer values than the table has columns;
+    // the columns they lack read as their DEFAULT.
+    fn to_table_record(&self, index_record: IndexRecord) -> Record {
+        let column_values = self.stored_positions.iter()
+                                                 .zip(self.table_description.column_defaults.iter())
+                                                 .map(|(&stored_position, default)| index_record.key.get(stored_position).unwrap_or(default).clone())
+                                                 .collect();
+        return Record { row_id: 0, column_values };
+    }
+}
+
+impl Iterator for WithoutRowidCursor<'_> {
+    type Item = Result<Record, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index_record = self.cursor.next()?;
+        Some(index_record.map(|index_record| self.to_table_record(index_record)))
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashSet;
+use crate::models::dbfile::pager::Pager;
+use crate::models::error::DatabaseError;
+
+// The pages of the database that are not in use, as a linked list of trunk pages
+// (https://www.sqlite.org/fileformat.html#the_freelist). Each trunk page starts with the
+// number of the next trunk page (0 on the last one) and a count of leaf pages, followed by
+// the leaf page numbers. Trunk pages are free pages themselves; leaf pages hold nothing.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Freelist {
+    pub trunk_pages: Vec<u32>,
+    pub leaf_pages: Vec<u32>
+}
+
+impl Freelist {
+    // Walks the trunk chain starting at the header's first freelist trunk page (0 for an empty freelist).
+    pub fn read(pager: &mut Pager, first_trunk_page: u32) -> Result<Self, DatabaseError> {
+        // A trunk page holds at most this many leaf page numbers after its 8-byte header
+        let max_leaf_count = pager.usable_page_size / 4 - 2;
+        let mut freelist = Freelist::default();
+        let mut seen_pages: HashSet<u32> = HashSet::new();
+
+        let mut trunk_page_number = first_trunk_page;
+        while trunk_page_number != 0 {
+            if !seen_pages.insert(trunk_page_number) {
+                return Err(DatabaseError::corrupt(trunk_page_number, 0, "freelist trunk page appears more than once in the freelist"));
+            }
+            let page_buffer = pager.get_page(trunk_page_number)?;
+            let read_u32 = |offset: usize| u32::from_be_bytes([page_buffer[offset], page_buffer[offset+1], page_buffer[offset+2], page_buffer[offset+3]]);
+
+            let leaf_count = read_u32(4) as usize;
+            if leaf_count > max_leaf_count {
+                return Err(DatabaseError::corrupt(trunk_page_number, 4, format!("freelist trunk page claims {} leaf pages", leaf_count)));
+            }
+            for leaf_index in 0..leaf_count {
+                let leaf_page_number = read_u32(8 + leaf_index * 4);
+                if leaf_page_number == 0 || leaf_page_number > pager.page_count {
+                    return Err(DatabaseError::corrupt(trunk_page_number, 8 + leaf_index * 4, format!("freelist leaf page {} is out of range", leaf_page_number)));
+                }
+                if !seen_pages.insert(leaf_page_number) {
+                    return Err(DatabaseError::corrupt(trunk_page_number, 8 + leaf_index * 4, format!("page {} appears more than once in the freelist", leaf_page_number)));
+                }
+                freelist.leaf_pages.push(leaf_page_number);
+            }
+
+            freelist.trunk_pages.push(trunk_page_number);
+            trunk_page_number = read_u32(0);
+        }
+
+        Ok(freelist)
+    }
+
+    // Trunk and leaf pages together; the header's freelist page count should match this.
+    pub fn page_count(&self) -> usize {
+        self.trunk_pages.len() + self.leaf_pages.len()
+    }
+
+    pub fn contains(&self, page_number: u32) -> bool {
+        self.trunk_pages.contains(&page_number) || self.leaf_pages.contains(&page_number)
+    }
+}
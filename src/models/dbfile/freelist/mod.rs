@@ -0,0 +1,2 @@
+pub mod freelist;
+pub use freelist::Freelist;
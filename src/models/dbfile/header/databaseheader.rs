@@ -0,0 +1,138 @@
+use std::cmp::Ordering;
+use crate::models::error::DatabaseError;
+
+pub const HEADER_SIZE: usize = 100;
+pub const MAGIC_STRING: &[u8; 16] = b"SQLite format 3\0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    Utf16le,
+    Utf16be
+}
+
+impl TextEncoding {
+    // Every TEXT value in the file, the schema included, is stored in the database encoding.
+    // Invalid sequences become U+FFFD rather than failing the whole row.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match self {
+            TextEncoding::Utf8 => String::from_utf8_lossy(bytes).to_string(),
+            TextEncoding::Utf16le => Self::decode_utf16(bytes, u16::from_le_bytes),
+            TextEncoding::Utf16be => Self::decode_utf16(bytes, u16::from_be_bytes)
+        }
+    }
+
+    // SQLite compares text byte by byte in the database encoding. UTF-8 and UTF-16be bytes sort
+    // like code points (bar surrogate pairs), but UTF-16le bytes don't.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            TextEncoding::Utf8 => a.as_bytes().cmp(b.as_bytes()),
+            TextEncoding::Utf16le => a.encode_utf16().flat_map(u16::to_le_bytes).cmp(b.encode_utf16().flat_map(u16::to_le_bytes)),
+            TextEncoding::Utf16be => a.encode_utf16().cmp(b.encode_utf16())
+        }
+    }
+
+    fn decode_utf16(bytes: &[u8], code_unit: fn([u8; 2]) -> u16) -> String {
+        let code_units: Vec<u16> = bytes.chunks_exact(2)
+                                        .map(|chunk| code_unit([chunk[0], chunk[1]]))
+                                        .collect();
+        String::from_utf16_lossy(&code_units)
+    }
+}
+
+// The 100-byte header at the start of every database file
+// (https://www.sqlite.org/fileformat.html#the_database_header).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseHeader {
+    pub magic: String,                   // offset 0:  "SQLite format 3\0"
+    pub page_size: u32,                  // offset 16: 1 means 65536
+    pub write_version: u8,               // offset 18: 1 legacy, 2 WAL
+    pub read_version: u8,                // offset 19: 1 legacy, 2 WAL
+    pub reserved_space: u8,              // offset 20: unused bytes at the end of each page
+    pub max_payload_fraction: u8,        // offset 21: must be 64
+    pub min_payload_fraction: u8,        // offset 22: must be 32
+    pub leaf_payload_fraction: u8,       // offset 23: must be 32
+    pub file_change_counter: u32,        // offset 24
+    pub database_size: u32,              // offset 28: in pages, only valid if version_valid_for == file_change_counter
+    pub freelist_trunk_page: u32,        // offset 32: 0 if the freelist is empty
+    pub freelist_page_count: u32,        // offset 36
+    pub schema_cookie: u32,              // offset 40
+    pub schema_format: u32,              // offset 44: 1 to 4
+    pub default_cache_size: u32,         // offset 48
+    pub largest_root_page: u32,          // offset 52: non-zero in auto-vacuum databases
+    pub text_encoding: TextEncoding,     // offset 56: 1 UTF-8, 2 UTF-16le, 3 UTF-16be
+    pub user_version: u32,               // offset 60
+    pub incremental_vacuum: bool,        // offset 64
+    pub application_id: u32,             // offset 68
+    pub version_valid_for: u32,          // offset 92
+    pub sqlite_version_number: u32       // offset 96
+}
+
+impl DatabaseHeader {
+    pub fn from_bytes(data: &[u8]) -> Result<Self, DatabaseError> {
+        if data.len() < HEADER_SIZE || &data[0..16] != MAGIC_STRING {
+            return Err(DatabaseError::NotADatabase);
+        }
+
+        let read_u32 = |offset: usize| u32::from_be_bytes([data[offset], data[offset+1], data[offset+2], data[offset+3]]);
+
+        let page_size = Self::decode_page_size([data[16], data[17]]);
+        if !(512..=65536).contains(&page_size) || !page_size.is_power_of_two() {
+            return Err(DatabaseError::NotADatabase);
+        }
+        // The file format requires at least 480 usable bytes per page
+        if page_size - (data[20] as u32) < 480 {
+            return Err(DatabaseError::NotADatabase);
+        }
+
+        let text_encoding = match read_u32(56) {
+                                // 0 only appears in a database that has no schema yet, which reads as UTF-8
+                                0 | 1 => TextEncoding::Utf8,
+                                2 => TextEncoding::Utf16le,
+                                3 => TextEncoding::Utf16be,
+                                _ => return Err(DatabaseError::NotADatabase)
+                            };
+
+        Ok(DatabaseHeader {
+            magic: String::from_utf8_lossy(&data[0..15]).to_string(),
+            page_size,
+            write_version: data[18],
+            read_version: data[19],
+            reserved_space: data[20],
+            max_payload_fraction: data[21],
+            min_payload_fraction: data[22],
+            leaf_payload_fraction: data[23],
+            file_change_counter: read_u32(24),
+            database_size: read_u32(28),
+            freelist_trunk_page: read_u32(32),
+            freelist_page_count: read_u32(36),
+            schema_cookie: read_u32(40),
+            schema_format: read_u32(44),
+            default_cache_size: read_u32(48),
+            largest_root_page: read_u32(52),
+            text_encoding,
+            user_version: read_u32(60),
+            incremental_vacuum: read_u32(64) != 0,
+            application_id: read_u32(68),
+            version_valid_for: read_u32(92),
+            sqlite_version_number: read_u32(96)
+        })
+    }
+
+    // The header stores the page size as a big-endian u16, which can't hold the
+    // maximum page size of 65536, so that one is written as the value 1 instead.
+    pub fn decode_page_size(bytes: [u8; 2]) -> u32 {
+        match u16::from_be_bytes(bytes) {
+            1 => 65536,
+            n => n as u32
+        }
+    }
+
+    pub fn usable_page_size(&self) -> usize {
+        self.page_size as usize - self.reserved_space as usize
+    }
+
+    pub fn is_wal_mode(&self) -> bool {
+        self.write_version == 2 || self.read_version == 2
+    }
+}
@@ -0,0 +1,2 @@
+pub mod databaseheader;
+pub use databaseheader::{DatabaseHeader, TextEncoding};
@@ -0,0 +1,404 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use crate::command::sql::{SortKey, SortedRows, Sorter};
+use crate::command::sql::evaluator::Collation;
+use crate::command::sql::parser::sql_statement::{CreateTableStatement, IndexedColumn};
+use crate::models::dbfile::dbindex::{IndexCursor, IndexRecord};
+use crate::models::dbfile::dbindex::indexpage::IndexPage;
+use crate::models::dbfile::dbtable::{TableCursor, TableRow, WithoutRowidCursor};
+use crate::models::dbfile::dbtable::tablepage::{Record, TablePage, Value, MAX_BTREE_DEPTH};
+use crate::models::dbfile::freelist::Freelist;
+use crate::models::dbfile::header::{DatabaseHeader, TextEncoding};
+use crate::models::dbfile::pagemap::PageMap;
+use crate::models::dbfile::pager::Pager;
+use crate::models::dbfile::schema::{SchemaIndex, SchemaRAW, SchemaRow};
+use crate::models::error::DatabaseError;
+
+// Mismatches listed for one index before the rest are only counted, so a badly damaged
+// index doesn't bury every other problem.
+const MAX_REPORTED_MISMATCHES: usize = 10;
+
+// SQLite never uses the page holding the byte at offset 2^30, which it locks instead.
+const LOCK_BYTE_OFFSET: u32 = 1 << 30;
+
+// The keys held by one b-tree page: rowids in a table b-tree, whole entries in an index b-tree.
+enum PageKeys<K> {
+    Leaf(Vec<K>),
+    Interior(Vec<(u32, K)>, Option<u32>)    // left child and key of each cell, then the right-most child
+}
+
+// A b-tree page still to check: its number, its parent's, the bounds on its keys and its depth.
+type PendingPage<K> = (u32, u32, Option<K>, Option<K>, usize);
+
+// What the entries of an index b-tree hold and how they are ordered.
+struct IndexLayout {
+    columns: Vec<Option<usize>>,    // table column behind each stored value, None for an expression
+    descending: Vec<bool>,
+    binary: bool,                   // every column uses the BINARY collation, the only one whose order we can check
+    has_rowid: bool                 // entries end with the rowid of their row
+}
+
+impl IndexLayout {
+    // Entries of an index on a WITHOUT ROWID table end with the primary key columns it doesn't already hold.
+    fn for_index(index: &SchemaIndex, table: &CreateTableStatement) -> Self {
+        let mut columns: Vec<Option<usize>> = Vec::new();
+        let mut descending: Vec<bool> = Vec::new();
+        let mut binary = true;
+        let key_columns = if table.without_rowid { table.primary_key.as_slice() } else { &[] };
+        for (position, indexed_column) in index.sql.columns.iter().chain(key_columns.iter()).enumerate() {
+            let column_index = table.column_index(&indexed_column.name);
+            if position >= index.sql.columns.len() && column_index.is_some() && columns.contains(&column_index) {
+                continue;
+            }
+            columns.push(column_index);
+            descending.push(indexed_column.descending);
+            binary &= is_binary(indexed_column, table);
+        }
+        IndexLayout { columns, descending, binary, has_rowid: !table.without_rowid }
+    }
+
+    // A WITHOUT ROWID table is stored as an index on its primary key.
+    fn for_without_rowid_table(table: &CreateTableStatement) -> Self {
+        IndexLayout {
+            columns: table.primary_key.iter().map(|key_column| table.column_index(&key_column.name)).collect(),
+            descending: table.primary_key.iter().map(|key_column| key_column.descending).collect(),
+            binary: table.primary_key.iter().all(|key_column| is_binary(key_column, table)),
+            has_rowid: false
+        }
+    }
+
+    // The entry the index should hold for a table row, or None if it indexes an expression.
+    fn entry_for(&self, row: &Record) -> Option<Vec<Value>> {
+        let mut entry = Vec::with_capacity(self.columns.len() + 1);
+        for column in self.columns.iter() {
+            entry.push(row.column_values.get((*column)?)?.clone());
+        }
+        if self.has_rowid {
+            entry.push(Value::Integer(row.row_id));
+        }
+        Some(entry)
+    }
+}
+
+// A column's collation comes from the index, or else from the table's column definition.
+fn is_binary(indexed_column: &IndexedColumn, table: &CreateTableStatement) -> bool {
+    let table_collation = table.column_index(&indexed_column.name).and_then(|column_index| table.column_collations[column_index].as_ref());
+    match indexed_column.collation.as_ref().or(table_collation) {
+        Some(collation) => collation.eq_ignore_ascii_case("BINARY"),
+        None => true
+    }
+}
+
+fn index_entry(record: &IndexRecord, has_rowid: bool) -> Vec<Value> {
+    let mut entry = record.key.clone();
+    if has_rowid {
+        entry.push(Value::Integer(record.row_id));
+    }
+    entry
+}
+
+fn compare_entries(a: &[Value], b: &[Value], descending: &[bool], text_encoding: TextEncoding) -> Ordering {
+    for (column_index, (a_value, b_value)) in a.iter().zip(b.iter()).enumerate() {
+        let ordering = a_value.compare_encoded(b_value, text_encoding);
+        let ordering = if descending.get(column_index) == Some(&true) { ordering.reverse() } else { ordering };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+// Sorts entries the way compare_entries orders them with every column ascending.
+fn entry_sorter(layout: &IndexLayout, text_encoding: TextEncoding, memory_budget: usize) -> Sorter {
+    let sort_key = SortKey { collation: Collation::Binary, descending: false, nulls_first: true, text_encoding };
+    Sorter::new(vec![sort_key; layout.columns.len() + usize::from(layout.has_rowid)], memory_budget)
+}
+
+// The Sorter hands back rows without their keys, so the entry is both.
+fn push_entry(sorter: &mut Sorter, entry: Vec<Value>) -> Result<(), DatabaseError> {
+    sorter.push(entry.clone(), TableRow { row_id: 0, column_values: entry })?;
+    Ok(())
+}
+
+fn describe_entry(entry: &[Value]) -> String {
+    let values: Vec<String> = entry.iter().map(|value| value.to_string()).collect();
+    format!("({})", values.join(", "))
+}
+
+fn describe_problem(problem: &DatabaseError) -> String {
+    match problem {
+        DatabaseError::Corrupt { page: 0, reason, .. } => reason.clone(),
+        DatabaseError::Corrupt { page, offset: 0, reason } => format!("page {}: {}", page, reason),
+        DatabaseError::Corrupt { page, offset, reason } => format!("page {}, offset {}: {}", page, offset, reason),
+        other => other.to_string()
+    }
+}
+
+// Our equivalent of sqlite3's PRAGMA integrity_check. Walks the whole file and lists every
+// problem found, in the order found; an empty list means the file is sound. Covers what the
+// page map checks (each page used exactly once, cells inside their page and not overlapping),
+// the freelist count in the header, the order of keys in every b-tree and, for each index
+// whose columns are plain table columns, that it holds exactly one entry per table row.
+pub struct IntegrityCheck<'a> {
+    pager: &'a mut Pager,
+    problems: Vec<String>,
+    damaged_pages: HashSet<u32>,    // pages with a problem already listed, whose errors aren't repeated
+    sort_budget: usize              // bytes of index entries sorted in memory before they spill to a temporary file
+}
+
+impl<'a> IntegrityCheck<'a> {
+    pub fn run(pager: &'a mut Pager, header: &DatabaseHeader, schema: &SchemaRAW, sort_budget: usize) -> Result<Vec<String>, DatabaseError> {
+        let (page_map, scan_problems) = PageMap::scan(pager, header, schema)?;
+        let mut check = IntegrityCheck { pager, problems: Vec::new(), damaged_pages: HashSet::new(), sort_budget };
+        for problem in scan_problems {
+            if let DatabaseError::Corrupt { page, .. } = problem {
+                check.damaged_pages.insert(page);
+            }
+            check.problems.push(describe_problem(&problem));
+        }
+
+        let lock_byte_page = LOCK_BYTE_OFFSET / header.page_size + 1;
+        for page_number in page_map.unused_pages() {
+            if page_number != lock_byte_page {
+                check.problems.push(format!("page {}: never used", page_number));
+            }
+        }
+        // A freelist that can't be read was reported by the page map
+        if let Ok(freelist) = Freelist::read(check.pager, header.freelist_trunk_page) {
+            if freelist.page_count() != header.freelist_page_count as usize {
+                check.problems.push(format!("freelist has {} pages but the header says {}", freelist.page_count(), header.freelist_page_count));
+            }
+        }
+
+        let tables = schema.to_schema_rows()?;
+        let indexes = schema.to_schema_indexes()?;
+        check.check_table_order(1)?;
+        for table in tables.iter() {
+            if table.sql.without_rowid {
+                check.check_index_order(table.rootpage, &IndexLayout::for_without_rowid_table(&table.sql))?;
+            } else {
+                check.check_table_order(table.rootpage)?;
+            }
+
+            let table_indexes: Vec<&SchemaIndex> = indexes.iter().filter(|index| index.table_name.eq_ignore_ascii_case(&table.table_name)).collect();
+            for index in table_indexes.iter() {
+                check.check_index_order(index.rootpage, &IndexLayout::for_index(index, &table.sql))?;
+            }
+            check.check_index_contents(table, &table_indexes)?;
+        }
+
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut problems = check.problems;
+        problems.retain(|problem| seen.insert(problem.clone()));
+        Ok(problems)
+    }
+
+    // Lists a corruption found after the page map, unless its page already has a problem listed.
+    // I/O errors end the check.
+    fn report(&mut self, problem: DatabaseError) -> Result<(), DatabaseError> {
+        match problem {
+            DatabaseError::Io(e) => Err(DatabaseError::Io(e)),
+            DatabaseError::Corrupt { page, .. } if !self.damaged_pages.insert(page) => Ok(()),
+            problem => {
+                self.problems.push(describe_problem(&problem));
+                Ok(())
+            }
+        }
+    }
+
+    // Rowids increase within each page, and a child only holds rowids after the previous cell's
+    // key and up to and including its own cell's key.
+    fn check_table_order(&mut self, rootpage: u32) -> Result<(), DatabaseError> {
+        let read_page = |pager: &mut Pager, page_number: u32| -> Result<PageKeys<i64>, DatabaseError> {
+            match TablePage::from_page(page_number, pager)? {
+                TablePage::Leaf(leaf_page) => Ok(PageKeys::Leaf(leaf_page.cells.iter().map(|(row_id, _)| *row_id).collect())),
+                TablePage::Interior(interior_page) => Ok(PageKeys::Interior(interior_page.cells, interior_page.sibling_page_number))
+            }
+        };
+        self.check_key_order(rootpage, read_page, |a, b| a.cmp(b), true, |row_id| format!("rowid {}", row_id))
+    }
+
+    // Like table b-trees, except that an interior cell's entry sorts after everything in its left child.
+    fn check_index_order(&mut self, rootpage: u32, layout: &IndexLayout) -> Result<(), DatabaseError> {
+        if !layout.binary {
+            return Ok(());
+        }
+        let has_rowid = layout.has_rowid;
+        let text_encoding = self.pager.text_encoding;
+        let read_page = |pager: &mut Pager, page_number: u32| -> Result<PageKeys<Vec<Value>>, DatabaseError> {
+            match IndexPage::from_page(page_number, pager, has_rowid)? {
+                IndexPage::Leaf(leaf_page) => Ok(PageKeys::Leaf(leaf_page.cells.iter().map(|record| index_entry(record, has_rowid)).collect())),
+                IndexPage::Interior(interior_page) => Ok(PageKeys::Interior(interior_page.cells.iter().map(|(child_page_number, record)| (*child_page_number, index_entry(record, has_rowid))).collect(),
+                                                                           Some(interior_page.right_most_page_number)))
+            }
+        };
+        let compare = |a: &Vec<Value>, b: &Vec<Value>| compare_entries(a, b, &layout.descending, text_encoding);
+        self.check_key_order(rootpage, read_page, compare, false, |entry| format!("entry {}", describe_entry(entry)))
+    }
+
+    // Walks a b-tree checking each page's keys against each other and against the range its parent
+    // allows. Pointers the page map found to be out of range or cyclic aren't followed again.
+    fn check_key_order<K, R, C, D>(&mut self, rootpage: u32, mut read_page: R, compare: C, upper_bound_inclusive: bool, describe: D) -> Result<(), DatabaseError>
+    where K: Clone,
+          R: FnMut(&mut Pager, u32) -> Result<PageKeys<K>, DatabaseError>,
+          C: Fn(&K, &K) -> Ordering,
+          D: Fn(&K) -> String {
+        let mut pending: Vec<PendingPage<K>> = vec![(rootpage, 0, None, None, 0)];
+        let mut visited_pages: HashSet<u32> = HashSet::new();
+
+        while let Some((page_number, parent_page_number, lower_bound, upper_bound, depth)) = pending.pop() {
+            if depth > MAX_BTREE_DEPTH || page_number == 0 || page_number > self.pager.page_count || !visited_pages.insert(page_number) {
+                continue;
+            }
+            let page_keys = match read_page(self.pager, page_number) {
+                                Ok(page_keys) => page_keys,
+                                Err(e) => {
+                                    self.report(e)?;
+                                    continue;
+                                }
+                            };
+
+            let keys: Vec<&K> = match &page_keys {
+                                    PageKeys::Leaf(keys) => keys.iter().collect(),
+                                    PageKeys::Interior(cells, _) => cells.iter().map(|(_, key)| key).collect()
+                                };
+            let mut previous_key: Option<&K> = None;
+            for key in keys {
+                let above_upper_bound = upper_bound.as_ref().is_some_and(|upper_bound| match compare(key, upper_bound) {
+                                                                            Ordering::Greater => true,
+                                                                            Ordering::Equal => !upper_bound_inclusive,
+                                                                            Ordering::Less => false
+                                                                        });
+                if previous_key.is_some_and(|previous_key| compare(key, previous_key) != Ordering::Greater) {
+                    self.problems.push(format!("page {}: {} is out of order", page_number, describe(key)));
+                } else if lower_bound.as_ref().is_some_and(|lower_bound| compare(key, lower_bound) != Ordering::Greater) || above_upper_bound {
+                    self.problems.push(format!("page {}: {} is outside the range of its parent page {}", page_number, describe(key), parent_page_number));
+                }
+                previous_key = Some(key);
+            }
+
+            if let PageKeys::Interior(cells, right_most_page_number) = page_keys {
+                let mut child_lower_bound = lower_bound;
+                for (child_page_number, key) in cells {
+                    pending.push((child_page_number, page_number, child_lower_bound, Some(key.clone()), depth + 1));
+                    child_lower_bound = Some(key);
+                }
+                if let Some(right_most_page_number) = right_most_page_number {
+                    pending.push((right_most_page_number, page_number, child_lower_bound, upper_bound, depth + 1));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Compares the entries each index should hold with the ones it does, reading the table once per
+    // index, which also decodes every record. Both sides are sorted into the same order, spilling to
+    // temporary files past the sort budget, then walked together, so a large table isn't held in memory.
+    // Partial indexes only cover the rows their WHERE clause matches, so for those only entries
+    // without a row are problems.
+    fn check_index_contents(&mut self, table: &SchemaRow, indexes: &[&SchemaIndex]) -> Result<(), DatabaseError> {
+        // The entries of an index on an expression can't be worked out from the row
+        let layouts: Vec<(&SchemaIndex, IndexLayout)> = indexes.iter()
+                                                               .map(|index| (*index, IndexLayout::for_index(index, &table.sql)))
+                                                               .filter(|(_, layout)| !layout.columns.contains(&None))
+                                                               .collect();
+        if layouts.is_empty() {
+            self.walk_table(table, |_| Ok(()))?;
+            return Ok(());
+        }
+
+        let text_encoding = self.pager.text_encoding;
+        for (index, layout) in layouts.iter() {
+            let mut expected_entries = entry_sorter(layout, text_encoding, self.sort_budget);
+            let table_read = self.walk_table(table, |row| {
+                                     match layout.entry_for(&row) {
+                                         Some(entry) => push_entry(&mut expected_entries, entry),
+                                         None => Ok(())
+                                     }
+                                 })?;
+            if !table_read {
+                return Ok(());
+            }
+
+            let mut entries = entry_sorter(layout, text_encoding, self.sort_budget);
+            let mut cursor = if layout.has_rowid {
+                                IndexCursor::new(self.pager, index.rootpage, layout.descending.clone())
+                             } else {
+                                IndexCursor::without_rowid(self.pager, index.rootpage, layout.descending.clone())
+                             };
+            if let Err(e) = cursor.try_for_each(|record| push_entry(&mut entries, index_entry(&record?, layout.has_rowid))) {
+                self.report(e)?;
+                continue;
+            }
+            self.compare_index_entries(table, index, layout, expected_entries.into_sorted()?, entries.into_sorted()?)?;
+        }
+        Ok(())
+    }
+
+    // Hands each row of the table to `visit`. A table that can't be read is reported, and false returned.
+    fn walk_table<F>(&mut self, table: &SchemaRow, mut visit: F) -> Result<bool, DatabaseError>
+    where F: FnMut(Record) -> Result<(), DatabaseError> {
+        let mut rows: Box<dyn Iterator<Item = Result<Record, DatabaseError>> + '_> = if table.sql.without_rowid {
+                                                                                        Box::new(WithoutRowidCursor::new(self.pager, table.rootpage, table.sql.clone()))
+                                                                                    } else {
+                                                                                        Box::new(TableCursor::new(self.pager, table.rootpage, table.sql.clone()))
+                                                                                    };
+        let walk = rows.try_for_each(|row| visit(row?));
+        drop(rows);
+        match walk {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                self.report(e)?;
+                Ok(false)
+            }
+        }
+    }
+
+    fn compare_index_entries(&mut self, table: &SchemaRow, index: &SchemaIndex, layout: &IndexLayout, mut expected_entries: SortedRows, mut entries: SortedRows) -> Result<(), DatabaseError> {
+        let text_encoding = self.pager.text_encoding;
+        let next_entry = |sorted_entries: &mut SortedRows| -> Result<Option<Vec<Value>>, DatabaseError> {
+            Ok(sorted_entries.next().transpose()?.map(|row| row.column_values))
+        };
+        let mut expected_entry = next_entry(&mut expected_entries)?;
+        let mut entry = next_entry(&mut entries)?;
+        let mut mismatch_count = 0;
+        loop {
+            let ordering = match (&expected_entry, &entry) {
+                                (Some(expected_entry), Some(entry)) => compare_entries(expected_entry, entry, &[], text_encoding),
+                                (Some(_), None) => Ordering::Less,
+                                (None, Some(_)) => Ordering::Greater,
+                                (None, None) => break
+                           };
+            let mismatch = match ordering {
+                                Ordering::Equal => {
+                                    expected_entry = next_entry(&mut expected_entries)?;
+                                    entry = next_entry(&mut entries)?;
+                                    None
+                                },
+                                Ordering::Less => {
+                                    let missing_entry = std::mem::replace(&mut expected_entry, next_entry(&mut expected_entries)?).unwrap_or_default();
+                                    match missing_entry.last() {
+                                        _ if index.sql.partial => None,
+                                        Some(Value::Integer(row_id)) if layout.has_rowid => Some(format!("row {} is missing from index {}", row_id, index.name)),
+                                        _ => Some(format!("row {} is missing from index {}", describe_entry(&missing_entry), index.name))
+                                    }
+                                },
+                                Ordering::Greater => {
+                                    let extra_entry = std::mem::replace(&mut entry, next_entry(&mut entries)?).unwrap_or_default();
+                                    Some(format!("index {} has an entry {} that matches no row of {}", index.name, describe_entry(&extra_entry), table.table_name))
+                                }
+                           };
+            if let Some(mismatch) = mismatch {
+                mismatch_count += 1;
+                if mismatch_count <= MAX_REPORTED_MISMATCHES {
+                    self.problems.push(mismatch);
+                }
+            }
+        }
+
+        if mismatch_count > MAX_REPORTED_MISMATCHES {
+            self.problems.push(format!("index {}: {} more entries don't match {}", index.name, mismatch_count - MAX_REPORTED_MISMATCHES, table.table_name));
+        }
+        Ok(())
+    }
+}
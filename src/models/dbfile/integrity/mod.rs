@@ -0,0 +1,2 @@
+pub mod integritycheck;
+pub use integritycheck::IntegrityCheck;
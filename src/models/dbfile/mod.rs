@@ -1,4 +1,18 @@
+pub mod header;
+pub use header::DatabaseHeader;
 pub mod schema;
+pub mod storage;
+pub use storage::Storage;
+pub mod pager;
+pub use pager::Pager;
+pub mod wal;
+pub use wal::Wal;
+pub mod freelist;
+pub use freelist::Freelist;
+pub mod pagemap;
+pub use pagemap::{PageMap, PageOwner};
+pub mod integrity;
+pub use integrity::IntegrityCheck;
 pub mod dbfile;
 pub use dbfile::DBFile;
 
@@ -6,3 +20,6 @@ pub use dbfile::DBFile;
 pub mod dbtable;
 pub use dbtable::table;
 
+
+pub mod dbindex;
+pub use dbindex::index;
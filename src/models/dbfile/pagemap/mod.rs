@@ -0,0 +1,5 @@
+pub mod pageowner;
+pub use pageowner::PageOwner;
+
+pub mod pagemap;
+pub use pagemap::PageMap;
@@ -0,0 +1,251 @@
+use crate::utils::varint::parse_varint;
+use crate::models::dbfile::dbtable::tablepage::MAX_BTREE_DEPTH;
+use crate::models::dbfile::dbtable::tablepage::overflow::{index_local_payload_size, local_payload_size};
+use crate::models::dbfile::freelist::Freelist;
+use crate::models::dbfile::header::DatabaseHeader;
+use crate::models::dbfile::pagemap::PageOwner;
+use crate::models::dbfile::pager::Pager;
+use crate::models::dbfile::schema::SchemaRAW;
+use crate::models::error::DatabaseError;
+
+// What owns each page of the file, found by walking every b-tree in the schema down to its
+// overflow chains, the freelist and, in auto-vacuum databases, the pointer map.
+// A page claimed by two owners means the file is corrupt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageMap {
+    owners: Vec<PageOwner>      // owners[n - 1] owns page n
+}
+
+impl PageMap {
+    // Fails on the first corruption found; `scan` carries on and collects every problem instead.
+    pub fn build(pager: &mut Pager, header: &DatabaseHeader, schema: &SchemaRAW) -> Result<Self, DatabaseError> {
+        let (page_map, problems) = Self::scan(pager, header, schema)?;
+        match problems.into_iter().next() {
+            Some(problem) => Err(problem),
+            None => Ok(page_map)
+        }
+    }
+
+    // Claims every page it can reach, noting each corruption found on the way. The parts of the
+    // file below a corrupt page are left unclaimed. Only I/O errors end the walk.
+    pub fn scan(pager: &mut Pager, header: &DatabaseHeader, schema: &SchemaRAW) -> Result<(Self, Vec<DatabaseError>), DatabaseError> {
+        let mut page_map = PageMap { owners: vec![PageOwner::Unused; pager.page_count as usize] };
+        let mut problems: Vec<DatabaseError> = Vec::new();
+
+        if header.largest_root_page != 0 {
+            for page_number in Self::pointer_map_pages(pager.page_count, pager.usable_page_size) {
+                note(&mut problems, page_map.claim(page_number, PageOwner::PointerMap, 0))?;
+            }
+        }
+
+        match Freelist::read(pager, header.freelist_trunk_page) {
+            Ok(freelist) => {
+                for &page_number in freelist.trunk_pages.iter() {
+                    note(&mut problems, page_map.claim(page_number, PageOwner::FreelistTrunk, 0))?;
+                }
+                for &page_number in freelist.leaf_pages.iter() {
+                    note(&mut problems, page_map.claim(page_number, PageOwner::FreelistLeaf, 0))?;
+                }
+            },
+            Err(e) => {
+                note(&mut problems, Err(e))?;
+            }
+        }
+
+        page_map.claim_btree(pager, 1, "sqlite_schema", &mut problems)?;
+        for (name, rootpage) in schema.btree_roots() {
+            page_map.claim_btree(pager, rootpage, &name, &mut problems)?;
+        }
+
+        Ok((page_map, problems))
+    }
+
+    // Pointer map pages come right after page 1 and then after every run of pages they describe,
+    // each holding a 5-byte entry per page.
+    pub fn pointer_map_pages(page_count: u32, usable_page_size: usize) -> Vec<u32> {
+        let entries_per_page = (usable_page_size / 5) as u32;
+        let mut pages = Vec::new();
+        let mut page_number = 2;
+        while page_number <= page_count {
+            pages.push(page_number);
+            page_number += entries_per_page + 1;
+        }
+        pages
+    }
+
+    pub fn owner(&self, page_number: u32) -> Option<&PageOwner> {
+        self.owners.get(page_number.checked_sub(1)? as usize)
+    }
+
+    pub fn page_count(&self) -> u32 {
+        self.owners.len() as u32
+    }
+
+    // Page numbers whose owner satisfies `predicate`, in ascending order.
+    pub fn pages_where<F: Fn(&PageOwner) -> bool>(&self, predicate: F) -> Vec<u32> {
+        self.owners.iter()
+                   .enumerate()
+                   .filter(|(_, owner)| predicate(owner))
+                   .map(|(index, _)| index as u32 + 1)
+                   .collect()
+    }
+
+    pub fn unused_pages(&self) -> Vec<u32> {
+        self.pages_where(|owner| *owner == PageOwner::Unused)
+    }
+
+    fn claim(&mut self, page_number: u32, owner: PageOwner, referenced_from: u32) -> Result<(), DatabaseError> {
+        let page_count = self.owners.len();
+        let slot = page_number.checked_sub(1)
+                              .and_then(|index| self.owners.get_mut(index as usize))
+                              .ok_or_else(|| DatabaseError::corrupt(referenced_from, 0, format!("page {} is out of range (database has {} pages)", page_number, page_count)))?;
+        if *slot != PageOwner::Unused {
+            return Err(DatabaseError::corrupt(page_number, 0, format!("page is used by both the {} and the {}", slot, owner)));
+        }
+        *slot = owner;
+        Ok(())
+    }
+
+    // Claims every page of the b-tree rooted at `rootpage` and of its cells' overflow chains.
+    // The root's page type decides whether it is a table or an index b-tree.
+    fn claim_btree(&mut self, pager: &mut Pager, rootpage: u32, name: &str, problems: &mut Vec<DatabaseError>) -> Result<(), DatabaseError> {
+        let mut pending: Vec<(u32, u32, usize)> = vec![(rootpage, 0, 0)];     // page, referring page, depth
+        let mut is_table: Option<bool> = None;
+
+        while let Some((page_number, referenced_from, depth)) = pending.pop() {
+            let result = self.claim_btree_page(pager, (page_number, referenced_from, depth), name, &mut is_table, &mut pending, problems);
+            note(problems, result)?;
+        }
+        Ok(())
+    }
+
+    // Claims one b-tree page and queues its children. A page that can't be claimed or parsed is
+    // an error, and none of its children are queued; a bad overflow chain or overlapping cells
+    // are noted in `problems` without stopping the walk.
+    fn claim_btree_page(&mut self, pager: &mut Pager, (page_number, referenced_from, depth): (u32, u32, usize), name: &str,
+                        is_table: &mut Option<bool>, pending: &mut Vec<(u32, u32, usize)>, problems: &mut Vec<DatabaseError>) -> Result<(), DatabaseError> {
+        if depth > MAX_BTREE_DEPTH {
+            return Err(DatabaseError::corrupt(page_number, 0, "b-tree is too deep"));
+        }
+        if page_number == 0 || page_number > pager.page_count {
+            return Err(DatabaseError::corrupt(referenced_from, 0, format!("child page {} is out of range", page_number)));
+        }
+        let page_buffer = pager.get_page(page_number)?;
+        let header_offset = if page_number == 1 { 100 } else { 0 };
+        let page_type = page_buffer[header_offset];
+        let page_is_table = match page_type {
+                                0x05 | 0x0D => true,
+                                0x02 | 0x0A => false,
+                                e => return Err(DatabaseError::corrupt(page_number, header_offset, format!("page type {} is not a b-tree page", e)))
+                            };
+        if *is_table.get_or_insert(page_is_table) != page_is_table {
+            return Err(DatabaseError::corrupt(page_number, header_offset, "table and index pages are mixed in one b-tree"));
+        }
+        let owner = if page_is_table { PageOwner::TableBTree(name.to_string()) } else { PageOwner::IndexBTree(name.to_string()) };
+        self.claim(page_number, owner, referenced_from)?;
+
+        let is_leaf = page_type == 0x0D || page_type == 0x0A;
+        let header_size = if is_leaf { 8 } else { 12 };
+        let cell_count = u16::from_be_bytes([page_buffer[header_offset+3], page_buffer[header_offset+4]]) as usize;
+        let cell_pointer_array_end = header_offset + header_size + cell_count * 2;
+        if cell_pointer_array_end > pager.usable_page_size {
+            return Err(DatabaseError::corrupt(page_number, header_offset, "cell pointer array runs past the end of the page"));
+        }
+        let read_u32 = |offset: usize| page_buffer.get(offset..offset+4).map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+
+        if !is_leaf {
+            let right_most_page_number = read_u32(header_offset + 8).unwrap();
+            pending.push((right_most_page_number, page_number, depth + 1));
+        }
+
+        let mut cell_extents: Vec<(usize, usize)> = Vec::with_capacity(cell_count);     // first byte and size of each cell
+        for cell_index in 0..cell_count {
+            let pointer_offset = header_offset + header_size + cell_index * 2;
+            let cell_pointer = u16::from_be_bytes([page_buffer[pointer_offset], page_buffer[pointer_offset+1]]) as usize;
+            if cell_pointer < cell_pointer_array_end || cell_pointer + 4 > pager.usable_page_size {
+                return Err(DatabaseError::corrupt(page_number, cell_pointer, "cell pointer out of bounds"));
+            }
+
+            let mut offset = cell_pointer;
+            if !is_leaf {
+                pending.push((read_u32(offset).unwrap(), page_number, depth + 1));
+                offset += 4;
+            }
+            // Table interior cells hold only a rowid
+            if page_type == 0x05 {
+                let (_, rowid_varint_len) = parse_varint(page_buffer.get(offset..).unwrap_or_default());
+                cell_extents.push((cell_pointer, offset + rowid_varint_len - cell_pointer));
+                continue;
+            }
+
+            let (payload_size, payload_varint_len) = parse_varint(page_buffer.get(offset..).unwrap_or_default());
+            offset += payload_varint_len;
+            if page_type == 0x0D {
+                let (_, rowid_varint_len) = parse_varint(page_buffer.get(offset..).unwrap_or_default());
+                offset += rowid_varint_len;
+            }
+            if payload_size < 0 || payload_size as u64 > pager.page_count as u64 * pager.usable_page_size as u64 {
+                return Err(DatabaseError::corrupt(page_number, cell_pointer, format!("payload size {} is larger than the database", payload_size)));
+            }
+
+            let payload_size = payload_size as usize;
+            let local_size = if page_is_table { local_payload_size(payload_size, pager.usable_page_size) } else { index_local_payload_size(payload_size, pager.usable_page_size) };
+            // SQLite never allocates less than 4 bytes for a cell
+            let overflow_pointer_size = if local_size < payload_size { 4 } else { 0 };
+            cell_extents.push((cell_pointer, (offset + local_size + overflow_pointer_size - cell_pointer).max(4)));
+            if local_size < payload_size {
+                let Some(first_overflow_page) = read_u32(offset + local_size) else {
+                    problems.push(DatabaseError::corrupt(page_number, cell_pointer, "overflow page number runs past the end of the page"));
+                    continue;
+                };
+                let overflow_page_count = (payload_size - local_size).div_ceil(pager.usable_page_size - 4);
+                let result = self.claim_overflow_chain(pager, first_overflow_page, overflow_page_count, page_number, name);
+                note(problems, result)?;
+            }
+        }
+
+        // Cells may sit in any order in the cell content area, but never on top of each other
+        cell_extents.sort();
+        for (cell_index, &(cell_start, cell_size)) in cell_extents.iter().enumerate() {
+            if cell_start + cell_size > pager.usable_page_size {
+                problems.push(DatabaseError::corrupt(page_number, cell_start, "cell extends past the end of the page"));
+            }
+            if let Some(&(next_cell_start, _)) = cell_extents.get(cell_index + 1) {
+                if cell_start + cell_size > next_cell_start {
+                    problems.push(DatabaseError::corrupt(page_number, cell_start, format!("cell overlaps the cell at offset {}", next_cell_start)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn claim_overflow_chain(&mut self, pager: &mut Pager, first_page_number: u32, page_count: usize, referenced_from: u32, name: &str) -> Result<(), DatabaseError> {
+        let mut page_number = first_page_number;
+        let mut previous_page_number = referenced_from;
+        for chain_index in 0..page_count {
+            if page_number == 0 {
+                return Err(DatabaseError::corrupt(previous_page_number, 0, "overflow chain ends before the payload is complete"));
+            }
+            self.claim(page_number, PageOwner::Overflow(name.to_string()), previous_page_number)?;
+            if chain_index + 1 < page_count {
+                let page_buffer = pager.get_page(page_number)?;
+                previous_page_number = page_number;
+                page_number = u32::from_be_bytes([page_buffer[0], page_buffer[1], page_buffer[2], page_buffer[3]]);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Corruption is noted so a walk can go on past it; I/O errors end the walk. Returns whether
+// `result` was a success.
+fn note(problems: &mut Vec<DatabaseError>, result: Result<(), DatabaseError>) -> Result<bool, DatabaseError> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(e @ DatabaseError::Io(_)) => Err(e),
+        Err(problem) => {
+            problems.push(problem);
+            Ok(false)
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::fmt;
+
+// What a page of the database file is used for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageOwner {
+    TableBTree(String),      // a page of the named table's b-tree; sqlite_schema's is rooted at page 1
+    IndexBTree(String),      // a page of the named index, or of a WITHOUT ROWID table, which is stored as one
+    Overflow(String),        // part of the overflow chain of a cell in the named table or index
+    FreelistTrunk,
+    FreelistLeaf,
+    PointerMap,              // auto-vacuum databases only
+    Unused                   // nothing refers to the page: it has leaked, or is the lock-byte page
+}
+
+impl PageOwner {
+    pub fn is_freelist(&self) -> bool {
+        matches!(self, PageOwner::FreelistTrunk | PageOwner::FreelistLeaf)
+    }
+}
+
+impl fmt::Display for PageOwner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PageOwner::TableBTree(name) => write!(f, "table b-tree {}", name),
+            PageOwner::IndexBTree(name) => write!(f, "index b-tree {}", name),
+            PageOwner::Overflow(name) => write!(f, "overflow chain of {}", name),
+            PageOwner::FreelistTrunk => write!(f, "freelist trunk"),
+            PageOwner::FreelistLeaf => write!(f, "freelist leaf"),
+            PageOwner::PointerMap => write!(f, "pointer map"),
+            PageOwner::Unused => write!(f, "unused")
+        }
+    }
+}
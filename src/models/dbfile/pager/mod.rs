@@ -0,0 +1,5 @@
+pub mod pager;
+pub use pager::{Pager, CacheStats};
+
+pub mod pagecache;
+pub use pagecache::PageCache;
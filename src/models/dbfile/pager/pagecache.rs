@@ -0,0 +1,75 @@
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+// Bounded least-recently-used cache of pages keyed by page number.
+// Every access stamps the page with an increasing tick; `recency` orders
+// pages by their last tick so the oldest one can be evicted first.
+#[derive(Debug)]
+pub struct PageCache {
+    capacity: usize,
+    tick: u64,
+    pages: HashMap<u32, (Rc<[u8]>, u64)>,
+    recency: BTreeMap<u64, u32>
+}
+
+impl PageCache {
+    pub fn new(capacity: usize) -> Self {
+        PageCache { capacity: capacity.max(1), tick: 0, pages: HashMap::new(), recency: BTreeMap::new() }
+    }
+
+    pub fn get(&mut self, page_number: u32) -> Option<Rc<[u8]>> {
+        let tick = self.next_tick();
+        let (page, last_used) = self.pages.get_mut(&page_number)?;
+        self.recency.remove(last_used);
+        self.recency.insert(tick, page_number);
+        *last_used = tick;
+        Some(Rc::clone(page))
+    }
+
+    pub fn insert(&mut self, page_number: u32, page: Rc<[u8]>) {
+        let tick = self.next_tick();
+        if let Some((_, last_used)) = self.pages.insert(page_number, (page, tick)) {
+            self.recency.remove(&last_used);
+        }
+        self.recency.insert(tick, page_number);
+        self.evict_to(self.capacity);
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.evict_to(self.capacity);
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    pub fn contains(&self, page_number: u32) -> bool {
+        self.pages.contains_key(&page_number)
+    }
+
+    pub fn clear(&mut self) {
+        self.pages.clear();
+        self.recency.clear();
+    }
+
+    fn evict_to(&mut self, capacity: usize) {
+        while self.pages.len() > capacity {
+            let Some((_, page_number)) = self.recency.pop_first() else { break };
+            self.pages.remove(&page_number);
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+}
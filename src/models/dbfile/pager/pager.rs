@@ -0,0 +1,92 @@
+use std::io;
+use std::rc::Rc;
+use crate::models::dbfile::header::TextEncoding;
+use crate::models::dbfile::pager::PageCache;
+use crate::models::dbfile::storage::Storage;
+use crate::models::dbfile::wal::Wal;
+use crate::models::error::DatabaseError;
+
+// Matches SQLite's default `PRAGMA cache_size = -2000`, i.e. roughly 2000 KiB of pages.
+pub const DEFAULT_CACHE_BUDGET: usize = 2000 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub cached_pages: usize
+}
+
+// Owns the database storage and hands out pages by their 1-based page number.
+// Pages are read once and then served from a bounded LRU cache whose size is
+// derived from a memory budget in bytes. With a WAL attached, committed pages in the
+// WAL take precedence over the main file.
+#[derive(Debug)]
+pub struct Pager {
+    storage: Box<dyn Storage>,
+    pub page_size: usize,
+    pub usable_page_size: usize,
+    pub page_count: u32,
+    pub text_encoding: TextEncoding,
+    pub wal: Option<Wal>,
+    cache: PageCache,
+    hits: u64,
+    misses: u64
+}
+
+impl Pager {
+    pub fn new<S: Storage + 'static>(mut storage: S, page_size: usize, usable_page_size: usize, text_encoding: TextEncoding) -> io::Result<Self> {
+        let file_size = storage.size()?;
+        let page_count = (file_size / page_size as u64).min(u32::MAX as u64) as u32;
+        let cache = PageCache::new(DEFAULT_CACHE_BUDGET / page_size);
+        Ok(Pager { storage: Box::new(storage), page_size, usable_page_size, page_count, text_encoding, wal: None, cache, hits: 0, misses: 0 })
+    }
+
+    pub fn get_page(&mut self, page_number: u32) -> Result<Rc<[u8]>, DatabaseError> {
+        if let Some(page) = self.cache.get(page_number) {
+            self.hits += 1;
+            return Ok(page);
+        }
+        self.misses += 1;
+
+        if page_number == 0 || page_number > self.page_count {
+            return Err(DatabaseError::corrupt(page_number, 0, format!("page number out of range (database has {} pages)", self.page_count)));
+        }
+        let mut page_buffer = vec![0u8; self.page_size];
+        let read_from_wal = match &mut self.wal {
+                                Some(wal) => wal.read_page(page_number, &mut page_buffer)?,
+                                None => false
+                            };
+        if !read_from_wal {
+            let start = self.page_size as u64 * (page_number - 1) as u64;
+            self.storage.read_exact_at(start, &mut page_buffer)?;
+        }
+
+        let page: Rc<[u8]> = Rc::from(page_buffer);
+        self.cache.insert(page_number, Rc::clone(&page));
+        Ok(page)
+    }
+
+    // The database size recorded by the last commit in the WAL replaces the main file's size,
+    // since the WAL may have grown or shrunk the database. Cached pages may be stale, so they are dropped.
+    pub fn attach_wal(&mut self, wal: Wal) {
+        if let Some(database_size) = wal.database_size {
+            self.page_count = database_size;
+        }
+        self.cache.clear();
+        self.wal = Some(wal);
+    }
+
+    // Resizes the cache so that it holds at most `budget` bytes worth of pages (at least one page).
+    pub fn set_cache_budget(&mut self, budget: usize) {
+        self.cache.set_capacity(budget / self.page_size);
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats { hits: self.hits, misses: self.misses, cached_pages: self.cache.len() }
+    }
+
+    pub fn reset_cache_stats(&mut self) {
+        self.hits = 0;
+        self.misses = 0;
+    }
+}
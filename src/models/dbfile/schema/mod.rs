@@ -1,18 +1,15 @@
-use std::io::{Read, Seek, SeekFrom};
+use crate::models::dbfile::pager::Pager;
+use crate::models::error::DatabaseError;
 
-pub fn extract_raw_schema_data<R: Read + Seek>(file: &mut R) -> SchemaRAW {
-    // Read the 2-byte page size at offset 16
-    let mut page_size_buffer = [0u8; 2];
-    file.seek(SeekFrom::Start(16)).expect("seek failed");
-    file.read_exact(&mut page_size_buffer).expect("failed to read page size");
-    let page_size = u16::from_be_bytes(page_size_buffer);
-
-    // Read the full schema page
-    let mut schema_raw_buffer = vec![0; page_size as usize];
-    file.seek(SeekFrom::Start(0)).expect("seek failed");
-    file.read_exact(&mut schema_raw_buffer).expect("failed to read schema page");
-
-    SchemaRAW::from_bytes(&schema_raw_buffer)
+pub fn extract_raw_schema_data(pager: &mut Pager) -> Result<SchemaRAW, DatabaseError> {
+    // The schema b-tree is rooted at page 1, right after the 100-byte file header
+    let schema_page = pager.get_page(1)?;
+    let mut schema = SchemaRAW::from_bytes(&schema_page)?;
+    schema.read_records(pager)?;
+    // Parse every CREATE statement up front so a malformed schema fails here rather than mid-query
+    schema.to_schema_rows()?;
+    schema.to_schema_indexes()?;
+    Ok(schema)
 }
 
 
@@ -20,4 +17,7 @@ pub mod schemaraw;
 pub use schemaraw::SchemaRAW;
 
 pub mod schemarow;
-pub use schemarow::SchemaRow;
\ No newline at end of file
+pub use schemarow::SchemaRow;
+
+pub mod schemaindex;
+pub use schemaindex::SchemaIndex;
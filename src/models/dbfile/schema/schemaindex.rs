@@ -0,0 +1,9 @@
+use crate::command::sql::parser::sql_statement::CreateIndexStatement;
+
+#[derive(Debug)]
+pub struct SchemaIndex {
+    pub name: String,               // index name
+    pub table_name: String,         // table the index belongs to
+    pub rootpage: u32,              // root b-tree page number
+    pub sql: CreateIndexStatement   // CREATE INDEX statement
+}
@@ -1,125 +1,125 @@
 use crate::command::sql::parser::sql_statement::{CreateIndexStatement, CreateTableStatement};
 use crate::command::sql::parser::sql_token::Tokenize;
-use crate::models::dbfile::schema::SchemaRow;
-use crate::utils::varint::parse_varint;
-use crate::sql::parser::SQLToken;
+use crate::models::dbfile::schema::{SchemaIndex, SchemaRow};
+use crate::models::dbfile::dbtable::tablepage::{Record, Value};
+use crate::models::dbfile::dbtable::TableCursor;
+use crate::models::dbfile::pager::Pager;
+use crate::models::dbfile::header::DatabaseHeader;
+use crate::models::error::DatabaseError;
+
+// The schema table's own definition, which SQLite never stores in the file.
+pub const SQLITE_SCHEMA_SQL: &str = "CREATE TABLE sqlite_schema (type text, name text, tbl_name text, rootpage integer, sql text)";
+
+// The type, name, tbl_name, rootpage and sql of a sqlite_schema row.
+type SchemaColumns = (String, String, String, u32, String);
 
 pub struct SchemaRAW {
-    pub page_size: u16,
+    pub page_size: u32,
     pub file_header: [u8; 100],
-    pub page_header: [u8; 8],
+    pub page_header: Vec<u8>,
     pub cell_pointer_array: Vec<u16>,
-    pub cells: Vec<Vec<u8>>
+    pub records: Vec<Record>
 }
 
 
 impl SchemaRAW {
-    pub fn from_bytes(data: &[u8]) -> Self {
-        let page_size: u16 =  u16::from_be_bytes([data[16], data[17]]);
+    // Parses the file header and the b-tree header of page 1. The schema rows
+    // themselves are filled in by `read_records`, since sqlite_schema can span many pages.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, DatabaseError> {
+        if data.len() < 112 {
+            return Err(DatabaseError::corrupt(1, 0, "schema page is shorter than its headers"));
+        }
+        let page_size: u32 = DatabaseHeader::decode_page_size([data[16], data[17]]);
         let file_header = data[0..100].try_into().unwrap();
-        let page_header: [u8; 8] =  match data[100] {
-                                        0x0D => data[100..108].try_into().unwrap(),
-                                        0x05 => data[100..112].try_into().unwrap(),
-                                        _ => panic!("Unrecognized page type id for schema page")
+        let page_header: Vec<u8> =  match data[100] {
+                                        0x0D => data[100..108].to_vec(),
+                                        0x05 => data[100..112].to_vec(),
+                                        e => return Err(DatabaseError::corrupt(1, 100, format!("unrecognized page type id {} for schema page", e)))
                                     };
         let cell_count = u16::from_be_bytes([page_header[3], page_header[4]]);
 
-        let cell_pointer_array: Vec<u16> = data[108..(108 + (cell_count * 2)) as usize]
+        let cell_pointer_array_offset = 100 + page_header.len();
+        let cell_pointer_array: Vec<u16> = data.get(cell_pointer_array_offset..cell_pointer_array_offset + (cell_count as usize * 2))
+                                            .ok_or_else(|| DatabaseError::corrupt(1, cell_pointer_array_offset, "cell pointer array runs past the end of the page"))?
                                             .chunks_exact(2)
                                             .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
                                             .collect();
 
-        let mut cells: Vec<Vec<u8>> = Vec::new();
-        for index in 0..cell_pointer_array.len() {
-            let cell_pointer = cell_pointer_array[index] as usize;
-            let mut offset = cell_pointer;
-            // Parse payload size varint
-            let (payload_size, len1) = parse_varint(&data[offset..]);
-            offset += len1;
-
-            // Parse rowid varint
-            let (_rowid, len2) = parse_varint(&data[offset..]);
-            offset += len2;
+        return Ok(Self { page_size, file_header, page_header, cell_pointer_array, records: Vec::new() })
+    }
 
-            // Now offset points to start of payload
-            let cell_end = offset + payload_size as usize;
+    // Walks the sqlite_schema b-tree rooted at page 1 the same way a user table is read.
+    pub fn read_records(&mut self, pager: &mut Pager) -> Result<(), DatabaseError> {
+        let table_description = CreateTableStatement::from_tokens(SQLITE_SCHEMA_SQL.tokenize())
+                                                     .map_err(|e| DatabaseError::MalformedSchema(e.to_string()))?;
+        self.records = TableCursor::new(pager, 1, table_description).collect::<Result<Vec<Record>, DatabaseError>>()?;
+        Ok(())
+    }
 
-            let cell = data[cell_pointer..cell_end].to_vec();
-            cells.push(cell);
-        }
-        return Self { page_size, file_header, page_header, cell_pointer_array, cells }
+    // Views and triggers have rootpage 0; a rootpage that can't be a page number means the schema is corrupt.
+    fn to_schema_columns(record: &Record) -> Result<Option<SchemaColumns>, DatabaseError> {
+        let mut column_values = record.column_values.iter().cloned();
+        let object_type = column_values.next().unwrap_or(Value::Null).to_string();
+        let name = column_values.next().unwrap_or(Value::Null).to_string();
+        let table_name = column_values.next().unwrap_or(Value::Null).to_string();
+        let rootpage = match column_values.next() {
+                            Some(Value::Integer(n)) => u32::try_from(n).map_err(|_| DatabaseError::MalformedSchema(format!("{}: rootpage {} is out of range", name, n)))?,
+                            _ => 0
+                       };
+        let sql_string = match column_values.next() {
+                            Some(Value::Text(sql)) => sql,
+                            _ => return Ok(None)
+                         };
+        Ok(Some((object_type, name, table_name, rootpage, sql_string)))
     }
 
-    pub fn to_schema_rows(&self) -> Vec<SchemaRow> {
+    pub fn to_schema_rows(&self) -> Result<Vec<SchemaRow>, DatabaseError> {
         let mut header_entries = Vec::new();
-        for cells_index in 0..self.cells.len() {
-            let cell = &self.cells[cells_index];
-            let (_, cell_size_varint_size) = parse_varint(&cell[0..9]);
-            let (_, row_id_varint_size) = parse_varint(&cell[cell_size_varint_size..cell_size_varint_size+9]);
-            
-            let cell_content_offset = cell_size_varint_size + row_id_varint_size;
-            let record_data = &cell[cell_content_offset..];
-
-            let record_header_value_sizes: Vec<i64>  = {
-                let mut record_header_values: Vec<i64> = Vec::new();
-                // let mut record_header_values: Vec<Vec<u8>> = Vec::new();
-
-                let (record_header_size, record_header_varint_size) = parse_varint(&record_data[0..9]);
-                record_header_values.push(record_header_size);
-                
-                let mut record_header_value_index = record_header_varint_size;
-                while record_header_value_index < record_header_size as usize {
-                    let (mut record_header_value_size, record_header_value_varint_size)  = parse_varint(&record_data[record_header_value_index..record_header_value_index+9]);
-                    if [0, 8, 9, 12, 13].contains(&record_header_value_size) {
-                        record_header_value_size = 0;
-                    } else if (record_header_value_size >= 12) & (record_header_value_size % 2 == 0) {
-                        record_header_value_size = (record_header_value_size-12)/2
-                    } else if (record_header_value_size >= 13) & (record_header_value_size % 2 != 0) {
-                        record_header_value_size = (record_header_value_size-13)/2
-
-                    }
-                    record_header_values.push(record_header_value_size);
-                    record_header_value_index += record_header_value_varint_size;
-                }
+        for record in self.records.iter() {
+            let Some((object_type, name, table_name, rootpage, sql_string)) = Self::to_schema_columns(record)? else { continue };
 
-                record_header_values
-            };
-            
-            let mut record_body_offset = record_header_value_sizes[0] as usize; 
-            let record_body = &record_data[record_body_offset..];
-
-            let object_type_bytes = record_body[..record_header_value_sizes[1] as usize].to_vec();
-            let object_type = String::from_utf8(object_type_bytes).unwrap();
-            
-            record_body_offset = record_header_value_sizes[1] as usize;
-            let name_bytes = record_body[record_body_offset..record_body_offset+record_header_value_sizes[2] as usize].to_vec();
-            let name = String::from_utf8(name_bytes).unwrap();
-            
-            record_body_offset = record_body_offset+record_header_value_sizes[2] as usize;
-           
-            let table_name_bytes = record_body[record_body_offset..(record_body_offset+record_header_value_sizes[3] as usize).min(record_body.len())].to_vec();
-            let table_name = String::from_utf8(table_name_bytes).unwrap();
-
-            record_body_offset = (record_body_offset+record_header_value_sizes[3] as usize).min(record_body.len());
-            let rootpage_byte = record_body[record_body_offset];
-            let rootpage = rootpage_byte as i8;
-
-            record_body_offset = record_body_offset+record_header_value_sizes[4] as usize;
-            let sql_bytes = record_body[record_body_offset..].to_vec();
-            let sql_string = String::from_utf8(sql_bytes).unwrap();
+            // Only tables are described here; indexes are read by `to_schema_indexes`, views and triggers are skipped
             let sql_string_tokens = sql_string.tokenize();
-            if sql_string_tokens.len() < 2 { continue; }
-            let sql = if let SQLToken::Identifier(token) = &sql_string_tokens[1] {
-                            match token.as_str() {
-                                "TABLE" => CreateTableStatement::from_tokens(sql_string_tokens),
-                                "INDEX" => continue,
-                                _ => panic!()
-                            }
-                       } else { panic!() };
+            let sql = match sql_string_tokens.get(1) {
+                            Some(token) if token.is_keyword("TABLE") => CreateTableStatement::from_tokens(sql_string_tokens)
+                                                                                                          .map_err(|e| DatabaseError::MalformedSchema(format!("{}: {}", name, e)))?,
+                            _ => continue
+                       };
 
             let schemarow_header = SchemaRow { object_type, name, table_name, rootpage, sql };
             header_entries.push(schemarow_header);        
         }
-        return header_entries;
+        return Ok(header_entries);
+    }
+
+    // Name and root page of every object stored in a b-tree of its own, the indexes SQLite creates
+    // for UNIQUE and PRIMARY KEY constraints included. Views and triggers have no b-tree (rootpage 0).
+    pub fn btree_roots(&self) -> Vec<(String, u32)> {
+        let mut roots = Vec::new();
+        for record in self.records.iter() {
+            let name = record.column_values.get(1).cloned().unwrap_or(Value::Null).to_string();
+            match record.column_values.get(3) {
+                Some(Value::Integer(rootpage)) if *rootpage > 0 && *rootpage <= u32::MAX as i64 => roots.push((name, *rootpage as u32)),
+                _ => continue
+            }
+        }
+        return roots;
+    }
+
+    // Indexes SQLite creates on its own for UNIQUE and PRIMARY KEY constraints have no sql
+    // and are left out, since their columns can only be recovered from the table definition.
+    pub fn to_schema_indexes(&self) -> Result<Vec<SchemaIndex>, DatabaseError> {
+        let mut indexes = Vec::new();
+        for record in self.records.iter() {
+            let Some((object_type, name, table_name, rootpage, sql_string)) = Self::to_schema_columns(record)? else { continue };
+            if object_type != "index" {
+                continue;
+            }
+
+            let sql = CreateIndexStatement::from_tokens(sql_string.tokenize())
+                                           .map_err(|e| DatabaseError::MalformedSchema(format!("{}: {}", name, e)))?;
+            indexes.push(SchemaIndex { name, table_name, rootpage, sql });
+        }
+        return Ok(indexes);
     }
 }
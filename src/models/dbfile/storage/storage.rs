@@ -0,0 +1,108 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+// Where the bytes of a database come from. The pager only ever asks for whole pages at known
+// offsets, so anything that can report its size and read at an offset will do: a file, a buffer
+// already in memory or, with the `mmap` feature, a memory-mapped file.
+pub trait Storage: fmt::Debug {
+    // Size of the database in bytes.
+    fn size(&mut self) -> io::Result<u64>;
+
+    // Fills `buffer` with the bytes starting at `offset`, failing with UnexpectedEof if the
+    // storage ends first.
+    fn read_exact_at(&mut self, offset: u64, buffer: &mut [u8]) -> io::Result<()>;
+}
+
+fn read_slice_at(bytes: &[u8], offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+    let start = usize::try_from(offset).unwrap_or(usize::MAX);
+    let source = start.checked_add(buffer.len())
+                      .and_then(|end| bytes.get(start..end))
+                      .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "read past the end of the database"))?;
+    buffer.copy_from_slice(source);
+    Ok(())
+}
+
+fn read_from_reader<R: Read + Seek>(reader: &mut R, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(buffer)
+}
+
+impl Storage for File {
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn read_exact_at(&mut self, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+        read_from_reader(self, offset, buffer)
+    }
+}
+
+impl Storage for Vec<u8> {
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    fn read_exact_at(&mut self, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+        read_slice_at(self, offset, buffer)
+    }
+}
+
+impl Storage for &[u8] {
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    fn read_exact_at(&mut self, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+        read_slice_at(self, offset, buffer)
+    }
+}
+
+// Lets `DBFile::from_bytes` take either an owned buffer or a borrowed one such as an `include_bytes!` asset.
+impl Storage for Cow<'_, [u8]> {
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    fn read_exact_at(&mut self, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+        read_slice_at(self, offset, buffer)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl Storage for memmap2::Mmap {
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    fn read_exact_at(&mut self, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+        read_slice_at(self, offset, buffer)
+    }
+}
+
+// Any other source that can seek, such as a `Cursor`.
+#[derive(Debug)]
+pub struct ReaderStorage<R: Read + Seek + fmt::Debug> {
+    reader: R
+}
+
+impl<R: Read + Seek + fmt::Debug> ReaderStorage<R> {
+    pub fn new(reader: R) -> Self {
+        ReaderStorage { reader }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read + Seek + fmt::Debug> Storage for ReaderStorage<R> {
+    fn size(&mut self) -> io::Result<u64> {
+        self.reader.seek(SeekFrom::End(0))
+    }
+
+    fn read_exact_at(&mut self, offset: u64, buffer: &mut [u8]) -> io::Result<()> {
+        read_from_reader(&mut self.reader, offset, buffer)
+    }
+}
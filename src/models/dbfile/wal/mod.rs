@@ -0,0 +1,23 @@
+pub mod walheader;
+pub use walheader::{WalHeader, WAL_HEADER_SIZE};
+
+pub mod walframe;
+pub use walframe::{WalFrameHeader, WAL_FRAME_HEADER_SIZE};
+
+pub mod wal;
+pub use wal::Wal;
+
+// The WAL checksum: a pair of running sums over the data taken as 32-bit words, two at a time.
+// The words are big-endian when the WAL magic number says so, little-endian otherwise.
+pub fn wal_checksum(data: &[u8], big_endian: bool, initial: [u32; 2]) -> [u32; 2] {
+    let [mut s0, mut s1] = initial;
+    for chunk in data.chunks_exact(8) {
+        let word = |bytes: &[u8]| {
+            let bytes: [u8; 4] = bytes.try_into().unwrap();
+            if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+        };
+        s0 = s0.wrapping_add(word(&chunk[0..4])).wrapping_add(s1);
+        s1 = s1.wrapping_add(word(&chunk[4..8])).wrapping_add(s0);
+    }
+    [s0, s1]
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use crate::models::dbfile::wal::{wal_checksum, WalFrameHeader, WalHeader, WAL_FRAME_HEADER_SIZE, WAL_HEADER_SIZE};
+use crate::models::error::DatabaseError;
+
+// The write-ahead log next to a database in WAL mode. Committed transactions that haven't
+// been checkpointed yet only exist here, so pages are looked up in the WAL before the main file.
+// Frames are only trusted up to the last commit frame of the unbroken run of frames whose
+// salts and checksums are valid; anything after that is an unfinished or stale write.
+#[derive(Debug)]
+pub struct Wal {
+    file: File,
+    pub header: Option<WalHeader>,
+    pub page_size: usize,
+    pub frame_count: u32,                // committed frames, i.e. the frames a reader may use
+    pub database_size: Option<u32>,      // size in pages as of the last commit in the WAL
+    frames: HashMap<u32, u32>            // page number to the latest committed frame holding it (1-based)
+}
+
+impl Wal {
+    // The WAL lives next to the database in a file with "-wal" appended to its name.
+    pub fn path_for(database_path: &Path) -> PathBuf {
+        let mut wal_path = database_path.as_os_str().to_owned();
+        wal_path.push("-wal");
+        PathBuf::from(wal_path)
+    }
+
+    // Returns None when there is no WAL file. A WAL whose header is invalid or written for a
+    // different page size holds no usable frames.
+    pub fn open(path: &Path, page_size: usize) -> Result<Option<Self>, DatabaseError> {
+        let file = match File::open(path) {
+                        Ok(file) => file,
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+                        Err(e) => return Err(e)?
+                   };
+        let mut wal = Wal { file, header: None, page_size, frame_count: 0, database_size: None, frames: HashMap::new() };
+        wal.build_frame_index()?;
+        Ok(Some(wal))
+    }
+
+    fn build_frame_index(&mut self) -> Result<(), DatabaseError> {
+        self.file.seek(SeekFrom::Start(0))?;
+        let mut reader = BufReader::new(&mut self.file);
+
+        let mut header_bytes = [0u8; WAL_HEADER_SIZE];
+        if !read_full(&mut reader, &mut header_bytes)? {
+            return Ok(());
+        }
+        let Some(header) = WalHeader::from_bytes(&header_bytes) else { return Ok(()) };
+        if header.page_size as usize != self.page_size {
+            return Ok(());
+        }
+
+        let big_endian = header.big_endian_checksums();
+        let mut checksum = header.checksum;
+        let mut uncommitted: Vec<(u32, u32)> = Vec::new();
+        let mut frame_header_bytes = [0u8; WAL_FRAME_HEADER_SIZE];
+        let mut page = vec![0u8; self.page_size];
+        let mut frame_number: u32 = 0;
+
+        while read_full(&mut reader, &mut frame_header_bytes)? && read_full(&mut reader, &mut page)? {
+            let frame_header = WalFrameHeader::from_bytes(&frame_header_bytes);
+            if frame_header.salt != header.salt || frame_header.page_number == 0 {
+                break;
+            }
+            checksum = wal_checksum(&frame_header_bytes[0..8], big_endian, checksum);
+            checksum = wal_checksum(&page, big_endian, checksum);
+            if checksum != frame_header.checksum {
+                break;
+            }
+
+            frame_number += 1;
+            uncommitted.push((frame_header.page_number, frame_number));
+            if frame_header.is_commit() {
+                self.frames.extend(uncommitted.drain(..));
+                self.frame_count = frame_number;
+                self.database_size = Some(frame_header.database_size);
+            }
+        }
+
+        self.header = Some(header);
+        Ok(())
+    }
+
+    // Number of the latest committed frame holding `page_number`, if the WAL has one.
+    pub fn frame_for_page(&self, page_number: u32) -> Option<u32> {
+        self.frames.get(&page_number).copied()
+    }
+
+    // Copies the latest committed image of a page into `page_buffer`. Returns false when the
+    // page has to be read from the main database file instead.
+    pub fn read_page(&mut self, page_number: u32, page_buffer: &mut [u8]) -> Result<bool, DatabaseError> {
+        let Some(frame_number) = self.frame_for_page(page_number) else { return Ok(false) };
+        let frame_size = (WAL_FRAME_HEADER_SIZE + self.page_size) as u64;
+        let page_offset = WAL_HEADER_SIZE as u64 + (frame_number - 1) as u64 * frame_size + WAL_FRAME_HEADER_SIZE as u64;
+        self.file.seek(SeekFrom::Start(page_offset))?;
+        self.file.read_exact(page_buffer)?;
+        Ok(true)
+    }
+}
+
+// Fills the buffer, returning false if the file ends first (a torn write at the end of the WAL).
+fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buffer) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e)
+    }
+}
@@ -0,0 +1,28 @@
+pub const WAL_FRAME_HEADER_SIZE: usize = 24;
+
+// The 24-byte header in front of every page image in the WAL. All fields are big-endian.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalFrameHeader {
+    pub page_number: u32,                // offset 0
+    pub database_size: u32,              // offset 4:  size in pages after a commit frame, 0 for other frames
+    pub salt: [u32; 2],                  // offset 8:  must match the WAL header
+    pub checksum: [u32; 2]               // offset 16: cumulative over the WAL up to and including this frame
+}
+
+impl WalFrameHeader {
+    pub fn from_bytes(data: &[u8; WAL_FRAME_HEADER_SIZE]) -> Self {
+        let read_u32 = |offset: usize| u32::from_be_bytes([data[offset], data[offset+1], data[offset+2], data[offset+3]]);
+        WalFrameHeader {
+            page_number: read_u32(0),
+            database_size: read_u32(4),
+            salt: [read_u32(8), read_u32(12)],
+            checksum: [read_u32(16), read_u32(20)]
+        }
+    }
+
+    // The last frame of a transaction records the database size; a reader sees nothing
+    // past the last commit frame.
+    pub fn is_commit(&self) -> bool {
+        self.database_size != 0
+    }
+}
@@ -0,0 +1,52 @@
+use crate::models::dbfile::wal::wal_checksum;
+
+pub const WAL_HEADER_SIZE: usize = 32;
+pub const WAL_MAGIC_LITTLE_ENDIAN: u32 = 0x377f0682;
+pub const WAL_MAGIC_BIG_ENDIAN: u32 = 0x377f0683;
+pub const WAL_FORMAT_VERSION: u32 = 3007000;
+
+// The 32-byte header at the start of a -wal file
+// (https://www.sqlite.org/fileformat.html#wal_file_format). All fields are big-endian.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalHeader {
+    pub magic: u32,                      // offset 0:  0x377f0682 or 0x377f0683, picks the checksum byte order
+    pub format_version: u32,             // offset 4:  3007000
+    pub page_size: u32,                  // offset 8
+    pub checkpoint_sequence: u32,        // offset 12
+    pub salt: [u32; 2],                  // offset 16: copied into every frame of the current WAL generation
+    pub checksum: [u32; 2]               // offset 24: over the first 24 bytes of the header
+}
+
+impl WalHeader {
+    // A header that fails any check means the WAL holds no valid frames, as SQLite treats it,
+    // so this returns None rather than an error.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() < WAL_HEADER_SIZE {
+            return None;
+        }
+        let read_u32 = |offset: usize| u32::from_be_bytes([data[offset], data[offset+1], data[offset+2], data[offset+3]]);
+
+        let header = WalHeader {
+            magic: read_u32(0),
+            format_version: read_u32(4),
+            page_size: read_u32(8),
+            checkpoint_sequence: read_u32(12),
+            salt: [read_u32(16), read_u32(20)],
+            checksum: [read_u32(24), read_u32(28)]
+        };
+        if header.magic != WAL_MAGIC_LITTLE_ENDIAN && header.magic != WAL_MAGIC_BIG_ENDIAN {
+            return None;
+        }
+        if header.format_version != WAL_FORMAT_VERSION || !(512..=65536).contains(&header.page_size) || !header.page_size.is_power_of_two() {
+            return None;
+        }
+        if wal_checksum(&data[0..24], header.big_endian_checksums(), [0, 0]) != header.checksum {
+            return None;
+        }
+        Some(header)
+    }
+
+    pub fn big_endian_checksums(&self) -> bool {
+        self.magic == WAL_MAGIC_BIG_ENDIAN
+    }
+}
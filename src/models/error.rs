@@ -12,13 +12,40 @@ pub enum CommandArgsError {
     Io(#[from] io::Error), // automatically adds From<io::Error>
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum DatabaseError {
+    #[error("File is not a database")]
+    NotADatabase,
+    #[error("Database disk image is malformed: page {page}, offset {offset}: {reason}")]
+    Corrupt { page: u32, offset: usize, reason: String },
+    #[error("Database schema is malformed: {0}")]
+    MalformedSchema(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl DatabaseError {
+    pub fn corrupt<S: Into<String>>(page: u32, offset: usize, reason: S) -> Self {
+        DatabaseError::Corrupt { page, offset, reason: reason.into() }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SQLCommandError {
     #[error("No table named \"{0}\" found")]
     UnknownTable(String),
+
+    #[error("No index named \"{0}\" found")]
+    UnknownIndex(String),
+
+    #[error("No column named \"{0}\" found")]
+    UnknownColumn(String),
     
     #[error("SQL Command: \"{0}\" is not supported.")]
-    UnsupportedCommand(String)
+    UnsupportedCommand(String),
+
+    #[error("Invalid expression: {0}")]
+    InvalidExpression(String)
 }
 
 
@@ -28,7 +55,10 @@ pub enum SQLSyntaxError {
     UnexpectedToken(String),
     
     #[error("SQL Syntax Error: Value is unsupported \"{0}\".")]
-    UnsupportedValue(String)
+    UnsupportedValue(String),
+
+    #[error("SQL Syntax Error: Expression tree is too large (maximum depth {0}).")]
+    ExpressionTooDeep(usize)
 
 }
 
@@ -39,6 +69,15 @@ pub enum SQLError {
 
     #[error(transparent)]
     Syntax(#[from] SQLSyntaxError),
+
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+
+    #[error("Failed to write output: {0}")]
+    Output(#[from] io::Error),
+
+    #[error("Failed to sort rows in temporary files: {0}")]
+    TemporaryFile(io::Error),
 }
 
 
@@ -49,5 +88,11 @@ pub enum RunError {
 
     #[error(transparent)]
     Sql(#[from] SQLError),
+
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+
+    #[error("Failed to write output: {0}")]
+    Output(#[from] io::Error),
 }
 
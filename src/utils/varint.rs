@@ -24,19 +24,25 @@ pub fn parse_varint(bytes: &[u8]) -> (i64, usize) {
     (value as i64, len)
 }
 
-pub fn encode_varint(mut value: i64) -> Vec<u8> {
-    let mut varint_bytes = Vec::new();
+// The inverse of `parse_varint`: 7 bits per byte, most significant group first, with the
+// high bit set on every byte but the last. Values that need more than 56 bits take nine
+// bytes, the ninth of which holds a full 8 bits.
+pub fn encode_varint(value: i64) -> Vec<u8> {
+    let value = value as u64;
+    if value >> 56 != 0 {
+        let mut varint_bytes: Vec<u8> = (0..8).map(|byte_index| ((value >> (8 + 7 * (7 - byte_index))) & 0x7F) as u8 | 0x80)
+                                              .collect();
+        varint_bytes.push(value as u8);
+        return varint_bytes;
+    }
 
-    loop {
-        let byte = (value & 0x7F) as u8; // take 7 bits
-        value >>= 7;
-        if value != 0 {
-            varint_bytes.push(byte | 0x80); // set MSB = 1 (more bytes follow)
-        } else {
-            varint_bytes.push(byte); // last byte, MSB = 0
-            break;
-        }
+    let mut varint_bytes = vec![(value & 0x7F) as u8];
+    let mut rest = value >> 7;
+    while rest != 0 {
+        varint_bytes.push((rest & 0x7F) as u8 | 0x80);
+        rest >>= 7;
     }
+    varint_bytes.reverse();
 
     return varint_bytes
 }
@@ -37,7 +37,7 @@ fn test_from_bytes_extracts_schemaraw_data_correctly() {
     data[112..114].copy_from_slice(&(CELL3_OFFSET).to_be_bytes());
     data[CELL3_OFFSET as usize..(CELL3_OFFSET as usize + CELL3_SIZE.len())].copy_from_slice(&CELL3_SIZE);
 
-    let raw_schema_data = SchemaRAW::from_bytes(&data);
+    let raw_schema_data = SchemaRAW::from_bytes(&data).unwrap();
     assert_eq!(raw_schema_data.file_header[16..18], DB_PAGE_SIZE.to_be_bytes());
     assert_eq!(raw_schema_data.page_header[3..5], SCHEMA_PAGE_CELL_COUNT.to_be_bytes());
 
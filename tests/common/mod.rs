@@ -0,0 +1,17 @@
+// Helpers shared by the integration tests; each test file uses only some of them.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use sqlite_fsr::models::DBFile;
+
+// The rows a query returns, each as its values joined by spaces.
+pub fn row_strings(file: &mut DBFile, sql: &str) -> Vec<String> {
+    file.execute(sql).unwrap().iter().map(|row| row.to_string()).collect()
+}
+
+// Writes `data` to a database file in the temporary directory, named after the test that uses it.
+pub fn write_temp_db(name: &str, data: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("sqlite-fsr-{}-{}.db", name, std::process::id()));
+    std::fs::write(&path, data).unwrap();
+    path
+}
@@ -0,0 +1,134 @@
+use std::path::PathBuf;
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::error::{DatabaseError, SQLError};
+
+mod common;
+use common::write_temp_db;
+
+// Small deterministic xorshift generator so failures are reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+// Opens the file and runs every query we support against every table. Any of these
+// may fail on a corrupt file, but none of them may panic.
+fn exercise(path: &PathBuf) {
+    let Ok(mut file) = DBFile::open(path) else { return };
+    let _ = file.get_dbinfo();
+    let _ = file.page_map();
+    for table_name in file.get_table_names().into_iter().take(5) {
+        let _ = file.execute(format!("SELECT * FROM {}", table_name).as_str());
+        let _ = file.execute(format!("SELECT COUNT(*) FROM {}", table_name).as_str());
+    }
+}
+
+fn fuzz(asset: &str, iterations: usize, seed: u64) {
+    let original = std::fs::read(format!("./tests/assets/{}", asset)).unwrap();
+    let mut rng = Rng(seed);
+    let path = write_temp_db(&format!("fuzz-{}", asset), &original);
+
+    for _ in 0..iterations {
+        let mut data = original.clone();
+        // Concentrate mutations on the first pages, where headers and cell pointers live
+        let hot_region = data.len().min(8192);
+        for _ in 0..1 + rng.below(8) {
+            let position = if rng.below(4) == 0 { rng.below(data.len()) } else { rng.below(hot_region) };
+            data[position] = rng.next() as u8;
+        }
+        if rng.below(10) == 0 {
+            data.truncate(rng.below(data.len()));
+        }
+        std::fs::write(&path, &data).unwrap();
+        exercise(&path);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_fuzzed_sample_database_never_panics() {
+    fuzz("sample.db", 2000, 0x5EED_0001);
+}
+
+#[test]
+fn test_fuzzed_typed_database_never_panics() {
+    fuzz("types.db", 1000, 0x5EED_0002);
+}
+
+#[test]
+fn test_fuzzed_overflow_database_never_panics() {
+    fuzz("overflow.db", 100, 0x5EED_0003);
+}
+
+#[test]
+fn test_fuzzed_multi_page_schema_never_panics() {
+    fuzz("manytables.db", 20, 0x5EED_0004);
+}
+
+#[test]
+fn test_arbitrary_bytes_are_not_a_database() {
+    let mut rng = Rng(0x5EED_0005);
+    let data: Vec<u8> = (0..4096).map(|_| rng.next() as u8).collect();
+    let path = write_temp_db("random", &data);
+    assert!(matches!(DBFile::open(&path), Err(DatabaseError::NotADatabase)));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_unknown_page_type_is_reported_as_corrupt() {
+    let mut data = std::fs::read("./tests/assets/sample.db").unwrap();
+    data[4096] = 0x07;
+    let path = write_temp_db("bad-page-type", &data);
+
+    let mut file = DBFile::open(&path).unwrap();
+    let result = file.execute("SELECT * FROM apples");
+    assert!(matches!(result, Err(SQLError::Database(DatabaseError::Corrupt { page: 2, offset: 0, .. }))));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_cell_pointer_past_page_end_is_reported_as_corrupt() {
+    let mut data = std::fs::read("./tests/assets/sample.db").unwrap();
+    data[4096 + 8..4096 + 10].copy_from_slice(&5000u16.to_be_bytes());
+    let path = write_temp_db("bad-cell-pointer", &data);
+
+    let mut file = DBFile::open(&path).unwrap();
+    let result = file.execute("SELECT * FROM apples");
+    assert!(matches!(result, Err(SQLError::Database(DatabaseError::Corrupt { page: 2, offset: 5000, .. }))));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_truncated_database_fails_to_open() {
+    let data = std::fs::read("./tests/assets/sample.db").unwrap();
+    let path = write_temp_db("truncated", &data[..2000]);
+    assert!(DBFile::open(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_child_page_referenced_twice_is_reported_as_corrupt() {
+    let mut data = std::fs::read("./tests/assets/superheroes.db").unwrap();
+    // Point the first cell of the table's interior root (page 2) at the same child as the second cell
+    let page = 4096;
+    let cell_pointer = |index: usize| u16::from_be_bytes([data[page + 12 + index * 2], data[page + 13 + index * 2]]) as usize;
+    let (first_cell, second_cell) = (page + cell_pointer(0), page + cell_pointer(1));
+    let second_child: [u8; 4] = data[second_cell..second_cell + 4].try_into().unwrap();
+    data[first_cell..first_cell + 4].copy_from_slice(&second_child);
+
+    let path = write_temp_db("duplicate-child", &data);
+    let mut file = DBFile::open(&path).unwrap();
+    let result = file.execute("SELECT COUNT(*) FROM superheroes");
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(result, Err(SQLError::Database(DatabaseError::Corrupt { .. }))));
+}
@@ -0,0 +1,64 @@
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+use sqlite_fsr::models::error::{SQLCommandError, SQLError};
+
+#[test]
+fn test_table_cursor_reads_one_leaf_at_a_time() {
+    let mut file = DBFile::open("./tests/assets/superheroes.db").unwrap();
+    let mut table = file.get_table(&"superheroes".to_string()).unwrap();
+
+    let names: Vec<Value> = table.cursor()
+                                 .take(3)
+                                 .map(|record| record.unwrap().column_values[1].clone())
+                                 .collect();
+    assert_eq!(names, vec!["Batman (Bruce Wayne)", "Superman (Clark Kent)", "Green Lantern (Hal Jordan)"]);
+
+    let row_count = table.cursor().count();
+    assert_eq!(row_count, 6895);
+}
+
+#[test]
+fn test_table_cursor_seeks_by_rowid() {
+    let mut file = DBFile::open("./tests/assets/superheroes.db").unwrap();
+    let mut table = file.get_table(&"superheroes".to_string()).unwrap();
+    let mut cursor = table.cursor();
+
+    cursor.seek(4000).unwrap();
+    let first = cursor.next().unwrap().unwrap();
+    let second = cursor.next().unwrap().unwrap();
+    assert_eq!((first.row_id, second.row_id), (4000, 4001));
+    assert_eq!(second.column_values[1], "Grace Balin (New Earth)");
+
+    cursor.seek(6895).unwrap();
+    assert_eq!(cursor.next().unwrap().unwrap().row_id, 6895);
+    assert!(cursor.next().is_none());
+
+    cursor.seek(i64::MAX).unwrap();
+    assert!(cursor.next().is_none());
+}
+
+#[test]
+fn test_execute_streaming_only_reads_what_is_consumed() {
+    let mut file = DBFile::open("./tests/assets/superheroes.db").unwrap();
+    file.pager().reset_cache_stats();
+    let first_rows: Vec<String> = file.execute_streaming("SELECT name FROM superheroes").unwrap()
+                                      .take(2)
+                                      .map(|row| row.unwrap().to_string())
+                                      .collect();
+    assert_eq!(first_rows, vec!["Batman (Bruce Wayne)", "Superman (Clark Kent)"]);
+    // The schema page, the table's root and its first leaf
+    assert!(file.pager().cache_stats().misses <= 3, "read {} pages", file.pager().cache_stats().misses);
+
+    let streamed = file.execute_streaming("SELECT COUNT(*) FROM superheroes WHERE id > 100").unwrap()
+                       .collect::<Result<Vec<_>, _>>()
+                       .unwrap();
+    assert_eq!(streamed[0][0], Value::Integer(6795));
+}
+
+#[test]
+fn test_selecting_an_unknown_column_is_an_error() {
+    let mut file = DBFile::open("./tests/assets/sample.db").unwrap();
+    assert!(matches!(file.execute("SELECT nope FROM apples"), Err(SQLError::Command(SQLCommandError::UnknownColumn(column))) if column == "nope"));
+    assert!(matches!(file.execute_streaming("SELECT name, colour FROM apples").err().unwrap(), SQLError::Command(SQLCommandError::UnknownColumn(column)) if column == "colour"));
+    assert_eq!(file.execute("SELECT NAME, Color FROM apples").unwrap().len(), 4);
+}
@@ -0,0 +1,81 @@
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+use sqlite_fsr::models::dbfile::header::TextEncoding;
+
+mod common;
+use common::row_strings;
+
+const UTF16_DATABASES: [(&str, TextEncoding); 2] = [("./tests/assets/utf16le.db", TextEncoding::Utf16le), ("./tests/assets/utf16be.db", TextEncoding::Utf16be)];
+
+#[test]
+fn test_text_encoding_decodes_utf16_in_both_byte_orders() {
+    assert_eq!(TextEncoding::Utf16le.decode(&[0x5A, 0x00, 0x6F, 0x00, 0xEB, 0x00]), "Zoë");
+    assert_eq!(TextEncoding::Utf16be.decode(&[0x00, 0x5A, 0x00, 0x6F, 0x00, 0xEB]), "Zoë");
+    // A surrogate pair, and an unpaired surrogate that can't be decoded
+    assert_eq!(TextEncoding::Utf16le.decode(&[0x3C, 0xD8, 0x75, 0xDF]), "🍵");
+    assert_eq!(TextEncoding::Utf16be.decode(&[0xD8, 0x3C]), "\u{FFFD}");
+    assert_eq!(TextEncoding::Utf8.decode("Zoë".as_bytes()), "Zoë");
+}
+
+#[test]
+fn test_schema_of_utf16_database_is_decoded() {
+    for (path, text_encoding) in UTF16_DATABASES {
+        let file = DBFile::open(path).unwrap();
+        assert_eq!(file.header.text_encoding, text_encoding);
+        assert_eq!(file.get_table_names(), vec!["cafés"]);
+        assert_eq!(file.schema.to_schema_indexes().unwrap()[0].name, "cafés_by_name");
+    }
+}
+
+#[test]
+fn test_select_decodes_utf16_text_values() {
+    for (path, _) in UTF16_DATABASES {
+        let mut file = DBFile::open(path).unwrap();
+        let rows = row_strings(&mut file, "SELECT name, city FROM cafés WHERE id <= 6");
+        assert_eq!(rows, vec!["Zoë Zürich", "Ānanda Kraków", "émile Besançon", "Ωmega Αθήνα", "plain Oslo", "🍵 tea 東京"]);
+        assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM cafés WHERE city = 'Łódź'"), vec!["294"]);
+
+        // Values long enough to continue on overflow pages
+        let long_cities = file.execute("SELECT city FROM cafés").unwrap()
+                              .iter()
+                              .filter(|row| matches!(&row[0], Value::Text(city) if city.chars().count() > 300))
+                              .count();
+        assert_eq!(long_cities, 6);
+    }
+}
+
+#[test]
+fn test_index_lookup_on_non_ascii_keys_in_utf16_database() {
+    for (path, _) in UTF16_DATABASES {
+        let mut file = DBFile::open(path).unwrap();
+        assert_eq!(file.query_plan("SELECT id FROM cafés WHERE name = 'Zoë'").unwrap().to_string(), "SEARCH cafés USING INDEX cafés_by_name (name=?)");
+        // UTF-16le keys are ordered by their little-endian bytes, not by code point
+        for (name, id) in [("Zoë", "1"), ("Ānanda", "2"), ("émile", "3"), ("Ωmega", "4"), ("🍵 tea", "6"), ("ş150", "156")] {
+            assert_eq!(row_strings(&mut file, &format!("SELECT id FROM cafés WHERE name = '{}'", name)), vec![id], "{} in {}", name, path);
+        }
+    }
+}
+
+#[test]
+fn test_utf16le_text_compares_by_stored_bytes() {
+    let a_macron = Value::Text("Ā".to_string());  // 0x0101, stored as 01 01
+    let e_acute = Value::Text("é".to_string());   // 0x00E9, stored as E9 00
+    assert_eq!(a_macron.compare_encoded(&e_acute, TextEncoding::Utf16le), std::cmp::Ordering::Less);
+    assert_eq!(a_macron.compare_encoded(&e_acute, TextEncoding::Utf16be), std::cmp::Ordering::Greater);
+    assert_eq!(a_macron.compare_encoded(&e_acute, TextEncoding::Utf8), std::cmp::Ordering::Greater);
+}
+
+#[test]
+fn test_utf16_text_compares_the_same_through_the_index_and_in_a_scan() {
+    // Ā (U+0101) is stored as 01 01 in UTF-16le, before Z (5A 00), but after it in UTF-8 and UTF-16be
+    for (path, less_than_z) in [("./tests/assets/utf16le.db", vec!["Ānanda", "🍵 tea"]), ("./tests/assets/utf16be.db", vec![])] {
+        let mut file = DBFile::open(path).unwrap();
+        assert_eq!(file.query_plan("SELECT name FROM cafés WHERE name < 'Z'").unwrap().to_string(), "SEARCH cafés USING INDEX cafés_by_name (name<?)");
+        assert_eq!(file.query_plan("SELECT name FROM cafés WHERE name < 'Z' OR id < 0").unwrap().to_string(), "SCAN cafés");
+        assert_eq!(row_strings(&mut file, "SELECT name FROM cafés WHERE name < 'Z'"), less_than_z, "{}", path);
+        assert_eq!(row_strings(&mut file, "SELECT name FROM cafés WHERE name < 'Z' OR id < 0"), less_than_z, "{}", path);
+        let greater_than_z = (306 - less_than_z.len()).to_string();
+        assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM cafés WHERE name > 'Z'"), vec![greater_than_z.clone()], "{}", path);
+        assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM cafés WHERE name > 'Z' OR id < 0"), vec![greater_than_z], "{}", path);
+    }
+}
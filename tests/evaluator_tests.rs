@@ -0,0 +1,184 @@
+use sqlite_fsr::command::sql::{Evaluator, QueryPlan};
+use sqlite_fsr::command::sql::parser::expr::Expr;
+use sqlite_fsr::command::sql::parser::sql_token::Tokenize;
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::tablepage::Record;
+use sqlite_fsr::models::dbfile::dbtable::{Affinity, Value};
+use sqlite_fsr::models::dbfile::header::TextEncoding;
+use sqlite_fsr::models::error::{SQLCommandError, SQLError};
+
+// tests/assets/affinity.db holds
+//   things(id integer primary key, name text collate nocase, code varchar(10), amount integer, price real, score numeric, data blob, tag)
+// with indexes on amount and name, and the rows
+//   (1, 'Apple', '10', 40, 2.5, 7, x'01', 'a')
+//   (2, 'banana', '9', NULL, 3.0, 12, NULL, 10)
+//   (3, 'Cherry  ', 'abc', 15, NULL, 'x', 'text', '10')
+//   (4, NULL, NULL, 40, 1.0, NULL, x'', NULL)
+//   (5, 'apple', '010', -3, 0.0, 0, NULL, 2.5)
+// Expected ids are those sqlite3 returns, sorted.
+fn ids(file: &mut DBFile, where_clause: &str) -> Vec<i64> {
+    let mut ids: Vec<i64> = file.execute(format!("SELECT id FROM things WHERE {}", where_clause).as_str()).unwrap()
+                                .iter()
+                                .map(|row| match row[0] {
+                                    Value::Integer(id) => id,
+                                    ref value => panic!("expected an integer id, got {:?}", value)
+                                })
+                                .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_where_clause_is_evaluated_against_each_row() {
+    let mut file = DBFile::open("./tests/assets/sample.db").unwrap();
+    let names = |file: &mut DBFile, sql: &str| -> Vec<String> { file.execute(sql).unwrap().iter().map(|row| row.to_string()).collect() };
+    assert_eq!(names(&mut file, "SELECT name FROM apples WHERE color = 'Red'"), vec!["Fuji"]);
+    assert_eq!(names(&mut file, "SELECT name FROM apples WHERE color = 'Red' OR color = 'Yellow'"), vec!["Fuji", "Golden Delicious"]);
+    assert_eq!(names(&mut file, "SELECT name FROM apples WHERE color LIKE '%red' AND NOT name = 'Fuji'"), vec!["Honeycrisp"]);
+    assert_eq!(names(&mut file, "SELECT name FROM oranges WHERE description GLOB '*snacking' AND id > 1"), vec!["Clementine"]);
+    assert_eq!(names(&mut file, "SELECT COUNT(*) FROM oranges WHERE name LIKE '%orange' OR id IN (1, 3)"), vec!["4"]);
+}
+
+#[test]
+fn test_null_follows_three_valued_logic() {
+    let mut file = DBFile::open("./tests/assets/affinity.db").unwrap();
+    // NULL compares to nothing, so row 2 is in neither
+    assert_eq!(ids(&mut file, "amount != 40"), vec![3, 5]);
+    assert_eq!(ids(&mut file, "NOT amount = 40"), vec![3, 5]);
+    assert_eq!(ids(&mut file, "amount IS NOT 40"), vec![2, 3, 5]);
+    assert_eq!(ids(&mut file, "amount IS NULL"), vec![2]);
+    // true OR NULL is true, true AND NULL is not
+    assert_eq!(ids(&mut file, "amount = 40 OR price IS NULL"), vec![1, 3, 4]);
+    assert_eq!(ids(&mut file, "amount = 40 AND price > 2 OR price IS NULL"), vec![1, 3]);
+    // x NOT IN a list with a NULL is never true
+    assert_eq!(ids(&mut file, "amount NOT IN (40, NULL)"), vec![]);
+    assert_eq!(ids(&mut file, "amount IN ()"), vec![]);
+    assert_eq!(ids(&mut file, "coalesce(amount, 0) = 0"), vec![2]);
+    assert_eq!(ids(&mut file, "nullif(amount, 40) IS NULL"), vec![1, 2, 4]);
+}
+
+#[test]
+fn test_comparisons_apply_the_column_affinity() {
+    let mut file = DBFile::open("./tests/assets/affinity.db").unwrap();
+    // A TEXT column compares numbers as text, so '10' < '9'
+    assert_eq!(ids(&mut file, "code = 10"), vec![1]);
+    assert_eq!(ids(&mut file, "code < 9"), vec![1, 5]);
+    // INTEGER, REAL and NUMERIC columns compare text that looks like a number as one
+    assert_eq!(ids(&mut file, "amount = '40'"), vec![1, 4]);
+    assert_eq!(ids(&mut file, "amount > '20'"), vec![1, 4]);
+    assert_eq!(ids(&mut file, "price = '3.0'"), vec![2]);
+    assert_eq!(ids(&mut file, "score = '7'"), vec![1]);
+    assert_eq!(ids(&mut file, "rowid = '3'"), vec![3]);
+    // Without affinity the storage class decides: numbers sort before text, text before blobs
+    assert_eq!(ids(&mut file, "tag = 10"), vec![2]);
+    assert_eq!(ids(&mut file, "tag > 5"), vec![1, 2, 3]);
+    assert_eq!(ids(&mut file, "data > 'a'"), vec![1, 3, 4]);
+    assert_eq!(ids(&mut file, "'abc' > 5"), vec![1, 2, 3, 4, 5]);
+    // A BLOB column has an affinity, unlike a literal, so a TEXT column doesn't convert it:
+    // the text '010' of row 5 sorts after the real 2.5 rather than before the text '2.5'
+    assert_eq!(ids(&mut file, "code > tag"), vec![2, 3, 5]);
+    assert_eq!(ids(&mut file, "tag < code"), vec![2, 3, 5]);
+    assert_eq!(ids(&mut file, "tag IN ('10')"), vec![3]);
+
+    // A REAL column reads back whole numbers stored as integers as reals
+    let prices: Vec<String> = file.execute("SELECT price FROM things").unwrap().iter().map(|row| row.to_string()).collect();
+    assert_eq!(prices, vec!["2.5", "3.0", "NULL", "1.0", "0.0"]);
+
+    assert_eq!(Affinity::from_declared_type(Some("UNSIGNED BIG INT")), Affinity::Integer);
+    assert_eq!(Affinity::from_declared_type(Some("VARCHAR")), Affinity::Text);
+    assert_eq!(Affinity::from_declared_type(Some("DOUBLE PRECISION")), Affinity::Real);
+    assert_eq!(Affinity::from_declared_type(Some("DECIMAL")), Affinity::Numeric);
+    assert_eq!(Affinity::from_declared_type(None), Affinity::Blob);
+    assert_eq!(Value::Text(" 12 ".to_string()).apply_affinity(Affinity::Numeric), Value::Integer(12));
+    assert_eq!(Value::Text("1e2".to_string()).apply_affinity(Affinity::Integer), Value::Integer(100));
+    assert_eq!(Value::Text("12abc".to_string()).apply_affinity(Affinity::Numeric), Value::Text("12abc".to_string()));
+    assert_eq!(Value::Real(2.5).apply_affinity(Affinity::Text), Value::Text("2.5".to_string()));
+}
+
+#[test]
+fn test_collations_and_patterns() {
+    let mut file = DBFile::open("./tests/assets/affinity.db").unwrap();
+    // name is declared COLLATE NOCASE; an explicit COLLATE overrides it
+    assert_eq!(ids(&mut file, "name = 'APPLE'"), vec![1, 5]);
+    assert_eq!(ids(&mut file, "name COLLATE BINARY = 'apple'"), vec![5]);
+    assert_eq!(ids(&mut file, "name = 'cherry'"), vec![]);
+    assert_eq!(ids(&mut file, "name = 'Cherry' COLLATE RTRIM"), vec![3]);
+
+    // LIKE ignores ASCII case, GLOB doesn't
+    assert_eq!(ids(&mut file, "name LIKE 'a_ple'"), vec![1, 5]);
+    assert_eq!(ids(&mut file, "code LIKE '1%'"), vec![1]);
+    assert_eq!(ids(&mut file, "name GLOB 'a*'"), vec![5]);
+    assert_eq!(ids(&mut file, "name GLOB '[A-C]*'"), vec![1, 3]);
+    assert_eq!(ids(&mut file, "name GLOB '[^a]*'"), vec![1, 2, 3]);
+    assert_eq!(ids(&mut file, "name || '%' LIKE '%!%' ESCAPE '!'"), vec![1, 2, 3, 5]);
+}
+
+#[test]
+fn test_patterns_with_many_wildcards_match_in_polynomial_time() {
+    let mut file = DBFile::open("./tests/assets/affinity.db").unwrap();
+    // Trying every split of the text between twenty runs would never finish on 200 characters
+    let text = "a".repeat(200);
+    assert_eq!(ids(&mut file, &format!("'{}' LIKE '{}b'", text, "%a".repeat(20))), vec![]);
+    assert_eq!(ids(&mut file, &format!("'{}' GLOB '{}b'", text, "*a".repeat(20))), vec![]);
+    assert_eq!(ids(&mut file, &format!("'{}b' GLOB '{}[b-c]'", text, "*a".repeat(20))), vec![1, 2, 3, 4, 5]);
+
+    // A mismatch after a run only has to go back to that run
+    assert_eq!(ids(&mut file, "'abcbcd' LIKE 'A%BCD'"), vec![1, 2, 3, 4, 5]);
+    assert_eq!(ids(&mut file, "'abcbcbd' GLOB 'a*bc?'"), vec![]);
+    assert_eq!(ids(&mut file, "'abcbcbd' GLOB 'a*b*b?'"), vec![1, 2, 3, 4, 5]);
+    assert_eq!(ids(&mut file, "'a%c' LIKE '%!%%' ESCAPE '!'"), vec![1, 2, 3, 4, 5]);
+    assert_eq!(ids(&mut file, "'abc' LIKE 'abc!' ESCAPE '!'"), vec![]);
+    assert_eq!(ids(&mut file, "'a[' GLOB 'a['"), vec![]);
+}
+
+#[test]
+fn test_arithmetic_and_functions() {
+    let mut file = DBFile::open("./tests/assets/affinity.db").unwrap();
+    assert_eq!(ids(&mut file, "amount + 1 > 40"), vec![1, 4]);
+    assert_eq!(ids(&mut file, "amount / 2 = 7"), vec![3]);
+    assert_eq!(ids(&mut file, "amount % 7 = 1"), vec![3]);
+    assert_eq!(ids(&mut file, "price * 2 = 5"), vec![1]);
+    assert_eq!(ids(&mut file, "amount / 0 IS NULL"), vec![1, 2, 3, 4, 5]);
+    assert_eq!(ids(&mut file, "name || code = 'Apple10'"), vec![1]);
+    assert_eq!(ids(&mut file, "-amount < 0 AND amount & 8"), vec![1, 3, 4]);
+    assert_eq!(ids(&mut file, "length(name) = 5 AND upper(name) = 'APPLE'"), vec![1, 5]);
+    assert_eq!(ids(&mut file, "abs(amount) = 3 OR typeof(score) = 'text'"), vec![3, 5]);
+
+    assert!(matches!(file.execute("SELECT id FROM things WHERE foo(1)"), Err(SQLError::Command(SQLCommandError::UnsupportedCommand(_)))));
+    assert!(matches!(file.execute("SELECT id FROM things WHERE lower(name, 1) = 'a'"), Err(SQLError::Command(SQLCommandError::InvalidExpression(_)))));
+    assert!(matches!(file.execute("SELECT id FROM things WHERE name COLLATE klingon = 'a'"), Err(SQLError::Command(SQLCommandError::InvalidExpression(_)))));
+}
+
+#[test]
+fn test_index_searches_compare_like_the_column() {
+    let mut file = DBFile::open("./tests/assets/affinity.db").unwrap();
+    // '40' is the integer 40 to an INTEGER column, so the index is searched for that
+    let QueryPlan::IndexSearch { index, prefix, .. } = file.query_plan("SELECT id FROM things WHERE amount = '40'").unwrap() else { panic!("expected an index search") };
+    assert_eq!(index.name, "things_by_amount");
+    assert_eq!(prefix, vec![Value::Integer(40)]);
+
+    // The name index is in NOCASE order, which a byte by byte search can't use
+    assert_eq!(file.query_plan("SELECT id FROM things WHERE name = 'apple'").unwrap().to_string(), "SCAN things");
+}
+
+// addcolumn.db has columns added with ALTER TABLE ADD COLUMN ... DEFAULT after rows were written.
+// Those rows don't store the added columns, which compare as their DEFAULT.
+#[test]
+fn test_columns_missing_from_a_record_evaluate_to_their_default() {
+    let mut file = DBFile::open("./tests/assets/addcolumn.db").unwrap();
+    assert_eq!(file.query_plan("SELECT a FROM t WHERE b = 7").unwrap().to_string(), "SEARCH t USING INDEX t_b (b=?)");
+    let names = |file: &mut DBFile, sql: &str| -> Vec<String> { file.execute(sql).unwrap().iter().map(|row| row.to_string()).collect() };
+    assert_eq!(names(&mut file, "SELECT a FROM t WHERE b = 7"), vec!["xylophone", "yodel"]);
+    assert_eq!(names(&mut file, "SELECT a FROM t WHERE b + 0 = 7"), vec!["xylophone", "yodel"]);
+    assert_eq!(names(&mut file, "SELECT a FROM t WHERE c = 'none' AND e = 12 AND g = 1 AND h = 'unquoted'"), vec!["xylophone", "yodel"]);
+    assert_eq!(names(&mut file, "SELECT name FROM k WHERE flag = 1"), vec!["p", "q"]);
+
+    // A DEFAULT takes its column's affinity: e INTEGER DEFAULT '12' is 12 and d REAL DEFAULT 2 is 2.0
+    let t = file.schema.to_schema_rows().unwrap().into_iter().find(|row| row.name == "t").unwrap();
+    assert_eq!(t.sql.column_defaults, vec![Value::Null, Value::Null, Value::Integer(7), Value::Text("none".to_string()), Value::Real(2.0),
+                                           Value::Integer(12), Value::Integer(-3), Value::Integer(1), Value::Text("unquoted".to_string())]);
+    let evaluator = Evaluator::new(&t.sql, TextEncoding::Utf8);
+    let expr = Expr::from_tokens(&mut "b = 7 AND d = 2.0 AND f = -3".tokenize().into_iter().peekable()).unwrap();
+    let record = Record { row_id: 1, column_values: vec![Value::Null, Value::Text("xylophone".to_string())] };
+    assert!(evaluator.is_true(&expr, &record).unwrap());
+}
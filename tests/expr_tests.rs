@@ -0,0 +1,157 @@
+use sqlite_fsr::command::sql::parser::expr::{BinaryOperator, Expr, PatternOperator, UnaryOperator, MAX_EXPR_DEPTH};
+use sqlite_fsr::command::sql::parser::sql_token::Tokenize;
+use sqlite_fsr::command::sql::parser::SQLToken;
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+use sqlite_fsr::models::error::{SQLError, SQLSyntaxError};
+
+// Parses the whole of `sql` as one expression.
+fn parse(sql: &str) -> Expr {
+    let mut tokens = sql.tokenize().into_iter().peekable();
+    let expr = Expr::from_tokens(&mut tokens).unwrap();
+    assert_eq!(tokens.next(), None, "{} was not parsed to the end", sql);
+    expr
+}
+
+fn parse_error(sql: &str) -> SQLSyntaxError {
+    Expr::from_tokens(&mut sql.tokenize().into_iter().peekable()).unwrap_err()
+}
+
+fn column(name: &str) -> Box<Expr> {
+    Box::new(Expr::Column { table: None, name: name.to_string() })
+}
+
+fn integer(integer: i64) -> Box<Expr> {
+    Box::new(Expr::Literal(Value::Integer(integer)))
+}
+
+fn binary(left: Box<Expr>, operator: BinaryOperator, right: Box<Expr>) -> Box<Expr> {
+    Box::new(Expr::Binary { left, operator, right })
+}
+
+#[test]
+fn test_operators_bind_by_precedence_and_group_left() {
+    assert_eq!(parse("a + b * c"), *binary(column("a"), BinaryOperator::Add, binary(column("b"), BinaryOperator::Multiply, column("c"))));
+    assert_eq!(parse("a - b - c"), *binary(binary(column("a"), BinaryOperator::Subtract, column("b")), BinaryOperator::Subtract, column("c")));
+    assert_eq!(parse("(a - b) * c"), *binary(binary(column("a"), BinaryOperator::Subtract, column("b")), BinaryOperator::Multiply, column("c")));
+    assert_eq!(parse("a OR b AND c"), *binary(column("a"), BinaryOperator::Or, binary(column("b"), BinaryOperator::And, column("c"))));
+    assert_eq!(parse("a < 1 = b > 2"), *binary(binary(column("a"), BinaryOperator::Less, integer(1)), BinaryOperator::Equals, binary(column("b"), BinaryOperator::Greater, integer(2))));
+
+    // NOT binds looser than comparisons but tighter than AND
+    let not = |operand: Box<Expr>| Box::new(Expr::Unary { operator: UnaryOperator::Not, operand });
+    assert_eq!(parse("NOT a = 1 AND b"), *binary(not(binary(column("a"), BinaryOperator::Equals, integer(1))), BinaryOperator::And, column("b")));
+
+    // Unary operators bind tightest; a sign is folded into a number
+    assert_eq!(parse("-a * -2"), *binary(Box::new(Expr::Unary { operator: UnaryOperator::Negate, operand: column("a") }), BinaryOperator::Multiply, integer(-2)));
+    assert_eq!(parse("'a' || 'b' || 'c'").to_string(), "'a' || 'b' || 'c'");
+    assert_eq!(parse("1 << 2 + 3 & 4").to_string(), "1 << 2 + 3 & 4");
+}
+
+#[test]
+fn test_postfix_and_keyword_operators() {
+    assert_eq!(parse("name IS NULL"), Expr::IsNull { operand: column("name"), negated: false });
+    assert_eq!(parse("name IS NOT NULL"), Expr::IsNull { operand: column("name"), negated: true });
+    assert_eq!(parse("name NOTNULL"), parse("name NOT NULL"));
+    assert_eq!(parse("name ISNULL"), parse("name IS NULL"));
+    assert_eq!(parse("a IS NOT b"), *binary(column("a"), BinaryOperator::IsNot, column("b")));
+
+    assert_eq!(parse("id NOT IN (1, 2 + 3)"), Expr::InList { operand: column("id"), list: vec![*integer(1), *binary(integer(2), BinaryOperator::Add, integer(3))], negated: true });
+    assert_eq!(parse("id IN ()"), Expr::InList { operand: column("id"), list: Vec::new(), negated: false });
+
+    // The AND inside BETWEEN belongs to it, the one after doesn't
+    assert_eq!(parse("age BETWEEN 1 + 1 AND 9 AND ok"),
+               *binary(Box::new(Expr::Between { operand: column("age"), low: binary(integer(1), BinaryOperator::Add, integer(1)), high: integer(9), negated: false }),
+                       BinaryOperator::And, column("ok")));
+    assert_eq!(parse("name NOT LIKE 'a%' ESCAPE '\\'"),
+               Expr::Like { operand: column("name"), operator: PatternOperator::Like, pattern: Box::new(Expr::Literal(Value::Text("a%".to_string()))),
+                            escape: Some(Box::new(Expr::Literal(Value::Text("\\".to_string())))), negated: true });
+    assert!(matches!(parse("name GLOB '*x'"), Expr::Like { operator: PatternOperator::Glob, negated: false, .. }));
+    assert_eq!(parse("name COLLATE NOCASE = 'x'"), *binary(Box::new(Expr::Collate { operand: column("name"), collation: "NOCASE".to_string() }), BinaryOperator::Equals,
+                                                           Box::new(Expr::Literal(Value::Text("x".to_string())))));
+}
+
+#[test]
+fn test_operands() {
+    assert_eq!(parse("people.age"), Expr::Column { table: Some("people".to_string()), name: "age".to_string() });
+    assert_eq!(parse("\"first name\""), *column("first name"));
+    assert_eq!(parse("NULL"), Expr::Literal(Value::Null));
+    assert_eq!(parse("X'0aff'"), Expr::Literal(Value::Blob(vec![0x0a, 0xff])));
+    assert_eq!(parse("-2.5"), Expr::Literal(Value::Real(-2.5)));
+    assert_eq!(parse("count(*)"), Expr::Function { name: "count".to_string(), arguments: Vec::new(), distinct: false });
+    assert_eq!(parse("max(DISTINCT a, 1)"), Expr::Function { name: "max".to_string(), arguments: vec![*column("a"), *integer(1)], distinct: true });
+    assert_eq!(parse("lower(replace(name, 'a', 'b'))").column_references(), vec![(None, "name")]);
+}
+
+#[test]
+fn test_expressions_are_written_back_as_sql() {
+    for sql in ["a = 1 AND (b = 2 OR c = 3)", "NOT (a AND b)", "x NOT BETWEEN -1 AND 10", "t.\"order\" IN (1, 'two', X'03', NULL, 4.5)",
+                "name LIKE 'it''s%' ESCAPE '!'", "count(*) > 1", "-(-a)", "-(a + b)", "~a | b", "name COLLATE NOCASE IS NOT NULL"] {
+        assert_eq!(parse(sql).to_string(), sql);
+        assert_eq!(parse(&parse(sql).to_string()), parse(sql));
+    }
+    assert_eq!(parse("a == 1 <> b").to_string(), "a = 1 != b");
+    // Not --5, which would start a comment
+    assert_eq!(Expr::Unary { operator: UnaryOperator::Negate, operand: integer(-5) }.to_string(), "-(-5)");
+}
+
+#[test]
+fn test_malformed_expressions_are_rejected() {
+    for sql in ["", "a +", "(a = 1", "a IN 1", "a IN (1,)", "a BETWEEN 1", "a NOT 5", "f(a,", "t.", "a COLLATE 'x'", "SELECT"] {
+        assert!(matches!(parse_error(sql), SQLSyntaxError::UnexpectedToken(_)), "{}", sql);
+    }
+
+    // An expression ends at the first token that can't continue it
+    let mut tokens = "a = 1 ORDER BY a".tokenize().into_iter().peekable();
+    Expr::from_tokens(&mut tokens).unwrap();
+    assert!(tokens.next().is_some_and(|token| token.is_keyword("ORDER")));
+    let mut tokens = "a ~ 3".tokenize().into_iter().peekable();
+    assert_eq!(Expr::from_tokens(&mut tokens).unwrap(), *column("a"));
+    assert!(matches!(tokens.next(), Some(SQLToken::Operator(_))));
+}
+
+// Runs `test` on a thread with the 8 MiB stack the CLI's main thread gets. Debug builds spend a few
+// KiB of stack per level of a nested expression, so the 2 MiB test threads can't reach the limit.
+fn with_main_thread_stack(test: impl FnOnce() + Send + 'static) {
+    std::thread::Builder::new().stack_size(8 << 20).spawn(test).unwrap().join().unwrap();
+}
+
+#[test]
+fn test_expressions_nested_past_the_depth_limit_are_rejected() {
+    with_main_thread_stack(|| {
+        let too_deep = |sql: String| {
+            let mut tokens = sql.tokenize().into_iter().peekable();
+            assert!(matches!(Expr::from_tokens(&mut tokens), Err(SQLSyntaxError::ExpressionTooDeep(MAX_EXPR_DEPTH))), "{:.40}...", sql);
+        };
+        too_deep(format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000)));
+        too_deep(format!("{}id", "NOT ".repeat(10_000)));
+        too_deep(format!("{}1", "- ".repeat(10_000)));
+        too_deep(vec!["1"; 10_000].join(" + "));
+        too_deep(format!("id IN ({}1{})", "(".repeat(MAX_EXPR_DEPTH), ")".repeat(MAX_EXPR_DEPTH)));
+
+        // Expressions just inside the limit are parsed and evaluated; an even number of NOTs cancels out
+        let mut file = DBFile::open("./tests/assets/sample.db").unwrap();
+        let sql = format!("SELECT name FROM apples WHERE {}id = 2", "NOT ".repeat(MAX_EXPR_DEPTH - 10));
+        assert_eq!(file.execute(sql.as_str()).unwrap().len(), 1);
+        let sql = format!("SELECT name FROM apples WHERE {} = 1000", vec!["1"; MAX_EXPR_DEPTH - 10].join(" + "));
+        assert!(file.execute(sql.as_str()).unwrap().is_empty());
+        assert!(matches!(file.execute(format!("SELECT name FROM apples WHERE {}", vec!["id"; 5_000].join(" OR ")).as_str()),
+                         Err(SQLError::Syntax(SQLSyntaxError::ExpressionTooDeep(_)))));
+    });
+}
+
+#[test]
+fn test_expressions_one_level_past_the_depth_limit_are_rejected() {
+    with_main_thread_stack(|| {
+        let parse_nots = |count: usize| {
+            let mut tokens = format!("{}id", "NOT ".repeat(count)).tokenize().into_iter().peekable();
+            Expr::from_tokens(&mut tokens)
+        };
+        // Each NOT is a level above the column it applies to
+        assert!(parse_nots(MAX_EXPR_DEPTH - 1).is_ok());
+        assert!(matches!(parse_nots(MAX_EXPR_DEPTH), Err(SQLSyntaxError::ExpressionTooDeep(MAX_EXPR_DEPTH))));
+
+        let mut file = DBFile::open("./tests/assets/sample.db").unwrap();
+        let sql = format!("SELECT name FROM apples WHERE {}id", "NOT ".repeat(MAX_EXPR_DEPTH));
+        assert!(matches!(file.execute(sql.as_str()), Err(SQLError::Syntax(SQLSyntaxError::ExpressionTooDeep(MAX_EXPR_DEPTH)))));
+    });
+}
@@ -0,0 +1,57 @@
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::header::{DatabaseHeader, TextEncoding};
+use sqlite_fsr::models::error::DatabaseError;
+
+#[test]
+fn test_header_exposes_file_header_fields() {
+    let file = DBFile::open("./tests/assets/header.db").unwrap();
+    let header = &file.header;
+
+    assert_eq!(header.magic, "SQLite format 3");
+    assert_eq!(header.page_size, 1024);
+    assert_eq!((header.write_version, header.read_version), (1, 1));
+    assert_eq!(header.reserved_space, 0);
+    assert_eq!((header.max_payload_fraction, header.min_payload_fraction, header.leaf_payload_fraction), (64, 32, 32));
+    assert_eq!(header.file_change_counter, 5);
+    assert_eq!(header.database_size, 3);
+    assert_eq!((header.freelist_trunk_page, header.freelist_page_count), (0, 0));
+    assert_eq!(header.schema_cookie, 1);
+    assert_eq!(header.schema_format, 4);
+    assert_eq!(header.largest_root_page, 3);
+    assert_eq!(header.text_encoding, TextEncoding::Utf8);
+    assert_eq!(header.user_version, 42);
+    assert!(header.incremental_vacuum);
+    assert_eq!(header.application_id, 1234567);
+    assert_eq!(header.version_valid_for, 5);
+    assert_eq!(header.sqlite_version_number, 3051002);
+}
+
+#[test]
+fn test_header_reads_default_database() {
+    let file = DBFile::open("./tests/assets/sample.db").unwrap();
+    assert_eq!(file.header.page_size, 4096);
+    assert_eq!(file.header.database_size, 4);
+    assert_eq!(file.header.usable_page_size(), 4096);
+    assert!(!file.header.incremental_vacuum);
+    assert!(!file.header.is_wal_mode());
+}
+
+#[test]
+fn test_header_rejects_bad_magic_string() {
+    let mut data = std::fs::read("./tests/assets/sample.db").unwrap();
+    data[0..16].copy_from_slice(b"PostgreSQL dump\0");
+    assert!(matches!(DatabaseHeader::from_bytes(&data), Err(DatabaseError::NotADatabase)));
+}
+
+#[test]
+fn test_header_rejects_invalid_page_size() {
+    let mut data = std::fs::read("./tests/assets/sample.db").unwrap();
+    data[16..18].copy_from_slice(&1000u16.to_be_bytes());
+    assert!(matches!(DatabaseHeader::from_bytes(&data), Err(DatabaseError::NotADatabase)));
+}
+
+#[test]
+fn test_open_fails_for_file_that_is_not_a_database() {
+    let result = DBFile::open("./Cargo.toml");
+    assert!(matches!(result, Err(DatabaseError::NotADatabase)));
+}
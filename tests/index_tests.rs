@@ -0,0 +1,150 @@
+use sqlite_fsr::command::sql::parser::sql_statement::CreateIndexStatement;
+use sqlite_fsr::command::sql::parser::sql_token::Tokenize;
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbindex::IndexRecord;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+use sqlite_fsr::models::error::{SQLCommandError, SQLError};
+
+fn scan_index(file: &mut DBFile, index_name: &str) -> Vec<IndexRecord> {
+    let mut index = file.get_index(&index_name.to_string()).unwrap();
+    index.cursor().collect::<Result<Vec<_>, _>>().unwrap()
+}
+
+#[test]
+fn test_schema_lists_indexes_with_their_columns() {
+    let file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    let indexes = file.schema.to_schema_indexes().unwrap();
+    let names: Vec<(&str, &str, u32)> = indexes.iter()
+                                               .map(|index| (index.name.as_str(), index.table_name.as_str(), index.rootpage))
+                                               .collect();
+    assert_eq!(names, vec![("people_by_age", "people", 86), ("people_by_city_name", "people", 105), ("people_by_bio", "people", 155), ("adults_by_name", "people", 208)]);
+
+    let city_name = &indexes[1].sql;
+    let columns: Vec<(&str, bool)> = city_name.columns.iter().map(|column| (column.name.as_str(), column.descending)).collect();
+    assert_eq!(columns, vec![("city", false), ("name", true)]);
+    assert!(indexes[2].sql.unique);
+    assert!(indexes[3].sql.partial);
+    assert!(!indexes[0].sql.unique && !indexes[0].sql.partial);
+
+    // Indexes are not tables
+    assert_eq!(file.get_table_names(), vec!["people"]);
+}
+
+#[test]
+fn test_create_index_statement_parses_optional_clauses() {
+    let statement = CreateIndexStatement::from_tokens("CREATE UNIQUE INDEX IF NOT EXISTS by_email ON users (email COLLATE NOCASE ASC, lower(name) DESC)".tokenize()).unwrap();
+    assert_eq!(statement.index_name, "by_email");
+    assert_eq!(statement.table_name, "users");
+    assert!(statement.unique);
+    assert!(!statement.partial);
+    assert_eq!(statement.columns[0].name, "email");
+    assert!(!statement.columns[0].descending);
+    assert_eq!(statement.columns[1].name, "lower(name)");
+    assert!(statement.columns[1].descending);
+
+    assert!(CreateIndexStatement::from_tokens("CREATE INDEX by_email users (email)".tokenize()).is_err());
+    assert!(CreateIndexStatement::from_tokens("CREATE INDEX by_email ON users (email".tokenize()).is_err());
+}
+
+#[test]
+fn test_index_cursor_scans_multi_level_index_in_key_order() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    assert_eq!(file.pager().get_page(86).unwrap()[0], 0x02);
+
+    let records = scan_index(&mut file, "people_by_age");
+    assert_eq!(records.len(), 2000);
+
+    let mut expected: Vec<(i64, i64)> = file.execute("SELECT id, age FROM people").unwrap()
+                                            .iter()
+                                            .map(|row| match (&row[0], &row[1]) {
+                                                (Value::Integer(id), Value::Integer(age)) => (*age, *id),
+                                                _ => panic!("unexpected row {}", row)
+                                            })
+                                            .collect();
+    expected.sort();
+    let scanned: Vec<(i64, i64)> = records.iter()
+                                          .map(|record| match record.key[0] { Value::Integer(age) => (age, record.row_id), _ => panic!() })
+                                          .collect();
+    assert_eq!(scanned, expected);
+}
+
+#[test]
+fn test_index_cursor_honours_descending_columns_and_nulls() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    let records = scan_index(&mut file, "people_by_city_name");
+    assert_eq!(records.len(), 2000);
+
+    // NULL cities sort first, names within a city are in descending order
+    assert_eq!(records[0].key, vec![Value::Null, Value::Text("person 1996".to_string())]);
+    assert_eq!(records[0].row_id, 1284);
+    assert_eq!(records[1].row_id, 889);
+    assert_eq!(records[1999].key[0], "Quito");
+}
+
+#[test]
+fn test_index_cursor_seeks_to_first_matching_key() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    let mut index = file.get_index(&"people_by_age".to_string()).unwrap();
+    let mut cursor = index.cursor();
+
+    let row_ids: Vec<i64> = cursor.lookup(&[Value::Integer(40)]).unwrap().iter().map(|record| record.row_id).collect();
+    assert_eq!(row_ids, (0..33).map(|n| 46 + n * 60).collect::<Vec<i64>>());
+
+    // Seeking between keys lands on the next larger key, a real compares equal to an integer
+    cursor.seek(&[Value::Real(76.5)]).unwrap();
+    assert_eq!(cursor.next().unwrap().unwrap().key, vec![Value::Integer(77)]);
+    assert_eq!(cursor.lookup(&[Value::Real(40.0)]).unwrap().len(), 33);
+
+    // Past the last key there is nothing left
+    cursor.seek(&[Value::Integer(78)]).unwrap();
+    assert!(cursor.next().is_none());
+    assert!(cursor.lookup(&[Value::Integer(12)]).unwrap().is_empty());
+}
+
+#[test]
+fn test_index_cursor_seeks_on_key_prefix_and_descending_column() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    let mut index = file.get_index(&"people_by_city_name".to_string()).unwrap();
+    let mut cursor = index.cursor();
+
+    let oslo = cursor.lookup(&[Value::Text("Oslo".to_string())]).unwrap();
+    assert_eq!(oslo.len(), 400);
+    assert!(oslo.iter().all(|record| record.row_id % 5 == 1));
+
+    let people = cursor.lookup(&[Value::Text("Oslo".to_string()), Value::Text("person 0004".to_string())]).unwrap();
+    assert_eq!(people.len(), 1);
+    assert_eq!(people[0].row_id, 716);
+
+    assert_eq!(cursor.lookup(&[Value::Null]).unwrap().len(), 400);
+}
+
+#[test]
+fn test_index_entries_spilling_onto_overflow_pages() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    let long_bio = format!("{} #{}", "b".repeat(600), 700);
+
+    let mut index = file.get_index(&"people_by_bio".to_string()).unwrap();
+    let records = index.cursor().lookup(&[Value::Text(long_bio.clone())]).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].row_id, 700);
+    assert_eq!(records[0].key[0], long_bio.as_str());
+
+    let records = scan_index(&mut file, "people_by_bio");
+    assert_eq!(records.len(), 2000);
+    assert_eq!(records.iter().filter(|record| matches!(&record.key[0], Value::Text(bio) if bio.len() > 600)).count(), 20);
+}
+
+#[test]
+fn test_partial_index_only_holds_matching_rows() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    assert_eq!(scan_index(&mut file, "adults_by_name").len(), 1900);
+}
+
+#[test]
+fn test_get_index_with_unknown_name_fails() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    match file.get_index(&"missing".to_string()) {
+        Err(SQLError::Command(SQLCommandError::UnknownIndex(name))) => assert_eq!(name, "missing"),
+        _ => panic!("expected an unknown index error")
+    }
+}
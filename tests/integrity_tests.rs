@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+use sqlite_fsr::models::DBFile;
+
+mod common;
+use common::write_temp_db;
+
+// indexed.db has 1024-byte pages; page 3 is the first leaf of people, whose rows start at rowid 1.
+const PAGE_SIZE: usize = 1024;
+const PEOPLE_LEAF_OFFSET: usize = 2 * PAGE_SIZE;
+
+// Runs the check on a copy of `source` passed through `edit`.
+fn check_edited(source: &str, name: &str, edit: fn(&mut Vec<u8>)) -> Vec<String> {
+    let mut data = std::fs::read(source).unwrap();
+    edit(&mut data);
+    let path = write_temp_db(name, &data);
+    let problems = DBFile::open(&path).unwrap().integrity_check().unwrap();
+    std::fs::remove_file(path).unwrap();
+    problems
+}
+
+#[test]
+fn test_shipped_databases_pass_the_integrity_check() {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir("./tests/assets").unwrap()
+                                          .map(|entry| entry.unwrap().path())
+                                          .filter(|path| path.extension().is_some_and(|extension| extension == "db"))
+                                          .collect();
+    paths.sort();
+    assert!(paths.len() > 10);
+    for path in paths {
+        let problems = DBFile::open(&path).unwrap().integrity_check().unwrap();
+        assert!(problems.is_empty(), "{}: {:?}", path.display(), problems);
+    }
+}
+
+// addcolumn.db has columns added with ALTER TABLE ADD COLUMN ... DEFAULT after rows were written,
+// and indexes on them built from those defaults. sqlite3's PRAGMA integrity_check says ok.
+#[test]
+fn test_rows_written_before_add_column_are_indexed_with_the_default() {
+    let mut file = DBFile::open("./tests/assets/addcolumn.db").unwrap();
+    assert_eq!(file.integrity_check().unwrap(), Vec::<String>::new());
+
+    // The index still has to agree with the rows
+    let problems = check_edited("./tests/assets/addcolumn.db", "changed-default", |data| {
+        let position = data.windows(9).position(|window| window == b"DEFAULT 7").unwrap();
+        data[position + 8] = b'6';
+    });
+    assert!(problems.contains(&"row 1 is missing from index t_b".to_string()), "{:?}", problems);
+}
+
+#[test]
+fn test_rowids_out_of_order_are_reported() {
+    // Swap the first two cell pointers of the leaf
+    let problems = check_edited("./tests/assets/indexed.db", "swapped-cells", |data| {
+        let pointers = PEOPLE_LEAF_OFFSET + 8;
+        let first = [data[pointers], data[pointers + 1]];
+        data.copy_within(pointers + 2..pointers + 4, pointers);
+        data[pointers + 2..pointers + 4].copy_from_slice(&first);
+    });
+    assert_eq!(problems, vec!["page 3: rowid 1 is out of order"]);
+}
+
+#[test]
+fn test_overlapping_cells_are_reported() {
+    // Point the second cell two bytes into the first
+    let problems = check_edited("./tests/assets/indexed.db", "overlapping-cells", |data| {
+        let pointers = PEOPLE_LEAF_OFFSET + 8;
+        let first = u16::from_be_bytes([data[pointers], data[pointers + 1]]);
+        data[pointers + 2..pointers + 4].copy_from_slice(&(first + 2).to_be_bytes());
+    });
+    assert!(problems.iter().any(|problem| problem.starts_with("page 3, offset 995: cell overlaps the cell at offset 997")), "{:?}", problems);
+}
+
+#[test]
+fn test_index_entries_that_do_not_match_the_table_are_reported() {
+    // Change the age of person 1 from 55 to 56 in the table but not in people_by_age
+    let problems = check_edited("./tests/assets/indexed.db", "changed-age", |data| {
+        let leaf = &data[PEOPLE_LEAF_OFFSET..PEOPLE_LEAF_OFFSET + PAGE_SIZE];
+        let name_offset = leaf.windows(11).position(|bytes| bytes == b"person 1919").unwrap();
+        assert_eq!(leaf[name_offset + 11], 55);
+        data[PEOPLE_LEAF_OFFSET + name_offset + 11] = 56;
+    });
+    assert_eq!(problems, vec!["index people_by_age has an entry (55, 1) that matches no row of people",
+                              "row 1 is missing from index people_by_age"]);
+}
+
+#[test]
+fn test_index_contents_are_compared_within_the_sort_budget() {
+    // With a 1 KiB budget the 2000 entries of each people index are sorted in runs spilled to temporary files
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    file.set_sort_budget(1024);
+    assert_eq!(file.integrity_check().unwrap(), Vec::<String>::new());
+
+    let mut data = std::fs::read("./tests/assets/indexed.db").unwrap();
+    let name_offset = PEOPLE_LEAF_OFFSET + data[PEOPLE_LEAF_OFFSET..PEOPLE_LEAF_OFFSET + PAGE_SIZE].windows(11).position(|bytes| bytes == b"person 1919").unwrap();
+    data[name_offset + 11] = 56;
+    let path = write_temp_db("changed-age-spilled", &data);
+    let mut file = DBFile::open(&path).unwrap();
+    file.set_sort_budget(1024);
+    assert_eq!(file.integrity_check().unwrap(), vec!["index people_by_age has an entry (55, 1) that matches no row of people",
+                                                     "row 1 is missing from index people_by_age"]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_freelist_count_and_leaked_pages_are_reported() {
+    let problems = check_edited("./tests/assets/indexed.db", "freelist-count", |data| data[36..40].copy_from_slice(&5u32.to_be_bytes()));
+    assert_eq!(problems, vec!["freelist has 0 pages but the header says 5"]);
+
+    // Without its first trunk page, all 224 pages of pagemap.db's freelist are lost
+    let problems = check_edited("./tests/assets/pagemap.db", "lost-freelist", |data| data[32..36].copy_from_slice(&[0; 4]));
+    assert_eq!(problems.len(), 225);
+    assert_eq!(problems[0], "page 8: never used");
+    assert_eq!(problems[224], "freelist has 0 pages but the header says 224");
+}
+
+#[test]
+fn test_every_problem_is_listed_not_just_the_first() {
+    // The same double claim page_map() fails on, plus a wrong freelist count
+    let problems = check_edited("./tests/assets/pagemap.db", "double-claim", |data| {
+        let leaf_offset = 7 * 512 + 8;
+        data[leaf_offset..leaf_offset + 4].copy_from_slice(&3u32.to_be_bytes());
+        data[36..40].copy_from_slice(&300u32.to_be_bytes());
+    });
+    assert_eq!(problems[0], "page 3: page is used by both the freelist leaf and the table b-tree docs");
+    assert!(problems.contains(&"freelist has 224 pages but the header says 300".to_string()), "{:?}", problems);
+    // The docs b-tree below its root was never reached, so its pages look leaked
+    assert!(problems.iter().any(|problem| problem.ends_with("never used")));
+}
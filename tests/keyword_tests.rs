@@ -0,0 +1,81 @@
+use sqlite_fsr::command::sql::parser::sql_statement::{CreateIndexStatement, SQLStatement, ToSQLStatement};
+use sqlite_fsr::command::sql::parser::sql_token::{Tokenize, KEYWORDS, NAME_KEYWORDS};
+use sqlite_fsr::command::sql::parser::SQLToken;
+use sqlite_fsr::models::DBFile;
+
+mod common;
+use common::row_strings;
+
+#[test]
+fn test_keywords_are_recognized_in_any_case() {
+    let tokens = "select Name from apples wHeRe id >= 2 order by name limit 1".tokenize();
+    let keywords: Vec<String> = tokens.iter()
+                                      .filter_map(|token| match token {
+                                          SQLToken::Keyword(word) => Some(word.clone()),
+                                          _ => None
+                                      })
+                                      .collect();
+    // Keywords keep the spelling they were written with
+    assert_eq!(keywords, vec!["select", "from", "wHeRe", "order", "by", "limit"]);
+    assert!(tokens[0].is_keyword("SELECT"));
+    assert_eq!(tokens[1], SQLToken::Identifier("Name".to_string()));
+
+    // Quoted keywords are identifiers
+    assert_eq!("\"select\" [from]".tokenize(), vec![SQLToken::Identifier("select".to_string()), SQLToken::Identifier("from".to_string())]);
+}
+
+#[test]
+fn test_keyword_lists_are_sorted_and_complete() {
+    assert_eq!(KEYWORDS.len(), 147);
+    assert!(KEYWORDS.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(NAME_KEYWORDS.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(NAME_KEYWORDS.iter().all(|word| KEYWORDS.contains(word)));
+    for word in KEYWORDS {
+        assert!(matches!(word.to_lowercase().tokenize().as_slice(), [SQLToken::Keyword(_)]), "{}", word);
+    }
+}
+
+#[test]
+fn test_some_keywords_can_be_names() {
+    let [key, temp, select] = ["key", "temp", "select"].map(|word| word.tokenize().remove(0));
+    assert!(key.is_name() && temp.is_name());
+    assert!(!select.is_name());
+
+    let Ok(SQLStatement::CreateTable(statement)) = "create table if not exists settings (key TEXT PRIMARY KEY, value, \"primary\" INT)".to_sql_statment() else { panic!("expected a CREATE TABLE") };
+    assert_eq!(statement.columns, vec!["key", "value", "primary"]);
+    assert_eq!(statement.primary_key[0].name, "key");
+
+    let Ok(SQLStatement::Select(statement)) = "SELECT key, replace FROM temp WHERE key = 'a'".to_sql_statment() else { panic!("expected a SELECT") };
+    assert_eq!(statement.columns, Some(vec!["key".to_string(), "replace".to_string()]));
+    assert_eq!(statement.table_name, "temp");
+    assert_eq!(statement.where_clause.unwrap().column_references(), vec![(None, "key")]);
+
+    // Reserved keywords need quotes
+    assert!("SELECT order FROM settings".to_sql_statment().is_err());
+    assert!("SELECT name FROM group".to_sql_statment().is_err());
+
+    let statement = CreateIndexStatement::from_tokens("CREATE INDEX by_desc ON t (desc, asc DESC)".tokenize()).unwrap();
+    assert_eq!(statement.columns.iter().map(|column| (column.name.as_str(), column.descending)).collect::<Vec<_>>(),
+               vec![("desc", false), ("asc", true)]);
+}
+
+#[test]
+fn test_lowercase_queries_run() {
+    let mut file = DBFile::open("./tests/assets/sample.db").unwrap();
+    assert_eq!(row_strings(&mut file, "select name from apples where id between 2 and 3"), vec!["Fuji", "Honeycrisp"]);
+    assert_eq!(row_strings(&mut file, "select count(*) from apples"), vec!["4"]);
+    assert_eq!(row_strings(&mut file, "SELECT Count(*) FROM apples WHERE color LIKE '%red'"), vec!["2"]);
+    let Ok(SQLStatement::Select(statement)) = "select count, sum from totals".to_sql_statment() else { panic!("expected a SELECT") };
+    assert_eq!((statement.aggregator_function, statement.columns), (None, Some(vec!["count".to_string(), "sum".to_string()])));
+}
+
+#[test]
+fn test_tables_and_columns_named_after_keywords() {
+    let mut file = DBFile::open("./tests/assets/keywords.db").unwrap();
+    assert!(file.schema.to_schema_rows().unwrap().iter().any(|row| row.sql.without_rowid));
+    assert_eq!(row_strings(&mut file, "SELECT key, value FROM settings WHERE \"order\" = 2"), vec!["theme dark"]);
+    assert_eq!(row_strings(&mut file, "SELECT [Select] FROM settings WHERE key = 'size'"), vec!["c"]);
+    assert_eq!(row_strings(&mut file, "SELECT replace FROM \"group\" WHERE temp > 0"), vec!["x", "z"]);
+    assert_eq!(row_strings(&mut file, "SELECT id FROM \"group\" WHERE replace = 'y'"), vec!["2"]);
+    assert!(file.integrity_check().unwrap().is_empty());
+}
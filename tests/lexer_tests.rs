@@ -0,0 +1,94 @@
+use sqlite_fsr::command::sql::parser::lexer::{Lexer, Span};
+use sqlite_fsr::command::sql::parser::sql_token::{Operator, Symbol, Tokenize};
+use sqlite_fsr::command::sql::parser::SQLToken;
+use sqlite_fsr::models::DBFile;
+
+fn kinds(sql: &str) -> Vec<SQLToken> {
+    Lexer::new(sql).map(|token| token.kind).collect()
+}
+
+fn identifier(name: &str) -> SQLToken {
+    SQLToken::Identifier(name.to_string())
+}
+
+#[test]
+fn test_tokens_carry_their_source_span() {
+    let sql = "SELECT name\n  FROM apples;";
+    let spans: Vec<Span> = Lexer::new(sql).map(|token| token.span).collect();
+    assert_eq!(spans, vec![Span { start: 0, end: 6 }, Span { start: 7, end: 11 }, Span { start: 14, end: 18 },
+                           Span { start: 19, end: 25 }, Span { start: 25, end: 26 }]);
+    assert_eq!(&sql[spans[3].start..spans[3].end], "apples");
+}
+
+#[test]
+fn test_string_literals_keep_spaces_and_unescape_quotes() {
+    assert_eq!(kinds("'Granny Smith'"), vec![SQLToken::StringLiteral("Granny Smith".to_string())]);
+    assert_eq!(kinds("'it''s' ''"), vec![SQLToken::StringLiteral("it's".to_string()), SQLToken::StringLiteral(String::new())]);
+    assert_eq!(kinds("'open"), vec![SQLToken::Illegal("'open".to_string())]);
+}
+
+#[test]
+fn test_numbers_are_integer_or_float_literals() {
+    assert_eq!(kinds("42 3.5 .5 1e3 2.5E-2 0x1F 0xFFFFFFFFFFFFFFFF 9223372036854775808"),
+               vec![SQLToken::IntegerLiteral(42), SQLToken::FloatLiteral(3.5), SQLToken::FloatLiteral(0.5),
+                    SQLToken::FloatLiteral(1000.0), SQLToken::FloatLiteral(0.025), SQLToken::IntegerLiteral(31),
+                    SQLToken::IntegerLiteral(-1), SQLToken::FloatLiteral(9223372036854775808.0)]);
+    // A number may not run into a name
+    assert_eq!(kinds("12abc 1e"), vec![SQLToken::Illegal("12abc".to_string()), SQLToken::Illegal("1e".to_string())]);
+}
+
+#[test]
+fn test_blob_literals_need_an_even_number_of_hex_digits() {
+    assert_eq!(kinds("X'00ff' x''"), vec![SQLToken::BlobLiteral(vec![0, 255]), SQLToken::BlobLiteral(Vec::new())]);
+    assert_eq!(kinds("x'abc'"), vec![SQLToken::Illegal("x'abc'".to_string())]);
+    assert_eq!(kinds("x'zz'"), vec![SQLToken::Illegal("x'zz'".to_string())]);
+    // Only x immediately followed by a quote starts a blob
+    assert_eq!(kinds("x 'a'"), vec![identifier("x"), SQLToken::StringLiteral("a".to_string())]);
+}
+
+#[test]
+fn test_operators_take_the_longest_match() {
+    let operators: Vec<Operator> = kinds("= == != <> < <= > >= + - * / % || & | ~ << >> -> ->>").into_iter()
+                                        .map(|token| match token {
+                                            SQLToken::Operator(operator) => operator,
+                                            token => panic!("expected an operator, got {:?}", token)
+                                        })
+                                        .collect();
+    assert_eq!(operators.iter().map(|operator| operator.as_str()).collect::<Vec<&str>>().join(" "),
+               "= == != <> < <= > >= + - * / % || & | ~ << >> -> ->>");
+    assert_eq!(kinds("a>=b"), vec![identifier("a"), SQLToken::Operator(Operator::GreaterOrEqual), identifier("b")]);
+    assert_eq!(kinds("! ?"), vec![SQLToken::Illegal("!".to_string()), SQLToken::Illegal("?".to_string())]);
+}
+
+#[test]
+fn test_quoted_identifiers_lose_their_quotes() {
+    assert_eq!(kinds(r#""first name" [order] `x``y` "a""b" t.c"#),
+               vec![identifier("first name"), identifier("order"), identifier("x`y"), identifier("a\"b"),
+                    identifier("t"), SQLToken::Symbol(Symbol::Dot), identifier("c")]);
+    assert_eq!(kinds("\"SELECT\""), vec![identifier("SELECT")]);
+    assert_eq!(kinds("[open"), vec![SQLToken::Illegal("[open".to_string())]);
+}
+
+#[test]
+fn test_comments_are_tokens_that_tokenize_drops() {
+    let sql = "SELECT -- the names\nname /* all of them */ FROM apples /* open";
+    assert_eq!(kinds(sql)[1], SQLToken::Comment("-- the names".to_string()));
+    assert_eq!(kinds(sql)[3], SQLToken::Comment("/* all of them */".to_string()));
+    assert_eq!(kinds(sql).last(), Some(&SQLToken::Comment("/* open".to_string())));
+    assert_eq!(sql.tokenize(), vec![SQLToken::Keyword("SELECT".to_string()), identifier("name"),
+                                    SQLToken::Keyword("FROM".to_string()), identifier("apples")]);
+    // -1 is a minus and a number, -- is a comment
+    assert_eq!(kinds("-1--1"), vec![SQLToken::Operator(Operator::Minus), SQLToken::IntegerLiteral(1), SQLToken::Comment("--1".to_string())]);
+}
+
+#[test]
+fn test_queries_with_literals_and_comments_run() {
+    let mut file = DBFile::open("./tests/assets/sample.db").unwrap();
+    let rows: Vec<String> = file.execute("SELECT color FROM apples -- by name\nWHERE name = 'Granny Smith' /* one row */;").unwrap()
+                                .iter().map(|row| row.to_string()).collect();
+    assert_eq!(rows, vec!["Light Green"]);
+
+    let rows: Vec<String> = file.execute("SELECT [name] FROM \"apples\" WHERE id>=3").unwrap()
+                                .iter().map(|row| row.to_string()).collect();
+    assert_eq!(rows, vec!["Honeycrisp", "Golden Delicious"]);
+}
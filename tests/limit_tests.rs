@@ -0,0 +1,68 @@
+use sqlite_fsr::command::sql::parser::expr::Expr;
+use sqlite_fsr::command::sql::parser::sql_statement::{Limit, SQLStatement, ToSQLStatement};
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+use sqlite_fsr::models::error::{SQLCommandError, SQLError, SQLSyntaxError};
+
+mod common;
+use common::row_strings;
+
+#[test]
+fn test_limit_and_offset_are_parsed_in_both_forms() {
+    let limit = |sql: &str| -> Option<Limit> {
+        let Ok(SQLStatement::Select(statement)) = sql.to_sql_statment() else { panic!("expected a SELECT: {}", sql) };
+        statement.limit
+    };
+    let integer = |integer: i64| Expr::Literal(Value::Integer(integer));
+    assert_eq!(limit("SELECT name FROM apples"), None);
+    assert_eq!(limit("SELECT name FROM apples LIMIT 10"), Some(Limit { count: integer(10), offset: None }));
+    assert_eq!(limit("SELECT name FROM apples WHERE id > 1 ORDER BY name limit 10 offset 5;"), Some(Limit { count: integer(10), offset: Some(integer(5)) }));
+    // LIMIT m, n skips m rows and returns n
+    assert_eq!(limit("SELECT name FROM apples LIMIT 5, 10"), Some(Limit { count: integer(10), offset: Some(integer(5)) }));
+
+    for sql in ["SELECT name FROM apples LIMIT", "SELECT name FROM apples LIMIT 1 OFFSET", "SELECT name FROM apples LIMIT 1,",
+                "SELECT name FROM apples LIMIT 1 ORDER BY name", "SELECT name FROM apples LIMIT 1 WHERE id = 1", "SELECT name FROM apples OFFSET 1"] {
+        assert!(matches!(sql.to_sql_statment(), Err(SQLSyntaxError::UnexpectedToken(_))), "{}", sql);
+    }
+}
+
+// Expected rows are those sqlite3 returns.
+#[test]
+fn test_limit_and_offset_select_a_window_of_the_rows() {
+    let mut file = DBFile::open("./tests/assets/superheroes.db").unwrap();
+    assert_eq!(row_strings(&mut file, "SELECT id, name FROM superheroes LIMIT 2"), vec!["1 Batman (Bruce Wayne)", "2 Superman (Clark Kent)"]);
+    assert_eq!(row_strings(&mut file, "SELECT id FROM superheroes LIMIT 3 OFFSET 6890"), vec!["6891", "6892", "6893"]);
+    assert_eq!(row_strings(&mut file, "SELECT id FROM superheroes LIMIT 6893, 5"), vec!["6894", "6895"]);
+    assert_eq!(row_strings(&mut file, "SELECT id FROM superheroes WHERE id > 100 LIMIT 2 OFFSET 1"), vec!["102", "103"]);
+    assert!(row_strings(&mut file, "SELECT id FROM superheroes LIMIT 0").is_empty());
+    // A negative count means no limit and a negative offset skips nothing
+    assert_eq!(row_strings(&mut file, "SELECT id FROM superheroes WHERE id < 4 LIMIT -1 OFFSET -5"), vec!["1", "2", "3"]);
+    // The terms are expressions that have to come out as integers
+    assert_eq!(row_strings(&mut file, "SELECT id FROM superheroes LIMIT 1 + 1 OFFSET '2'"), vec!["3", "4"]);
+    assert_eq!(row_strings(&mut file, "SELECT id FROM superheroes LIMIT 2.0"), vec!["1", "2"]);
+
+    // ORDER BY and aggregates see every row; LIMIT applies to what they return
+    assert_eq!(row_strings(&mut file, "SELECT id FROM superheroes ORDER BY name DESC LIMIT 3 OFFSET 2"), vec!["4734", "5694", "1590"]);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM superheroes LIMIT 5"), vec!["6895"]);
+    assert!(row_strings(&mut file, "SELECT COUNT(*) FROM superheroes LIMIT 1 OFFSET 1").is_empty());
+
+    let error = |file: &mut DBFile, sql: &str| file.execute(sql).err().unwrap();
+    assert!(matches!(error(&mut file, "SELECT id FROM superheroes LIMIT 2.5"), SQLError::Command(SQLCommandError::InvalidExpression(_))));
+    assert!(matches!(error(&mut file, "SELECT id FROM superheroes LIMIT 1 OFFSET 'x'"), SQLError::Command(SQLCommandError::InvalidExpression(_))));
+    assert!(matches!(error(&mut file, "SELECT id FROM superheroes LIMIT NULL"), SQLError::Command(SQLCommandError::InvalidExpression(_))));
+    assert!(matches!(error(&mut file, "SELECT id FROM superheroes LIMIT id"), SQLError::Command(SQLCommandError::UnknownColumn(column)) if column == "id"));
+}
+
+#[test]
+fn test_limit_stops_the_scan_early() {
+    let mut file = DBFile::open("./tests/assets/superheroes.db").unwrap();
+    file.pager().reset_cache_stats();
+    assert_eq!(file.execute("SELECT name FROM superheroes LIMIT 2").unwrap().len(), 2);
+    // The schema page, the table's root and its first leaf
+    assert!(file.pager().cache_stats().misses <= 3, "read {} pages", file.pager().cache_stats().misses);
+
+    // Sorting has to read the whole table
+    file.pager().reset_cache_stats();
+    assert_eq!(file.execute("SELECT name FROM superheroes ORDER BY id LIMIT 2").unwrap().len(), 2);
+    assert!(file.pager().cache_stats().misses > 100, "read {} pages", file.pager().cache_stats().misses);
+}
@@ -0,0 +1,112 @@
+use sqlite_fsr::command::sql::parser::expr::Expr;
+use sqlite_fsr::command::sql::parser::sql_statement::{OrderingTerm, SQLStatement, ToSQLStatement};
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+use sqlite_fsr::models::error::{SQLCommandError, SQLError, SQLSyntaxError};
+
+mod common;
+use common::row_strings;
+
+// The rows of tests/assets/affinity.db are described in tests/evaluator_tests.rs.
+// Expected orders are those sqlite3 returns.
+fn ids(file: &mut DBFile, order_by: &str) -> Vec<String> {
+    row_strings(file, &format!("SELECT id FROM things ORDER BY {}", order_by))
+}
+
+#[test]
+fn test_order_by_is_parsed_with_directions_and_nulls() {
+    let Ok(SQLStatement::Select(statement)) = "SELECT name AS n, age FROM people WHERE age > 3 ORDER BY n DESC, 2, lower(city) NULLS LAST;".to_sql_statment() else { panic!("expected a SELECT") };
+    assert_eq!(statement.columns, Some(vec!["name".to_string(), "age".to_string()]));
+    assert_eq!(statement.column_aliases, vec![Some("n".to_string()), None]);
+    let column = |name: &str| Expr::Column { table: None, name: name.to_string() };
+    assert_eq!(statement.order_by, vec![OrderingTerm { expr: column("n"), descending: true, nulls_first: false },
+                                        OrderingTerm { expr: Expr::Literal(Value::Integer(2)), descending: false, nulls_first: true },
+                                        OrderingTerm { expr: Expr::Function { name: "lower".to_string(), arguments: vec![column("city")], distinct: false }, descending: false, nulls_first: false }]);
+
+    let Ok(SQLStatement::Select(statement)) = "SELECT name nickname FROM people ORDER BY age ASC NULLS FIRST".to_sql_statment() else { panic!("expected a SELECT") };
+    assert_eq!(statement.column_aliases, vec![Some("nickname".to_string())]);
+    assert!(statement.order_by[0].nulls_first && !statement.order_by[0].descending);
+
+    for sql in ["SELECT name FROM people ORDER name", "SELECT name FROM people ORDER BY", "SELECT name FROM people ORDER BY age NULLS",
+                "SELECT name FROM people ORDER BY age, ", "SELECT name FROM people ORDER BY age ORDER BY name", "SELECT name FROM people ORDER BY age WHERE age > 1"] {
+        assert!(matches!(sql.to_sql_statment(), Err(SQLSyntaxError::UnexpectedToken(_))), "{}", sql);
+    }
+}
+
+#[test]
+fn test_rows_sort_by_several_keys_in_either_direction() {
+    let mut file = DBFile::open("./tests/assets/sample.db").unwrap();
+    assert_eq!(row_strings(&mut file, "SELECT name FROM apples ORDER BY name"), vec!["Fuji", "Golden Delicious", "Granny Smith", "Honeycrisp"]);
+    assert_eq!(row_strings(&mut file, "SELECT id, name FROM oranges WHERE id > 2 ORDER BY name DESC"),
+               vec!["5 Valencia Orange", "3 Tangerine", "6 Navel Orange", "4 Clementine"]);
+    assert_eq!(row_strings(&mut file, "SELECT name FROM oranges ORDER BY length(description) DESC, name"),
+               vec!["Clementine", "Navel Orange", "Tangerine", "Mandarin", "Valencia Orange", "Tangelo"]);
+
+    let mut file = DBFile::open("./tests/assets/affinity.db").unwrap();
+    assert_eq!(ids(&mut file, "amount NULLS LAST, id DESC"), vec!["5", "3", "4", "1", "2"]);
+    assert_eq!(ids(&mut file, "rowid DESC"), vec!["5", "4", "3", "2", "1"]);
+    assert_eq!(ids(&mut file, "price * -1 NULLS LAST"), vec!["2", "1", "4", "5", "3"]);
+}
+
+#[test]
+fn test_values_of_different_types_sort_null_number_text_blob() {
+    let mut file = DBFile::open("./tests/assets/affinity.db").unwrap();
+    // tag holds 'a', 10, '10', NULL and 2.5
+    assert_eq!(ids(&mut file, "tag"), vec!["4", "5", "2", "3", "1"]);
+    assert_eq!(ids(&mut file, "tag DESC"), vec!["1", "3", "2", "5", "4"]);
+    assert_eq!(ids(&mut file, "tag DESC NULLS FIRST"), vec!["4", "1", "3", "2", "5"]);
+    // data holds x'01', NULL, 'text', x'' and NULL
+    assert_eq!(ids(&mut file, "data, id"), vec!["2", "5", "3", "4", "1"]);
+    // code is text, so '010' < '10' < '9'
+    assert_eq!(ids(&mut file, "code"), vec!["4", "5", "1", "2", "3"]);
+
+    // name is declared COLLATE NOCASE, which an explicit COLLATE overrides
+    assert_eq!(ids(&mut file, "name DESC, id"), vec!["3", "2", "1", "5", "4"]);
+    assert_eq!(ids(&mut file, "name COLLATE BINARY"), vec!["4", "1", "3", "5", "2"]);
+}
+
+#[test]
+fn test_order_by_positions_and_aliases() {
+    let mut file = DBFile::open("./tests/assets/affinity.db").unwrap();
+    assert_eq!(row_strings(&mut file, "SELECT id, amount FROM things ORDER BY 2 DESC, 1"), vec!["1 40", "4 40", "3 15", "5 -3", "2 NULL"]);
+    assert_eq!(row_strings(&mut file, "SELECT id, amount AS a FROM things ORDER BY a, id DESC"), vec!["2 NULL", "5 -3", "3 15", "4 40", "1 40"]);
+    // An alias wins over the column it shadows
+    assert_eq!(row_strings(&mut file, "SELECT id, amount id FROM things WHERE id > 1 ORDER BY id DESC"), vec!["4 40", "3 15", "5 -3", "2 NULL"]);
+    // Positions count the columns SELECT * returns
+    let rows = file.execute("SELECT * FROM things ORDER BY 4, 1 DESC").unwrap();
+    assert_eq!(rows.iter().map(|row| row.row_id).collect::<Vec<i64>>(), vec![2, 5, 3, 4, 1]);
+    // and the columns as the SELECT names them, a column named twice taking two positions
+    assert_eq!(row_strings(&mut file, "SELECT amount, id FROM things ORDER BY 1, 2 DESC"), vec!["2 NULL", "5 -3", "3 15", "4 40", "1 40"]);
+    assert_eq!(row_strings(&mut file, "SELECT id, id FROM things ORDER BY 2 DESC"), vec!["5", "4", "3", "2", "1"]);
+
+    let error = |file: &mut DBFile, sql: &str| file.execute(sql).err().unwrap();
+    assert!(matches!(error(&mut file, "SELECT id, amount FROM things ORDER BY 3"), SQLError::Command(SQLCommandError::InvalidExpression(_))));
+    assert!(matches!(error(&mut file, "SELECT id FROM things ORDER BY 0"), SQLError::Command(SQLCommandError::InvalidExpression(_))));
+    assert!(matches!(error(&mut file, "SELECT id, id FROM things ORDER BY 3"), SQLError::Command(SQLCommandError::InvalidExpression(_))));
+    assert!(matches!(error(&mut file, "SELECT id FROM things ORDER BY colour"), SQLError::Command(SQLCommandError::UnknownColumn(column)) if column == "colour"));
+}
+
+#[test]
+fn test_order_by_keyword_named_columns() {
+    let mut file = DBFile::open("./tests/assets/keywords.db").unwrap();
+    assert_eq!(row_strings(&mut file, "SELECT key FROM settings ORDER BY \"order\""), vec!["font", "theme", "size"]);
+    assert_eq!(row_strings(&mut file, "SELECT replace FROM \"group\" ORDER BY temp DESC"), vec!["x", "z", "y"]);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM \"group\" ORDER BY 1"), vec!["3"]);
+}
+
+// Text sorts by its bytes in the database encoding. Ā (U+0101) is stored as 01 01 in UTF-16le,
+// ahead of every ASCII letter, but comes after them in UTF-8 and UTF-16be.
+#[test]
+fn test_order_by_sorts_text_in_the_database_encoding() {
+    for (path, first, last) in [("./tests/assets/utf16le.db", ["Ānanda", "🍵 tea"], ["émile", "Ωmega"]),
+                                ("./tests/assets/utf16be.db", ["Zoë", "plain"], ["🍵 tea", "Ωmega"])] {
+        let mut file = DBFile::open(path).unwrap();
+        let sorted = row_strings(&mut file, "SELECT name FROM cafés ORDER BY name");
+        assert_eq!(sorted[..2], first, "{}", path);
+        assert_eq!(row_strings(&mut file, "SELECT name FROM cafés ORDER BY name DESC")[..2], last, "{}", path);
+
+        // Runs spilled to temporary files merge in the same order
+        file.set_sort_budget(1024);
+        assert_eq!(row_strings(&mut file, "SELECT name FROM cafés ORDER BY name"), sorted, "{}", path);
+    }
+}
@@ -0,0 +1,47 @@
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+use sqlite_fsr::models::dbfile::dbtable::tablepage::overflow::local_payload_size;
+
+#[test]
+fn test_local_payload_size_keeps_small_payloads_on_page() {
+    assert_eq!(local_payload_size(100, 4096), 100);
+    assert_eq!(local_payload_size(4061, 4096), 4061);
+}
+
+#[test]
+fn test_local_payload_size_splits_large_payloads() {
+    // M = ((4096 - 12) * 32 / 255) - 23 = 489, K = M + ((P - M) % (4096 - 4))
+    // and K is only kept on the page when it fits under X = 4096 - 35.
+    assert_eq!(local_payload_size(20000, 4096), 3632);
+    assert_eq!(local_payload_size(8000, 4096), 3908);
+    assert_eq!(local_payload_size(4062, 4096), 489);
+}
+
+#[test]
+fn test_select_reads_text_spilling_into_overflow_pages() {
+    let mut file = DBFile::open("./tests/assets/overflow.db").unwrap();
+    let results = file.execute("SELECT * FROM documents").unwrap();
+    assert_eq!(results.len(), 40);
+
+    for row in results.iter() {
+        let Value::Integer(id) = row[0] else { panic!("Expected integer id") };
+        let Value::Text(body) = &row[2] else { panic!("Expected text body") };
+        assert_eq!(body.len(), id as usize * 500 + 4);
+        assert!(body.starts_with("aaaa"));
+        assert!(body.ends_with("|end"));
+    }
+}
+
+#[test]
+fn test_select_reads_blob_spanning_overflow_chain() {
+    let mut file = DBFile::open("./tests/assets/overflow.db").unwrap();
+    let results = file.execute("SELECT * FROM documents").unwrap();
+
+    let Value::Blob(attachment) = &results[6][3] else { panic!("Expected blob attachment") };
+    assert_eq!(attachment.len(), 20004);
+    assert_eq!(&attachment[..10], b"0123456789");
+    assert_eq!(&attachment[attachment.len() - 4..], b"tail");
+
+    let Value::Blob(attachment) = &results[39][3] else { panic!("Expected blob attachment") };
+    assert_eq!(attachment.len(), 12000);
+}
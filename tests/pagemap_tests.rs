@@ -0,0 +1,107 @@
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::{PageMap, PageOwner};
+use sqlite_fsr::models::error::DatabaseError;
+
+mod common;
+use common::write_temp_db;
+
+// pagemap.db is an incremental auto-vacuum database with 512-byte pages. Two thirds of its docs
+// were deleted, leaving 224 free pages; page counts below are from sqlite3's dbstat table.
+fn count(page_map: &PageMap, owner: PageOwner) -> usize {
+    page_map.pages_where(|page_owner| *page_owner == owner).len()
+}
+
+#[test]
+fn test_freelist_walks_every_trunk_page() {
+    let mut file = DBFile::open("./tests/assets/pagemap.db").unwrap();
+    let freelist = file.freelist().unwrap();
+    assert_eq!(freelist.trunk_pages, vec![210, 8]);
+    assert_eq!(freelist.leaf_pages.len(), 222);
+    assert_eq!(freelist.page_count(), file.header.freelist_page_count as usize);
+    assert!(freelist.contains(8) && !freelist.contains(1));
+
+    let mut file = DBFile::open("./tests/assets/sample.db").unwrap();
+    assert_eq!(file.freelist().unwrap().page_count(), 0);
+}
+
+#[test]
+fn test_page_map_accounts_for_every_page() {
+    let mut file = DBFile::open("./tests/assets/pagemap.db").unwrap();
+    let page_map = file.page_map().unwrap();
+    assert_eq!(page_map.page_count(), 374);
+
+    assert_eq!(count(&page_map, PageOwner::TableBTree("sqlite_schema".to_string())), 2);
+    assert_eq!(count(&page_map, PageOwner::TableBTree("docs".to_string())), 36);
+    assert_eq!(count(&page_map, PageOwner::Overflow("docs".to_string())), 23);
+    assert_eq!(count(&page_map, PageOwner::IndexBTree("docs_by_body".to_string())), 12);
+    assert_eq!(count(&page_map, PageOwner::Overflow("docs_by_body".to_string())), 61);
+    // The index behind the UNIQUE constraint has no CREATE INDEX statement but still owns pages
+    assert_eq!(count(&page_map, PageOwner::IndexBTree("sqlite_autoindex_docs_1".to_string())), 3);
+    // A WITHOUT ROWID table is stored as an index b-tree
+    assert_eq!(count(&page_map, PageOwner::IndexBTree("tags".to_string())), 9);
+    assert_eq!(count(&page_map, PageOwner::FreelistTrunk), 2);
+    assert_eq!(count(&page_map, PageOwner::FreelistLeaf), 222);
+    assert_eq!(page_map.pages_where(|owner| *owner == PageOwner::PointerMap), vec![2, 105, 208, 311]);
+    assert!(page_map.unused_pages().is_empty());
+
+    assert_eq!(page_map.owner(1), Some(&PageOwner::TableBTree("sqlite_schema".to_string())));
+    assert_eq!(page_map.owner(3), Some(&PageOwner::TableBTree("docs".to_string())));
+    assert_eq!(page_map.owner(210), Some(&PageOwner::FreelistTrunk));
+    assert_eq!(page_map.owner(375), None);
+}
+
+#[test]
+fn test_page_map_of_databases_without_pointer_map() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    let page_map = file.page_map().unwrap();
+    assert!(page_map.pages_where(|owner| *owner == PageOwner::PointerMap).is_empty());
+    assert!(page_map.unused_pages().is_empty());
+    assert_eq!(page_map.owner(86), Some(&PageOwner::IndexBTree("people_by_age".to_string())));
+
+    // header.db is a three-page auto-vacuum database
+    let mut file = DBFile::open("./tests/assets/header.db").unwrap();
+    let page_map = file.page_map().unwrap();
+    assert_eq!(page_map.owner(2), Some(&PageOwner::PointerMap));
+    assert!(page_map.unused_pages().is_empty());
+}
+
+#[test]
+fn test_pointer_map_pages_follow_the_entries_they_hold() {
+    // 512 / 5 = 102 entries per pointer map page
+    assert_eq!(PageMap::pointer_map_pages(400, 512), vec![2, 105, 208, 311]);
+    assert_eq!(PageMap::pointer_map_pages(1, 512), Vec::<u32>::new());
+    assert_eq!(PageMap::pointer_map_pages(2000, 4096), vec![2, 822, 1642]);
+}
+
+#[test]
+fn test_page_claimed_twice_is_reported_as_corrupt() {
+    let mut data = std::fs::read("./tests/assets/pagemap.db").unwrap();
+    // Point the first freelist leaf of trunk page 8 at page 3, the root of docs
+    let leaf_offset = 7 * 512 + 8;
+    data[leaf_offset..leaf_offset + 4].copy_from_slice(&3u32.to_be_bytes());
+    let path = write_temp_db("double-claim", &data);
+
+    let mut file = DBFile::open(&path).unwrap();
+    match file.page_map() {
+        Err(DatabaseError::Corrupt { page, reason, .. }) => {
+            assert_eq!(page, 3);
+            assert_eq!(reason, "page is used by both the freelist leaf and the table b-tree docs");
+        },
+        other => panic!("expected a corrupt page error, got {:?}", other)
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_freelist_cycle_is_reported_as_corrupt() {
+    let mut data = std::fs::read("./tests/assets/pagemap.db").unwrap();
+    // Make the last trunk page point back at the first
+    let trunk_offset = 7 * 512;
+    data[trunk_offset..trunk_offset + 4].copy_from_slice(&210u32.to_be_bytes());
+    let path = write_temp_db("freelist-cycle", &data);
+
+    let mut file = DBFile::open(&path).unwrap();
+    assert!(matches!(file.freelist(), Err(DatabaseError::Corrupt { page: 210, .. })));
+    assert!(file.page_map().is_err());
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,52 @@
+use std::rc::Rc;
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::pager::PageCache;
+
+#[test]
+fn test_pagecache_evicts_least_recently_used_page() {
+    let mut cache = PageCache::new(2);
+    cache.insert(1, Rc::from(vec![1u8]));
+    cache.insert(2, Rc::from(vec![2u8]));
+    cache.get(1);
+    cache.insert(3, Rc::from(vec![3u8]));
+
+    assert_eq!(cache.len(), 2);
+    assert!(cache.contains(1));
+    assert!(!cache.contains(2));
+    assert!(cache.contains(3));
+}
+
+#[test]
+fn test_pagecache_shrinks_when_capacity_lowered() {
+    let mut cache = PageCache::new(4);
+    for page_number in 1..=4 {
+        cache.insert(page_number, Rc::from(vec![page_number as u8]));
+    }
+    cache.set_capacity(1);
+
+    assert_eq!(cache.len(), 1);
+    assert!(cache.contains(4));
+}
+
+#[test]
+fn test_pager_serves_repeated_queries_from_cache() {
+    let mut file = DBFile::open("./tests/assets/superheroes.db").unwrap();
+    file.execute("SELECT COUNT(*) FROM superheroes").unwrap();
+    let first = file.pager().cache_stats();
+    assert!(first.misses > 0);
+
+    file.execute("SELECT COUNT(*) FROM superheroes").unwrap();
+    let second = file.pager().cache_stats();
+    assert_eq!(second.misses, first.misses);
+    assert!(second.hits > first.hits);
+}
+
+#[test]
+fn test_pager_respects_cache_budget() {
+    let mut file = DBFile::open("./tests/assets/superheroes.db").unwrap();
+    file.pager().set_cache_budget(4 * 4096);
+    let result = file.execute("SELECT COUNT(*) FROM superheroes").unwrap();
+
+    assert_eq!(result[0].to_string(), "6895");
+    assert_eq!(file.pager().cache_stats().cached_pages, 4);
+}
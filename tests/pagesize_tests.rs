@@ -0,0 +1,43 @@
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+use sqlite_fsr::models::dbfile::header::DatabaseHeader;
+
+fn assert_reads_every_item(path: &str, expected_page_size: u32) {
+    let mut file = DBFile::open(path).unwrap();
+    let (page_size, _) = file.get_dbinfo();
+    assert_eq!(page_size, expected_page_size);
+
+    let results = file.execute("SELECT * FROM items").unwrap();
+    assert_eq!(results.len(), 600);
+    for (index, row) in results.iter().enumerate() {
+        assert_eq!(row[0], Value::Integer(index as i64 + 1));
+        assert_eq!(row[1].to_string(), format!("item {}", index + 1));
+    }
+}
+
+#[test]
+fn test_decode_page_size_maps_one_to_65536() {
+    assert_eq!(DatabaseHeader::decode_page_size([0x00, 0x01]), 65536);
+    assert_eq!(DatabaseHeader::decode_page_size([0x02, 0x00]), 512);
+    assert_eq!(DatabaseHeader::decode_page_size([0x80, 0x00]), 32768);
+}
+
+#[test]
+fn test_select_reads_multi_level_tree_with_512_byte_pages() {
+    assert_reads_every_item("./tests/assets/pagesize_512.db", 512);
+}
+
+#[test]
+fn test_select_reads_multi_level_tree_with_1024_byte_pages() {
+    assert_reads_every_item("./tests/assets/pagesize_1024.db", 1024);
+}
+
+#[test]
+fn test_select_reads_multi_level_tree_with_8192_byte_pages() {
+    assert_reads_every_item("./tests/assets/pagesize_8192.db", 8192);
+}
+
+#[test]
+fn test_select_reads_multi_level_tree_with_65536_byte_pages() {
+    assert_reads_every_item("./tests/assets/pagesize_65536.db", 65536);
+}
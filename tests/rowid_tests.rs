@@ -0,0 +1,43 @@
+use sqlite_fsr::models::DBFile;
+
+mod common;
+use common::row_strings;
+
+#[test]
+fn test_rowid_point_lookup_uses_integer_primary_key() {
+    let mut file = DBFile::open("./tests/assets/superheroes.db").unwrap();
+    assert_eq!(file.query_plan("SELECT name FROM superheroes WHERE id = 3000").unwrap().to_string(), "SEARCH superheroes USING INTEGER PRIMARY KEY (rowid=?)");
+
+    file.pager().reset_cache_stats();
+    assert_eq!(row_strings(&mut file, "SELECT id, name FROM superheroes WHERE id = 3000"), vec!["3000 Caldwell Dayzl (New Earth)"]);
+    // One page per level of the table b-tree instead of all 307 pages
+    assert!(file.pager().cache_stats().misses < 5, "read {} pages", file.pager().cache_stats().misses);
+
+    assert_eq!(row_strings(&mut file, "SELECT name FROM superheroes WHERE rowid = 1"), vec!["Batman (Bruce Wayne)"]);
+    assert_eq!(row_strings(&mut file, "SELECT name FROM superheroes WHERE oid = 6895"), vec!["William McKinley (New Earth)"]);
+    assert!(file.execute("SELECT name FROM superheroes WHERE id = 6896").unwrap().is_empty());
+    assert!(file.execute("SELECT name FROM superheroes WHERE id = 2.5").unwrap().is_empty());
+}
+
+#[test]
+fn test_rowid_range_scan_reads_only_the_range() {
+    let mut file = DBFile::open("./tests/assets/superheroes.db").unwrap();
+    assert_eq!(file.query_plan("SELECT name FROM superheroes WHERE id BETWEEN 1000 AND 1999").unwrap().to_string(), "SEARCH superheroes USING INTEGER PRIMARY KEY (rowid>=? AND rowid<=?)");
+
+    file.pager().reset_cache_stats();
+    let ids: Vec<i64> = row_strings(&mut file, "SELECT id FROM superheroes WHERE id BETWEEN 1000 AND 1999").iter().map(|id| id.parse().unwrap()).collect();
+    assert_eq!(ids, (1000..2000).collect::<Vec<i64>>());
+    assert!(file.pager().cache_stats().misses < 100, "read {} pages", file.pager().cache_stats().misses);
+
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM superheroes WHERE id > 6890"), vec!["5"]);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM superheroes WHERE rowid < 10.5"), vec!["10"]);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM superheroes WHERE id >= 100 AND id < 100"), vec!["0"]);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM superheroes WHERE id < 'text'"), vec!["6895"]);
+}
+
+#[test]
+fn test_rowid_range_combines_with_other_predicates() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    assert_eq!(file.query_plan("SELECT id FROM people WHERE id >= 1500 AND age = 40").unwrap().to_string(), "SEARCH people USING INTEGER PRIMARY KEY (rowid>=?)");
+    assert_eq!(row_strings(&mut file, "SELECT id FROM people WHERE id >= 1500 AND age = 40"), vec!["1546", "1606", "1666", "1726", "1786", "1846", "1906", "1966"]);
+}
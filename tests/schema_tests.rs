@@ -0,0 +1,106 @@
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+use sqlite_fsr::models::error::DatabaseError;
+
+mod common;
+use common::write_temp_db;
+
+// tests/assets/rootpages.db has 16384-byte pages, so its whole schema fits on page 1. It holds
+// 100 tables t001 .. t100, each with an index on its label, then a filler table taking up pages
+// 202 to 262, then planets and stars, each with an index.
+#[test]
+fn test_schema_decodes_rootpages_past_127() {
+    let file = DBFile::open("./tests/assets/rootpages.db").unwrap();
+    let rootpages: Vec<(String, u32)> = file.schema.to_schema_rows().unwrap()
+                                                   .into_iter()
+                                                   .map(|row| (row.name, row.rootpage))
+                                                   .collect();
+
+    assert_eq!(rootpages.len(), 103);
+    assert_eq!(rootpages[0], ("t001".to_string(), 2));
+    assert_eq!(rootpages[63], ("t064".to_string(), 128));
+    assert_eq!(rootpages[99], ("t100".to_string(), 200));
+    assert_eq!(rootpages[100..], [("filler".to_string(), 202), ("planets".to_string(), 263), ("stars".to_string(), 265)]);
+
+    let index_rootpages: Vec<(String, u32)> = file.schema.to_schema_indexes().unwrap()
+                                                         .into_iter()
+                                                         .map(|index| (index.name, index.rootpage))
+                                                         .collect();
+    assert_eq!(index_rootpages.len(), 102);
+    assert_eq!(index_rootpages[0], ("i001".to_string(), 3));
+    assert_eq!(index_rootpages[63], ("i064".to_string(), 129));
+    assert_eq!(index_rootpages[99], ("i100".to_string(), 201));
+    assert_eq!(index_rootpages[100..], [("planets_by_name".to_string(), 264), ("stars_by_distance".to_string(), 266)]);
+}
+
+#[test]
+fn test_select_reads_table_rooted_past_page_255() {
+    let mut file = DBFile::open("./tests/assets/rootpages.db").unwrap();
+    let results = file.execute("SELECT * FROM planets").unwrap();
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[2][1], "Earth");
+    assert_eq!(results[3][2], Value::Integer(2));
+
+    let results = file.execute("SELECT name FROM stars").unwrap();
+    let names: Vec<String> = results.iter().map(|row| row.to_string()).collect();
+    assert_eq!(names, vec!["Sirius", "Vega"]);
+
+    let results = file.execute("SELECT size FROM t100").unwrap();
+    assert_eq!(results[0][0], Value::Integer(100));
+}
+
+#[test]
+fn test_schema_spanning_multiple_pages_lists_every_table() {
+    let file = DBFile::open("./tests/assets/manytables.db").unwrap();
+    assert_eq!(file.schema.page_header[0], 0x05);
+
+    let table_names = file.get_table_names();
+    assert_eq!(table_names.len(), 300);
+    assert_eq!(table_names[0], "table_001");
+    assert_eq!(table_names[299], "table_300");
+}
+
+#[test]
+fn test_schema_spanning_multiple_pages_decodes_two_byte_rootpages() {
+    let file = DBFile::open("./tests/assets/manytables.db").unwrap();
+    let schema_rows = file.schema.to_schema_rows().unwrap();
+    let max_rootpage = schema_rows.iter().map(|row| row.rootpage).max().unwrap();
+    assert_eq!(max_rootpage, 339);
+}
+
+#[test]
+fn test_select_reads_table_listed_on_later_schema_page() {
+    let mut file = DBFile::open("./tests/assets/manytables.db").unwrap();
+    let results = file.execute("SELECT * FROM table_287").unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0][1], "label 287");
+    assert_eq!(results[0][2], Value::Integer(287));
+}
+
+#[test]
+fn test_index_search_reads_index_rooted_past_page_255() {
+    let mut file = DBFile::open("./tests/assets/rootpages.db").unwrap();
+    assert_eq!(file.query_plan("SELECT id FROM planets WHERE name = 'Earth'").unwrap().to_string(), "SEARCH planets USING INDEX planets_by_name (name=?)");
+    let results = file.execute("SELECT id FROM planets WHERE name = 'Earth'").unwrap();
+    assert_eq!(results[0][0], Value::Integer(3));
+
+    let results = file.execute("SELECT name FROM stars WHERE distance > 10").unwrap();
+    assert_eq!(results[0][0], "Vega");
+
+    assert_eq!(file.query_plan("SELECT size FROM t100 WHERE label = 'label 100'").unwrap().to_string(), "SEARCH t100 USING INDEX i100 (label=?)");
+    let results = file.execute("SELECT size FROM t100 WHERE label = 'label 100'").unwrap();
+    assert_eq!(results[0][0], Value::Integer(100));
+}
+
+#[test]
+fn test_schema_rootpage_outside_the_page_number_range_is_corrupt() {
+    // oranges' rootpage 4 is stored as a one-byte integer right before its sql; 0xFF makes it -1
+    let mut data = std::fs::read("./tests/assets/sample.db").unwrap();
+    let sql_position = data.windows(20).position(|window| window == b"CREATE TABLE oranges").unwrap();
+    assert_eq!(data[sql_position - 1], 4);
+    data[sql_position - 1] = 0xFF;
+    let path = write_temp_db("negative-rootpage", &data);
+
+    assert!(matches!(DBFile::open(&path), Err(DatabaseError::MalformedSchema(message)) if message.contains("oranges")));
+    std::fs::remove_file(path).unwrap();
+}
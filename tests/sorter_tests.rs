@@ -0,0 +1,63 @@
+use sqlite_fsr::command::sql::{SortKey, Sorter};
+use sqlite_fsr::command::sql::evaluator::Collation;
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::{TableRow, Value};
+use sqlite_fsr::models::dbfile::dbtable::tablepage::Record;
+use sqlite_fsr::models::dbfile::header::TextEncoding;
+use sqlite_fsr::utils::varint::{encode_varint, parse_varint};
+
+mod common;
+use common::row_strings;
+
+#[test]
+fn test_varints_and_records_round_trip() {
+    for value in [0, 1, 127, 128, 240, 2287, 16383, 16384, 1 << 35, (1 << 56) - 1, 1 << 56, i64::MAX, -1, i64::MIN] {
+        let bytes = encode_varint(value);
+        assert_eq!(parse_varint(&bytes), (value, bytes.len()), "{}", value);
+    }
+    assert_eq!(encode_varint(-1).len(), 9);
+
+    let values = vec![Value::Null, Value::Integer(0), Value::Integer(1), Value::Integer(-129), Value::Integer(1 << 40), Value::Integer(i64::MIN),
+                      Value::Real(-2.5), Value::Text("héllo".to_string()), Value::Text(String::new()), Value::Blob(vec![0, 255, 7])];
+    assert_eq!(Record::decode_column_values(&Record::encode_column_values(&values), TextEncoding::Utf8).unwrap(), values);
+}
+
+#[test]
+fn test_sorter_spills_runs_and_merges_them_in_order() {
+    let sort_keys = vec![SortKey { collation: Collation::Binary, descending: false, nulls_first: true, text_encoding: TextEncoding::Utf8 },
+                         SortKey { collation: Collation::NoCase, descending: true, nulls_first: false, text_encoding: TextEncoding::Utf8 }];
+    let key_values = |row_id: i64| vec![if row_id % 7 == 0 { Value::Null } else { Value::Integer(row_id % 5) },
+                                        Value::Text(["a", "B", "c"][(row_id % 3) as usize].to_string())];
+    let rows = |budget: usize| -> (usize, Vec<i64>) {
+        let mut sorter = Sorter::new(sort_keys.clone(), budget);
+        for row_id in 0..500 {
+            sorter.push(key_values(row_id), TableRow { row_id, column_values: vec![Value::Text(format!("row {}", row_id))] }).unwrap();
+        }
+        let spilled_runs = sorter.spilled_runs();
+        (spilled_runs, sorter.into_sorted().unwrap().map(|row| row.unwrap().row_id).collect())
+    };
+
+    let (spilled_runs, in_memory) = rows(usize::MAX);
+    assert_eq!(spilled_runs, 0);
+    // Rows with equal keys keep the order they were pushed in
+    let mut expected: Vec<i64> = (0..500).collect();
+    expected.sort_by(|&a, &b| Sorter::compare(&sort_keys, &key_values(a), &key_values(b)));
+    assert_eq!(in_memory, expected);
+
+    // A few rows per run, and more runs than are merged at once
+    let (spilled_runs, merged) = rows(1000);
+    assert!(spilled_runs > 64, "{}", spilled_runs);
+    assert_eq!(merged, expected);
+}
+
+#[test]
+fn test_order_by_past_the_sort_budget_reads_back_from_temporary_files() {
+    let sql = "SELECT id, name, appearance_count FROM superheroes WHERE id > 10 ORDER BY eye_color DESC, appearance_count, name";
+    let mut file = DBFile::open("./tests/assets/superheroes.db").unwrap();
+    let expected = row_strings(&mut file, sql);
+    assert_eq!(expected.len(), 6885);
+
+    file.set_sort_budget(16 * 1024);
+    assert_eq!(row_strings(&mut file, sql), expected);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM superheroes ORDER BY 1"), vec!["6895"]);
+}
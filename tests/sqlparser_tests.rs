@@ -1,4 +1,5 @@
 use sqlite_fsr::command::sql::parser::{sql_statement::{SQLStatement, ToSQLStatement, AggregatorFunction}, sql_token::{ Symbol, ToSQLToken, Tokenize}, SQLToken};
+use sqlite_fsr::models::dbfile::dbtable::Value;
 
 #[test]
 fn test_ToSQLToken_converts_string_to_token_correctly() {
@@ -218,4 +219,14 @@ fn test_TOSQLStatement_detects_INTEGER_PRIMARY_KEY_column() {
         SQLStatement::CreateTable(statement) => assert!(statement.integer_primary_key_column.is_some()),
         _ => panic!("Expected CreateTable statement")
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_TOSQLStatement_reads_literal_DEFAULT_values() {
+    let string = "CREATE TABLE t (a DEFAULT -1.5, b DEFAULT +2, c DEFAULT X'CAFE', d DEFAULT 'it''s', e DEFAULT false, f DEFAULT NULL, g)";
+    let result = string.to_sql_statment().unwrap();
+    match result {
+        SQLStatement::CreateTable(statement) => assert_eq!(statement.column_defaults, vec![Value::Real(-1.5), Value::Integer(2), Value::Blob(vec![0xCA, 0xFE]),
+                                                                                           Value::Text("it's".to_string()), Value::Integer(0), Value::Null, Value::Null]),
+        _ => panic!("Expected CreateTable statement")
+    }
+}
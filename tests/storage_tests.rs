@@ -0,0 +1,78 @@
+use std::io::Cursor;
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::storage::{ReaderStorage, Storage};
+use sqlite_fsr::models::error::DatabaseError;
+
+mod common;
+use common::row_strings;
+
+static SAMPLE_DB: &[u8] = include_bytes!("assets/sample.db");
+
+#[test]
+fn test_database_from_bytes_reads_like_the_file() {
+    let sql = "SELECT name FROM people WHERE age = 40";
+    let mut opened = DBFile::open("./tests/assets/indexed.db").unwrap();
+    let mut in_memory = DBFile::from_bytes(std::fs::read("./tests/assets/indexed.db").unwrap()).unwrap();
+
+    assert_eq!(in_memory.get_table_names(), opened.get_table_names());
+    assert_eq!(in_memory.pager().page_count, opened.pager().page_count);
+    assert_eq!(row_strings(&mut in_memory, sql), row_strings(&mut opened, sql));
+    assert!(in_memory.integrity_check().unwrap().is_empty());
+}
+
+#[test]
+fn test_database_from_embedded_bytes() {
+    let mut file = DBFile::from_bytes(SAMPLE_DB).unwrap();
+    assert_eq!(file.get_dbinfo(), (4096, 3));
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM apples"), vec!["4"]);
+}
+
+#[test]
+fn test_database_from_any_seekable_reader() {
+    let mut file = DBFile::from_storage(ReaderStorage::new(Cursor::new(SAMPLE_DB))).unwrap();
+    assert_eq!(row_strings(&mut file, "SELECT name FROM apples WHERE id = 2"), vec!["Fuji"]);
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_memory_mapped_database_reads_like_the_file() {
+    let sql = "SELECT COUNT(*) FROM codes";
+    let mut mapped = DBFile::open_mmap("./tests/assets/withoutrowid.db").unwrap();
+    let mut opened = DBFile::open("./tests/assets/withoutrowid.db").unwrap();
+    assert_eq!(row_strings(&mut mapped, sql), row_strings(&mut opened, sql));
+
+    // The WAL next to a mapped file is still found
+    let mut mapped = DBFile::open_mmap("./tests/assets/wal.db").unwrap();
+    assert_eq!(row_strings(&mut mapped, "SELECT COUNT(*) FROM fruits"), vec!["503"]);
+}
+
+#[test]
+fn test_in_memory_database_in_wal_mode_is_read_as_of_its_last_checkpoint() {
+    // There is no path to find the WAL from
+    let mut file = DBFile::from_bytes(std::fs::read("./tests/assets/wal.db").unwrap()).unwrap();
+    assert!(file.pager().wal.is_none());
+    assert_eq!(file.get_table_names(), vec!["fruits"]);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM fruits"), vec!["3"]);
+}
+
+#[test]
+fn test_short_or_truncated_buffers_are_rejected() {
+    assert!(matches!(DBFile::from_bytes(&SAMPLE_DB[..50]), Err(DatabaseError::NotADatabase)));
+
+    // Only the schema page survives; the tables' pages are past the end of the buffer
+    let mut file = DBFile::from_bytes(SAMPLE_DB[..4096].to_vec()).unwrap();
+    assert_eq!(file.pager().page_count, 1);
+    assert!(matches!(file.pager().get_page(2), Err(DatabaseError::Corrupt { page: 2, .. })));
+    assert!(file.execute("SELECT * FROM apples").is_err());
+}
+
+#[test]
+fn test_slices_read_at_offsets() {
+    let mut bytes: &[u8] = &[1, 2, 3, 4, 5];
+    let mut buffer = [0u8; 2];
+    assert_eq!(bytes.size().unwrap(), 5);
+    bytes.read_exact_at(3, &mut buffer).unwrap();
+    assert_eq!(buffer, [4, 5]);
+    let error = bytes.read_exact_at(4, &mut buffer).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
@@ -1,4 +1,5 @@
 use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
 
 #[test]
 fn test_tablerow_index_operator_access() {
@@ -24,7 +25,7 @@ fn test_tablerow_with_all_columns() {
     
     // Test accessing id column (first column)
     let first_row = &results[0];
-    assert_eq!(first_row[0], "1");
+    assert_eq!(first_row[0], Value::Integer(1));
     assert_eq!(first_row[1], "Granny Smith");
     assert_eq!(first_row[2], "Light Green");
 }
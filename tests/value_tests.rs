@@ -0,0 +1,79 @@
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+
+#[test]
+fn test_value_decodes_integer_serial_types() {
+    assert_eq!(Value::from_serial_type(1, &[0xF9]), Value::Integer(-7));
+    assert_eq!(Value::from_serial_type(2, &[0x7F, 0xFF]), Value::Integer(32767));
+    assert_eq!(Value::from_serial_type(3, &[0x7F, 0xFF, 0xFF]), Value::Integer(8388607));
+    assert_eq!(Value::from_serial_type(4, &[0xFF, 0xFF, 0xFC, 0x18]), Value::Integer(-1000));
+    assert_eq!(Value::from_serial_type(5, &[0x7F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]), Value::Integer(140737488355327));
+    assert_eq!(Value::from_serial_type(6, &i64::MIN.to_be_bytes()), Value::Integer(i64::MIN));
+}
+
+#[test]
+fn test_value_decodes_constant_and_variable_length_serial_types() {
+    assert_eq!(Value::from_serial_type(0, &[]), Value::Null);
+    assert_eq!(Value::from_serial_type(7, &1.5f64.to_be_bytes()), Value::Real(1.5));
+    assert_eq!(Value::from_serial_type(8, &[]), Value::Integer(0));
+    assert_eq!(Value::from_serial_type(9, &[]), Value::Integer(1));
+    assert_eq!(Value::from_serial_type(16, &[0x00, 0xFF]), Value::Blob(vec![0x00, 0xFF]));
+    assert_eq!(Value::from_serial_type(19, b"one"), Value::Text("one".to_string()));
+    assert_eq!(Value::serial_type_size(5), 6);
+    assert_eq!(Value::serial_type_size(19), 3);
+}
+
+#[test]
+fn test_select_returns_typed_values() {
+    let mut file = DBFile::open("./tests/assets/types.db").unwrap();
+    let results = file.execute("SELECT * FROM measurements").unwrap();
+
+    let negatives = &results[2];
+    assert_eq!(negatives[0], Value::Integer(3));
+    assert_eq!(negatives[1], "negatives");
+    assert_eq!(negatives[2], Value::Integer(-7));
+    assert_eq!(negatives[3], Value::Integer(-1000));
+    assert_eq!(negatives[4], Value::Integer(-3000000000));
+    assert_eq!(negatives[5], Value::Real(-2.25));
+    assert_eq!(negatives[6], Value::Integer(0));
+    assert_eq!(negatives[7], Value::Blob(vec![0xDE, 0xAD, 0xBE, 0xEF]));
+    assert_eq!(negatives[8], Value::Null);
+
+    let wide = &results[3];
+    assert_eq!(wide[4], Value::Integer(i64::MAX));
+    assert_eq!(wide[6], Value::Integer(1));
+    assert_eq!(wide[7], Value::Null);
+}
+
+#[test]
+fn test_select_keeps_trailing_null_and_empty_values() {
+    let mut file = DBFile::open("./tests/assets/types.db").unwrap();
+    let results = file.execute("SELECT * FROM measurements").unwrap();
+
+    assert_eq!(results[0].column_values.len(), 9);
+    assert_eq!(results[0][7], Value::Blob(vec![]));
+    assert_eq!(results[0][8], Value::Null);
+    assert_eq!(results[4][8], "");
+    assert_eq!(results[4][5], Value::Real(1e100));
+}
+
+// addcolumn.db has columns added with ALTER TABLE ADD COLUMN ... DEFAULT after its first two rows were written.
+#[test]
+fn test_select_reads_columns_missing_from_a_row_as_their_default() {
+    let mut file = DBFile::open("./tests/assets/addcolumn.db").unwrap();
+    let results = file.execute("SELECT * FROM t").unwrap();
+
+    let first = &results[0];
+    assert_eq!(first.column_values.len(), 9);
+    assert_eq!(first[1], "xylophone");
+    assert_eq!(first[2], Value::Integer(7));
+    assert_eq!(first[3], "none");
+    assert_eq!(first[6], Value::Integer(-3));
+    assert_eq!(first[7], Value::Integer(1));
+    assert_eq!(first[8], "unquoted");
+
+    let third = &results[2];
+    assert_eq!(third[2], Value::Integer(8));
+    assert_eq!(third[3], "some");
+    assert_eq!(third[8], "w");
+}
@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::wal::{Wal, WAL_FRAME_HEADER_SIZE, WAL_HEADER_SIZE};
+
+mod common;
+use common::{row_strings, write_temp_db};
+
+// wal.db was copied while its WAL held three committed transactions on top of the main file
+// (500 more fruits, an update of fruit 1, a new baskets table) and an unfinished transaction
+// that had already spilled a frame into the WAL.
+const FRAME_SIZE: usize = WAL_FRAME_HEADER_SIZE + 1024;
+
+// Copies wal.db to a temporary location, with the WAL passed through `edit_wal` (or left out).
+fn copy_wal_db(name: &str, edit_wal: Option<fn(&mut Vec<u8>)>) -> PathBuf {
+    let path = write_temp_db(&format!("wal-{}", name), &std::fs::read("./tests/assets/wal.db").unwrap());
+    let wal_path = Wal::path_for(&path);
+    let _ = std::fs::remove_file(&wal_path);
+    if let Some(edit_wal) = edit_wal {
+        let mut wal = std::fs::read("./tests/assets/wal.db-wal").unwrap();
+        edit_wal(&mut wal);
+        std::fs::write(&wal_path, wal).unwrap();
+    }
+    path
+}
+
+fn remove_temp_db(path: PathBuf) {
+    let _ = std::fs::remove_file(Wal::path_for(&path));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_wal_frames_are_indexed_up_to_the_last_commit() {
+    let mut file = DBFile::open("./tests/assets/wal.db").unwrap();
+    let wal = file.pager().wal.as_ref().unwrap();
+    assert_eq!(wal.header.as_ref().unwrap().page_size, 1024);
+    // Frame 19 is valid but belongs to the unfinished transaction, the frames after it to an older WAL
+    assert_eq!(wal.frame_count, 18);
+    assert_eq!(wal.database_size, Some(15));
+    assert_eq!(wal.frame_for_page(1), Some(16));
+    assert_eq!(wal.frame_for_page(14), Some(14));
+    assert_eq!(wal.frame_for_page(15), Some(18));
+    assert_eq!(wal.frame_for_page(16), None);
+
+    // The database has grown past the two pages of the main file, and the header comes from the WAL
+    assert_eq!(file.pager().page_count, 15);
+    assert_eq!(file.header.database_size, 15);
+}
+
+#[test]
+fn test_select_reads_committed_pages_from_the_wal() {
+    let mut file = DBFile::open("./tests/assets/wal.db").unwrap();
+    assert_eq!(file.get_table_names(), vec!["fruits", "baskets"]);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM fruits"), vec!["503"]);
+    assert_eq!(row_strings(&mut file, "SELECT name FROM fruits WHERE id <= 3"), vec!["apricot", "banana", "cherry"]);
+    assert_eq!(row_strings(&mut file, "SELECT fruit_id FROM baskets"), vec!["1", "2"]);
+    // Rows from the unfinished transaction stay invisible
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM fruits WHERE colour = 'blue'"), vec!["0"]);
+}
+
+#[test]
+fn test_database_without_wal_file_reads_the_main_file() {
+    let path = copy_wal_db("missing", None);
+    let mut file = DBFile::open(&path).unwrap();
+    assert!(file.pager().wal.is_none());
+    assert_eq!(file.get_table_names(), vec!["fruits"]);
+    assert_eq!(row_strings(&mut file, "SELECT name FROM fruits"), vec!["apple", "banana", "cherry"]);
+    remove_temp_db(path);
+}
+
+#[test]
+fn test_wal_with_invalid_header_is_ignored() {
+    let path = copy_wal_db("bad-header", Some(|wal| wal[0] ^= 0xFF));
+    let mut file = DBFile::open(&path).unwrap();
+    assert_eq!(file.pager().wal.as_ref().unwrap().frame_count, 0);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM fruits"), vec!["3"]);
+    remove_temp_db(path);
+
+    // A header checksum mismatch is just as fatal
+    let path = copy_wal_db("bad-header-checksum", Some(|wal| wal[12] ^= 0x01));
+    let mut file = DBFile::open(&path).unwrap();
+    assert!(file.pager().wal.as_ref().unwrap().header.is_none());
+    assert_eq!(file.get_table_names(), vec!["fruits"]);
+    remove_temp_db(path);
+}
+
+#[test]
+fn test_wal_stops_at_first_frame_with_bad_checksum() {
+    // Damage the page image in frame 16, the first frame of the transaction creating baskets
+    let path = copy_wal_db("bad-frame", Some(|wal| wal[WAL_HEADER_SIZE + 15 * FRAME_SIZE + WAL_FRAME_HEADER_SIZE + 500] ^= 0x01));
+    let mut file = DBFile::open(&path).unwrap();
+    assert_eq!(file.pager().wal.as_ref().unwrap().frame_count, 15);
+    assert_eq!(file.get_table_names(), vec!["fruits"]);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM fruits"), vec!["503"]);
+    assert_eq!(row_strings(&mut file, "SELECT name FROM fruits WHERE id = 1"), vec!["apricot"]);
+    remove_temp_db(path);
+}
+
+#[test]
+fn test_wal_ignores_frames_with_other_salt_and_torn_writes() {
+    // A frame with a salt from another WAL generation ends the log, even if it would commit
+    let path = copy_wal_db("bad-salt", Some(|wal| wal[WAL_HEADER_SIZE + 13 * FRAME_SIZE + 8] ^= 0x01));
+    let mut file = DBFile::open(&path).unwrap();
+    assert_eq!(file.pager().wal.as_ref().unwrap().frame_count, 0);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM fruits"), vec!["3"]);
+    remove_temp_db(path);
+
+    // A frame cut short by a crash is not read: baskets was created in frame 17, filled in frame 18
+    let path = copy_wal_db("torn", Some(|wal| wal.truncate(WAL_HEADER_SIZE + 17 * FRAME_SIZE + 100)));
+    let mut file = DBFile::open(&path).unwrap();
+    assert_eq!(file.pager().wal.as_ref().unwrap().frame_count, 17);
+    assert_eq!(file.get_table_names(), vec!["fruits", "baskets"]);
+    assert!(file.execute("SELECT fruit_id FROM baskets").unwrap().is_empty());
+    remove_temp_db(path);
+}
@@ -0,0 +1,111 @@
+use sqlite_fsr::command::sql::{Constraint, QueryPlan};
+use sqlite_fsr::command::sql::parser::expr::{BinaryOperator, Expr};
+use sqlite_fsr::command::sql::parser::sql_statement::{SQLStatement, ToSQLStatement};
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+use sqlite_fsr::models::error::{SQLCommandError, SQLError, SQLSyntaxError};
+
+mod common;
+use common::row_strings;
+
+#[test]
+fn test_where_clause_is_parsed_into_an_expression() {
+    let Ok(SQLStatement::Select(statement)) = "SELECT name FROM people WHERE city = 'New York';".to_sql_statment() else { panic!("expected a SELECT") };
+    let expected = Expr::Binary { left: Box::new(Expr::Column { table: None, name: "city".to_string() }),
+                                  operator: BinaryOperator::Equals,
+                                  right: Box::new(Expr::Literal(Value::Text("New York".to_string()))) };
+    assert_eq!(statement.where_clause, Some(expected));
+
+    let constraints = |sql: &str| -> Vec<(String, &'static str, Value)> {
+        let Ok(SQLStatement::Select(statement)) = sql.to_sql_statment() else { panic!("expected a SELECT") };
+        Constraint::from_where_clause(statement.where_clause.as_ref()).into_iter()
+                                                                      .map(|constraint| (constraint.column, constraint.operator.as_str(), constraint.value))
+                                                                      .collect()
+    };
+    assert_eq!(constraints("SELECT name FROM people WHERE age >= -2.5"), vec![("age".to_string(), ">=", Value::Real(-2.5))]);
+    assert_eq!(constraints("SELECT name FROM people WHERE age BETWEEN 30 AND 40 AND 'Oslo' == city"),
+               vec![("age".to_string(), ">=", Value::Integer(30)), ("age".to_string(), "<=", Value::Integer(40)), ("city".to_string(), "=", Value::Text("Oslo".to_string()))]);
+    // Only terms ANDed at the top can narrow the rows read
+    assert_eq!(constraints("SELECT name FROM people WHERE age > 3 OR city = 'Oslo'"), vec![]);
+    assert_eq!(constraints("SELECT name FROM people WHERE 40 > age AND (city = 'Oslo' OR city = 'Rome')"), vec![("age".to_string(), "<", Value::Integer(40))]);
+
+    assert!(matches!("SELECT name FROM people WHERE age ~ 3".to_sql_statment(), Err(SQLSyntaxError::UnexpectedToken(_))));
+    assert!(matches!("SELECT name FROM people WHERE age BETWEEN 3".to_sql_statment(), Err(SQLSyntaxError::UnexpectedToken(_))));
+    assert!(matches!("SELECT name FROM people WHERE".to_sql_statment(), Err(SQLSyntaxError::UnexpectedToken(_))));
+}
+
+#[test]
+fn test_where_clause_filters_a_table_scan() {
+    let mut file = DBFile::open("./tests/assets/sample.db").unwrap();
+    assert_eq!(row_strings(&mut file, "SELECT name FROM apples WHERE color = 'Red'"), vec!["Fuji"]);
+    assert_eq!(row_strings(&mut file, "SELECT id, name FROM apples WHERE id >= 3"), vec!["3 Honeycrisp", "4 Golden Delicious"]);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM apples WHERE rowid != 2"), vec!["3"]);
+    assert_eq!(file.query_plan("SELECT name FROM apples WHERE color = 'Red'").unwrap().to_string(), "SCAN apples");
+}
+
+#[test]
+fn test_where_clause_on_unknown_column_fails() {
+    let mut file = DBFile::open("./tests/assets/sample.db").unwrap();
+    match file.execute("SELECT name FROM apples WHERE flavour = 'sweet'") {
+        Err(SQLError::Command(SQLCommandError::UnknownColumn(column))) => assert_eq!(column, "flavour"),
+        _ => panic!("expected an unknown column error")
+    }
+    // An unquoted word is a column name, wherever it appears
+    match file.execute("SELECT name FROM apples WHERE color = red OR oranges.id = 1") {
+        Err(SQLError::Command(SQLCommandError::UnknownColumn(column))) => assert_eq!(column, "red"),
+        _ => panic!("expected an unknown column error")
+    }
+    match file.execute("SELECT name FROM apples WHERE oranges.id = 1") {
+        Err(SQLError::Command(SQLCommandError::UnknownColumn(column))) => assert_eq!(column, "oranges.id"),
+        _ => panic!("expected an unknown column error")
+    }
+    assert_eq!(row_strings(&mut file, "SELECT name FROM apples WHERE Apples.id = 2"), vec!["Fuji"]);
+}
+
+#[test]
+fn test_query_plan_uses_index_on_leading_column() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    assert_eq!(file.query_plan("SELECT id FROM people WHERE age = 40").unwrap().to_string(), "SEARCH people USING INDEX people_by_age (age=?)");
+    assert_eq!(file.query_plan("SELECT id FROM people WHERE age > 40").unwrap().to_string(), "SEARCH people USING INDEX people_by_age (age>?)");
+    assert_eq!(file.query_plan("SELECT id FROM people WHERE city = 'Oslo'").unwrap().to_string(), "SEARCH people USING INDEX people_by_city_name (city=?)");
+    assert_eq!(file.query_plan("SELECT id FROM people WHERE bio = 'bio 5'").unwrap().to_string(), "SEARCH people USING INDEX people_by_bio (bio=?)");
+
+    assert_eq!(file.query_plan("SELECT id FROM people WHERE city = 'Oslo' AND name = 'person 0004'").unwrap().to_string(), "SEARCH people USING INDEX people_by_city_name (city=? AND name=?)");
+    assert_eq!(file.query_plan("SELECT id FROM people WHERE age BETWEEN 30 AND 35").unwrap().to_string(), "SEARCH people USING INDEX people_by_age (age>=? AND age<=?)");
+
+    // Not a leading column, a != predicate, and a column only covered by a partial index
+    assert_eq!(file.query_plan("SELECT id FROM people WHERE age != 40").unwrap().to_string(), "SCAN people");
+    assert_eq!(file.query_plan("SELECT id FROM people WHERE name = 'person 0010'").unwrap().to_string(), "SCAN people");
+    assert!(matches!(file.query_plan("SELECT id FROM people").unwrap(), QueryPlan::Scan { .. }));
+}
+
+#[test]
+fn test_indexed_where_returns_the_same_rows_as_sqlite() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+
+    let mut forties = row_strings(&mut file, "SELECT id FROM people WHERE age = 40");
+    forties.sort_by_key(|id| id.parse::<i64>().unwrap());
+    assert_eq!(forties, (0..33).map(|n| (46 + n * 60).to_string()).collect::<Vec<String>>());
+
+    assert_eq!(file.execute("SELECT id FROM people WHERE age > 70").unwrap().len(), 232);
+    assert_eq!(file.execute("SELECT id FROM people WHERE age <= 20").unwrap().len(), 100);
+    assert_eq!(file.execute("SELECT id FROM people WHERE city = 'Oslo'").unwrap().len(), 400);
+    assert_eq!(row_strings(&mut file, "SELECT id FROM people WHERE bio = 'bio 5'"), vec!["5"]);
+    assert!(file.execute("SELECT id FROM people WHERE age = 12").unwrap().is_empty());
+    assert_eq!(file.execute("SELECT id FROM people WHERE age BETWEEN 30 AND 35").unwrap().len(), 200);
+    assert_eq!(file.execute("SELECT id FROM people WHERE age > 30 AND age < 33").unwrap().len(), 67);
+    assert_eq!(file.execute("SELECT id FROM people WHERE age >= 70 AND city = 'Lagos'").unwrap().len(), 66);
+    assert_eq!(row_strings(&mut file, "SELECT id FROM people WHERE city = 'Oslo' AND name = 'person 0004'"), vec!["716"]);
+}
+
+#[test]
+fn test_indexed_point_lookup_reads_few_pages() {
+    let mut file = DBFile::open("./tests/assets/indexed.db").unwrap();
+    file.pager().reset_cache_stats();
+    let results = file.execute("SELECT name, age FROM people WHERE bio = 'bio 5'").unwrap();
+    assert_eq!(results[0].to_string(), "person 1595 23");
+
+    // A scan would have to read all 84 pages of the table
+    let stats = file.pager().cache_stats();
+    assert!(stats.misses < 10, "read {} pages", stats.misses);
+}
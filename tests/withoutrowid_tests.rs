@@ -0,0 +1,99 @@
+use sqlite_fsr::command::sql::parser::sql_statement::{SQLStatement, ToSQLStatement};
+use sqlite_fsr::models::DBFile;
+use sqlite_fsr::models::dbfile::dbtable::Value;
+use sqlite_fsr::models::error::{SQLCommandError, SQLError};
+
+mod common;
+use common::row_strings;
+
+#[test]
+fn test_create_table_statement_detects_without_rowid_and_primary_key() {
+    let Ok(SQLStatement::CreateTable(statement)) = "CREATE TABLE codes (country text, year int, label text, amount decimal(10, 2), PRIMARY KEY (country, year DESC)) WITHOUT ROWID".to_sql_statment() else { panic!("expected a CREATE TABLE") };
+    assert_eq!(statement.columns, vec!["country", "year", "label", "amount"]);
+    assert!(statement.without_rowid);
+    let primary_key: Vec<(&str, bool)> = statement.primary_key.iter().map(|column| (column.name.as_str(), column.descending)).collect();
+    assert_eq!(primary_key, vec![("country", false), ("year", true)]);
+    assert!(statement.integer_primary_key_column.is_none());
+
+    // An INTEGER PRIMARY KEY is only a rowid alias in a table that has a rowid
+    let Ok(SQLStatement::CreateTable(statement)) = "CREATE TABLE IF NOT EXISTS ids (id INTEGER PRIMARY KEY, name TEXT) WITHOUT ROWID".to_sql_statment() else { panic!("expected a CREATE TABLE") };
+    assert_eq!(statement.table_name, "ids");
+    assert!(statement.without_rowid);
+    assert!(statement.integer_primary_key_column.is_none());
+
+    let Ok(SQLStatement::CreateTable(statement)) = "CREATE TABLE ids (name TEXT, id INTEGER, PRIMARY KEY (id))".to_sql_statment() else { panic!("expected a CREATE TABLE") };
+    assert!(!statement.without_rowid);
+    assert_eq!(statement.integer_primary_key_column, Some(1));
+}
+
+#[test]
+fn test_select_scans_without_rowid_table_in_primary_key_order() {
+    let mut file = DBFile::open("./tests/assets/withoutrowid.db").unwrap();
+    assert_eq!(file.pager().get_page(2).unwrap()[0], 0x02);
+
+    let rows = file.execute("SELECT * FROM codes").unwrap();
+    assert_eq!(rows.len(), 1200);
+    assert_eq!(rows[0].to_string(), "C00 2019 label 1160 6.25");
+    assert_eq!(rows[1].to_string(), "C00 2018 label 1120 0");
+
+    // Countries ascend, years within a country descend
+    let keys: Vec<(String, i64)> = rows.iter()
+                                       .map(|row| match (&row[0], &row[1]) {
+                                           (Value::Text(country), Value::Integer(year)) => (country.clone(), -year),
+                                           _ => panic!("unexpected row {}", row)
+                                       })
+                                       .collect();
+    let mut sorted_keys = keys.clone();
+    sorted_keys.sort();
+    assert_eq!(keys, sorted_keys);
+
+    // Labels long enough to spill onto overflow pages
+    assert_eq!(rows.iter().filter(|row| matches!(&row[2], Value::Text(label) if label.len() > 400)).count(), 8);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM codes"), vec!["1200"]);
+}
+
+#[test]
+fn test_without_rowid_rows_are_returned_in_table_column_order() {
+    let mut file = DBFile::open("./tests/assets/withoutrowid.db").unwrap();
+
+    // The primary key is stored first, and rows from before ADD COLUMN lack the new column
+    assert_eq!(row_strings(&mut file, "SELECT * FROM colours"), vec!["black #000000 darkest", "blue #0000ff NULL", "green #00ff00 NULL", "red #ff0000 NULL"]);
+    assert_eq!(row_strings(&mut file, "SELECT hex, name FROM colours WHERE name = 'green'"), vec!["green #00ff00"]);
+
+    // Rowid tables in the same file are unaffected
+    assert_eq!(row_strings(&mut file, "SELECT * FROM notes"), vec!["1 first", "2 second"]);
+}
+
+#[test]
+fn test_where_clause_searches_the_primary_key() {
+    let mut file = DBFile::open("./tests/assets/withoutrowid.db").unwrap();
+    assert_eq!(file.query_plan("SELECT * FROM codes WHERE country = 'C05'").unwrap().to_string(), "SEARCH codes USING PRIMARY KEY (country=?)");
+    assert_eq!(file.query_plan("SELECT * FROM codes WHERE country > 'C05'").unwrap().to_string(), "SEARCH codes USING PRIMARY KEY (country>?)");
+    assert_eq!(file.query_plan("SELECT * FROM codes WHERE year = 2000").unwrap().to_string(), "SCAN codes");
+    // Secondary indexes of a WITHOUT ROWID table are not used
+    assert_eq!(file.query_plan("SELECT * FROM codes WHERE label = 'label 5'").unwrap().to_string(), "SCAN codes");
+
+    let labels = row_strings(&mut file, "SELECT label FROM codes WHERE country = 'C05' AND year > 2010");
+    assert_eq!(labels, (0..9).map(|n| format!("label {}", 1165 - n * 40)).collect::<Vec<String>>());
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM codes WHERE country > 'C05'"), vec!["1020"]);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM codes WHERE country >= 'C10' AND country < 'C12'"), vec!["60"]);
+    assert_eq!(row_strings(&mut file, "SELECT year FROM codes WHERE country = 'C00' AND year = 2000"), vec!["2000"]);
+    assert_eq!(row_strings(&mut file, "SELECT COUNT(*) FROM codes WHERE amount > 5"), vec!["342"]);
+    assert!(file.execute("SELECT * FROM codes WHERE country = 'XX'").unwrap().is_empty());
+}
+
+#[test]
+fn test_without_rowid_table_has_no_rowid_column() {
+    let mut file = DBFile::open("./tests/assets/withoutrowid.db").unwrap();
+    match file.execute("SELECT name FROM colours WHERE rowid = 1") {
+        Err(SQLError::Command(SQLCommandError::UnknownColumn(column))) => assert_eq!(column, "rowid"),
+        _ => panic!("expected an unknown column error")
+    }
+}
+
+// addcolumn.db's k got its flag column from ALTER TABLE ADD COLUMN ... DEFAULT 1 after p and q were written.
+#[test]
+fn test_without_rowid_rows_read_columns_they_lack_as_their_default() {
+    let mut file = DBFile::open("./tests/assets/addcolumn.db").unwrap();
+    assert_eq!(row_strings(&mut file, "SELECT * FROM k"), vec!["p 1 1", "q 2 1", "r 3 0"]);
+}